@@ -9,6 +9,9 @@ use std::{
 use anyhow::Context;
 use lazy_static::lazy_static;
 use tree_sitter::{Language, Parser, Query};
+use tree_sitter_cli::benchmark::{
+    find_corpus_path, find_grammar_paths, largest_corpus_example, measure_throughput, Throughput,
+};
 use tree_sitter_loader::{CompileConfig, Loader};
 
 include!("../src/tests/helpers/dirs.rs");
@@ -21,52 +24,42 @@ lazy_static! {
     static ref REPETITION_COUNT: usize = env::var("TREE_SITTER_BENCHMARK_REPETITION_COUNT")
         .map(|s| s.parse::<usize>().unwrap())
         .unwrap_or(5);
+    static ref THROUGHPUT_MODE: bool = env::var("TREE_SITTER_BENCHMARK_THROUGHPUT").is_ok();
     static ref TEST_LOADER: Loader = Loader::with_parser_lib_path(SCRATCH_DIR.clone());
     static ref EXAMPLE_AND_QUERY_PATHS_BY_LANGUAGE_DIR: BTreeMap<PathBuf, (Vec<PathBuf>, Vec<PathBuf>)> = {
-        fn process_dir(result: &mut BTreeMap<PathBuf, (Vec<PathBuf>, Vec<PathBuf>)>, dir: &Path) {
-            if dir.join("grammar.js").exists() {
-                let relative_path = dir.strip_prefix(GRAMMARS_DIR.as_path()).unwrap();
-                let (example_paths, query_paths) =
-                    result.entry(relative_path.to_owned()).or_default();
-
-                if let Ok(example_files) = fs::read_dir(dir.join("examples")) {
-                    example_paths.extend(example_files.filter_map(|p| {
-                        let p = p.unwrap().path();
-                        if p.is_file() {
-                            Some(p)
-                        } else {
-                            None
-                        }
-                    }));
-                }
-
-                if let Ok(query_files) = fs::read_dir(dir.join("queries")) {
-                    query_paths.extend(query_files.filter_map(|p| {
-                        let p = p.unwrap().path();
-                        if p.is_file() {
-                            Some(p)
-                        } else {
-                            None
-                        }
-                    }));
-                }
-            } else {
-                for entry in fs::read_dir(dir).unwrap() {
-                    let entry = entry.unwrap().path();
-                    if entry.is_dir() {
-                        process_dir(result, &entry);
-                    }
-                }
-            }
+        fn file_paths(dir: &Path) -> Vec<PathBuf> {
+            fs::read_dir(dir).map_or_else(
+                |_| Vec::new(),
+                |entries| {
+                    entries
+                        .map(|p| p.unwrap().path())
+                        .filter(|p| p.is_file())
+                        .collect()
+                },
+            )
         }
 
-        let mut result = BTreeMap::new();
-        process_dir(&mut result, &GRAMMARS_DIR);
-        result
+        find_grammar_paths(&GRAMMARS_DIR)
+            .unwrap()
+            .into_iter()
+            .map(|path| {
+                let dir = GRAMMARS_DIR.join(&path);
+                let paths = (
+                    file_paths(&dir.join("examples")),
+                    file_paths(&dir.join("queries")),
+                );
+                (path, paths)
+            })
+            .collect()
     };
 }
 
 fn main() {
+    if *THROUGHPUT_MODE {
+        report_throughput();
+        return;
+    }
+
     let max_path_length = EXAMPLE_AND_QUERY_PATHS_BY_LANGUAGE_DIR
         .values()
         .flat_map(|(e, q)| {
@@ -174,6 +167,36 @@ fn main() {
     eprintln!();
 }
 
+// Parse the largest corpus example of each language, and print the results as
+// tab-separated values on stdout.
+fn report_throughput() {
+    let mut parser = Parser::new();
+    println!("{}", Throughput::HEADER);
+    for language_path in EXAMPLE_AND_QUERY_PATHS_BY_LANGUAGE_DIR.keys() {
+        let language_name = language_path.file_name().unwrap().to_str().unwrap();
+        if let Some(filter) = LANGUAGE_FILTER.as_ref() {
+            if language_name != filter.as_str() {
+                continue;
+            }
+        }
+
+        let Some(corpus_path) = find_corpus_path(&GRAMMARS_DIR, language_path) else {
+            eprintln!("No corpus for {language_name}");
+            continue;
+        };
+        let Some(source) = largest_corpus_example(&corpus_path)
+            .with_context(|| format!("Failed to read corpus at {corpus_path:?}"))
+            .unwrap()
+        else {
+            eprintln!("No corpus examples for {language_name}");
+            continue;
+        };
+        parser.set_language(&get_language(language_path)).unwrap();
+        let throughput = measure_throughput(&mut parser, language_name, &source, *REPETITION_COUNT);
+        println!("{throughput}");
+    }
+}
+
 fn aggregate(speeds: &[usize]) -> Option<(usize, usize)> {
     if speeds.is_empty() {
         return None;
@@ -25,11 +25,11 @@ use libloading::{Library, Symbol};
 use once_cell::unsync::OnceCell;
 use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Deserializer, Serialize};
-use tree_sitter::Language;
 #[cfg(any(feature = "tree-sitter-highlight", feature = "tree-sitter-tags"))]
 use tree_sitter::QueryError;
 #[cfg(feature = "tree-sitter-highlight")]
 use tree_sitter::QueryErrorKind;
+use tree_sitter::{ffi::TSLanguage, Language};
 #[cfg(feature = "tree-sitter-highlight")]
 use tree_sitter_highlight::HighlightConfiguration;
 #[cfg(feature = "tree-sitter-tags")]
@@ -587,12 +587,19 @@ impl Loader {
             .with_context(|| format!("Error opening dynamic library {output_path:?}"))?;
         let language = unsafe {
             let language_fn = library
-                .get::<Symbol<unsafe extern "C" fn() -> Language>>(language_fn_name.as_bytes())
+                .get::<Symbol<unsafe extern "C" fn() -> *const TSLanguage>>(
+                    language_fn_name.as_bytes(),
+                )
                 .with_context(|| format!("Failed to load symbol {language_fn_name}"))?;
             language_fn()
         };
+        if language.is_null() {
+            return Err(anyhow!(
+                "Symbol {language_fn_name} returned a null language"
+            ));
+        }
         mem::forget(library);
-        Ok(language)
+        Ok(unsafe { Language::from_raw(language) })
     }
 
     fn compile_parser_to_dylib(
@@ -612,10 +619,6 @@ impl Loader {
             .includes(&config.header_paths)
             .std("c11");
 
-        if let Some(scanner_path) = config.scanner_path.as_ref() {
-            cc_config.file(scanner_path);
-        }
-
         if self.debug_build {
             cc_config.opt_level(0).extra_warnings(true);
         } else {
@@ -626,6 +629,21 @@ impl Loader {
             cc_config.define(flag, None);
         }
 
+        let output_path = config.output_path.as_ref().unwrap();
+
+        // A C++ scanner can't be passed to the same compiler invocation as the C parser, so
+        // it is compiled to an object file first, and that object is linked in below.
+        let mut scanner_object_path = None;
+        if let Some(scanner_path) = config.scanner_path.as_ref() {
+            if is_cpp_scanner(scanner_path) {
+                let object_path = output_path.with_extension("scanner.o");
+                Self::compile_cpp_scanner(&cc_config, scanner_path, &object_path)?;
+                scanner_object_path = Some(object_path);
+            } else {
+                cc_config.file(scanner_path);
+            }
+        }
+
         let compiler = cc_config.get_compiler();
         let mut command = Command::new(compiler.path());
         command.args(compiler.args());
@@ -633,13 +651,12 @@ impl Loader {
             command.env(key, value);
         }
 
-        let output_path = config.output_path.as_ref().unwrap();
-
         if compiler.is_like_msvc() {
             let out = format!("-out:{}", output_path.to_str().unwrap());
             command.arg(if self.debug_build { "-LDd" } else { "-LD" });
             command.arg("-utf-8");
             command.args(cc_config.get_files());
+            command.args(&scanner_object_path);
             command.arg("-link").arg(out);
         } else {
             command.arg("-Werror=implicit-function-declaration");
@@ -651,6 +668,14 @@ impl Loader {
                 command.arg("-shared");
             }
             command.args(cc_config.get_files());
+            if let Some(scanner_object_path) = &scanner_object_path {
+                command.arg(scanner_object_path);
+                if cfg!(any(target_os = "macos", target_os = "ios")) {
+                    command.arg("-lc++");
+                } else {
+                    command.arg("-lstdc++");
+                }
+            }
             command.arg("-o").arg(output_path);
         }
 
@@ -672,6 +697,45 @@ impl Loader {
         }
     }
 
+    fn compile_cpp_scanner(
+        cc_config: &cc::Build,
+        scanner_path: &Path,
+        object_path: &Path,
+    ) -> Result<(), Error> {
+        let compiler = cc_config.clone().cpp(true).std("c++14").get_compiler();
+        let mut command = Command::new(compiler.path());
+        command.args(compiler.args());
+        for (key, value) in compiler.env() {
+            command.env(key, value);
+        }
+
+        if compiler.is_like_msvc() {
+            command.arg("-utf-8").arg("-EHsc").arg("-c");
+            command.arg(format!("-Fo{}", object_path.to_str().unwrap()));
+            command.arg(scanner_path);
+        } else {
+            command
+                .arg("-c")
+                .arg(scanner_path)
+                .arg("-o")
+                .arg(object_path);
+        }
+
+        let output = command.output().with_context(|| {
+            format!("Failed to execute the C++ compiler with the following command:\n{command:?}")
+        })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Scanner compilation failed.\nStdout: {}\nStderr: {}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+
     #[cfg(unix)]
     fn check_external_scanner(&self, name: &str, library_path: &Path) -> Result<()> {
         let prefix = if cfg!(any(target_os = "macos", target_os = "ios")) {
@@ -687,9 +751,15 @@ impl Loader {
             format!("{prefix}tree_sitter_{name}_external_scanner_scan"),
         ];
 
+        // GNU nm treats `-U` as `--unicode`, so use the long form of "defined symbols only" there
+        let defined_only = if cfg!(any(target_os = "macos", target_os = "ios")) {
+            "-U"
+        } else {
+            "--defined-only"
+        };
         let command = Command::new("nm")
             .arg("-W")
-            .arg("-U")
+            .arg(defined_only)
             .arg(library_path)
             .output();
         if let Ok(output) = command {
@@ -1191,8 +1261,10 @@ impl Loader {
 
     #[must_use]
     pub fn get_scanner_path(&self, src_path: &Path) -> Option<PathBuf> {
-        let path = src_path.join("scanner.c");
-        path.exists().then_some(path)
+        ["scanner.c", "scanner.cc"]
+            .iter()
+            .map(|name| src_path.join(name))
+            .find(|path| path.exists())
     }
 }
 
@@ -1439,3 +1511,7 @@ fn replace_dashes_with_underscores(name: &str) -> String {
     }
     result
 }
+
+fn is_cpp_scanner(scanner_path: &Path) -> bool {
+    scanner_path.extension().is_some_and(|ext| ext == "cc")
+}
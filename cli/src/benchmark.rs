@@ -0,0 +1,137 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use tree_sitter::Parser;
+
+use super::test::{parse_tests_iter, TestEntry};
+
+/// The speed at which a language's parser parsed an example, as measured by
+/// [`measure_throughput`].
+#[derive(Debug, Clone)]
+pub struct Throughput {
+    pub language_name: String,
+    /// The length of the example, in bytes.
+    pub bytes: usize,
+    /// The number of nodes in the example's syntax tree.
+    pub nodes: usize,
+    pub iterations: usize,
+    /// The time taken by all of the iterations.
+    pub duration: Duration,
+}
+
+impl Throughput {
+    /// The column names of the lines written by this type's `Display`
+    /// implementation.
+    pub const HEADER: &'static str =
+        "language\tbytes\tnodes\titerations\tbytes_per_second\tnodes_per_second";
+
+    #[must_use]
+    pub fn bytes_per_second(&self) -> u64 {
+        self.per_second(self.bytes)
+    }
+
+    #[must_use]
+    pub fn nodes_per_second(&self) -> u64 {
+        self.per_second(self.nodes)
+    }
+
+    fn per_second(&self, count: usize) -> u64 {
+        let total = count as u128 * self.iterations as u128 * 1_000_000_000;
+        (total / self.duration.as_nanos().max(1)) as u64
+    }
+}
+
+/// Writes the throughput as one line of tab-separated values, in the order
+/// of [`Throughput::HEADER`].
+impl fmt::Display for Throughput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.language_name,
+            self.bytes,
+            self.nodes,
+            self.iterations,
+            self.bytes_per_second(),
+            self.nodes_per_second(),
+        )
+    }
+}
+
+/// Find the grammars in a directory of grammars, by searching its
+/// subdirectories until a `grammar.js` file is found. The paths are relative
+/// to `grammars_dir`, and sorted.
+pub fn find_grammar_paths(grammars_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut result = Vec::new();
+    let mut stack = vec![grammars_dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        if dir.join("grammar.js").exists() {
+            result.push(dir.strip_prefix(grammars_dir).unwrap().to_path_buf());
+            continue;
+        }
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                stack.push(path);
+            }
+        }
+    }
+    result.sort();
+    Ok(result)
+}
+
+/// Find the corpus of a grammar found by [`find_grammar_paths`]. Grammars in
+/// subdirectories share the corpus of the nearest ancestor that has one.
+#[must_use]
+pub fn find_corpus_path(grammars_dir: &Path, grammar_path: &Path) -> Option<PathBuf> {
+    grammar_path
+        .ancestors()
+        .take_while(|path| !path.as_os_str().is_empty())
+        .map(|path| grammars_dir.join(path).join("test").join("corpus"))
+        .find(|path| path.exists())
+}
+
+/// Find the input of the longest example in a corpus, or the first of the
+/// longest ones. Returns `None` if the corpus has no examples.
+pub fn largest_corpus_example(corpus_path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let mut result = None::<Vec<u8>>;
+    for entry in parse_tests_iter(corpus_path) {
+        if let TestEntry::Example { input, .. } = entry? {
+            if result.as_ref().map_or(true, |r| input.len() > r.len()) {
+                result = Some(input);
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Parse `source` `iterations` times with the parser's current language, and
+/// measure how fast it was parsed.
+///
+/// # Panics
+///
+/// Panics if the parser has no language, or if `iterations` is zero.
+pub fn measure_throughput(
+    parser: &mut Parser,
+    language_name: &str,
+    source: &[u8],
+    iterations: usize,
+) -> Throughput {
+    assert!(iterations > 0, "at least one iteration is required");
+    let start_time = Instant::now();
+    let mut tree = None;
+    for _ in 0..iterations {
+        tree = Some(parser.parse(source, None).expect("Failed to parse"));
+    }
+    let duration = start_time.elapsed();
+    Throughput {
+        language_name: language_name.to_string(),
+        bytes: source.len(),
+        nodes: tree.unwrap().root_node().descendant_count(),
+        iterations,
+        duration,
+    }
+}
@@ -1,6 +1,10 @@
+#[cfg(feature = "allocation-backtraces")]
+use std::backtrace::Backtrace;
 use std::{
     collections::HashMap,
+    fs, io,
     os::raw::c_void,
+    path::Path,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst},
         Mutex,
@@ -22,11 +26,18 @@ struct Allocation(*const c_void);
 unsafe impl Send for Allocation {}
 unsafe impl Sync for Allocation {}
 
+struct AllocationRecord {
+    index: usize,
+    #[cfg(feature = "allocation-backtraces")]
+    backtrace: Option<Backtrace>,
+}
+
 #[derive(Default)]
 struct AllocationRecorder {
     enabled: AtomicBool,
+    capture_backtraces: AtomicBool,
     allocation_count: AtomicUsize,
-    outstanding_allocations: Mutex<HashMap<Allocation, usize>>,
+    outstanding_allocations: Mutex<HashMap<Allocation, AllocationRecord>>,
 }
 
 thread_local! {
@@ -41,42 +52,109 @@ extern "C" {
 }
 
 pub fn record<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    start_recording();
+    let value = f();
+    let outstanding_allocation_indices = stop_recording();
+    if !outstanding_allocation_indices.is_empty() {
+        return Err(format!(
+            "Leaked allocation indices: {outstanding_allocation_indices:?}",
+        ));
+    }
+    Ok(value)
+}
+
+/// Run `f`, and panic if any allocations made by the tree-sitter library
+/// during `f` are still outstanding when it returns.
+///
+/// This is useful for checking that parsers and trees created by `f` are
+/// freed when they are dropped.
+pub fn assert_no_leaks(f: impl FnOnce()) {
+    start_recording();
+    f();
+    let outstanding_allocation_indices = stop_recording();
+    assert!(
+        outstanding_allocation_indices.is_empty(),
+        "Leaked {} allocations, with indices: {outstanding_allocation_indices:?}",
+        outstanding_allocation_indices.len(),
+    );
+}
+
+/// Run `f`, and write a report listing the allocations made by the
+/// tree-sitter library during `f` that are still outstanding when it returns.
+///
+/// When the `allocation-backtraces` feature is enabled, the report includes
+/// the backtrace of each outstanding allocation. Otherwise, only their indices
+/// are listed. Returns the number of outstanding allocations.
+pub fn write_leak_report(report_path: &Path, f: impl FnOnce()) -> io::Result<usize> {
+    RECORDER.with(|recorder| recorder.capture_backtraces.store(true, SeqCst));
+    start_recording();
+    f();
+    let records = stop_recording_with_records();
+    RECORDER.with(|recorder| recorder.capture_backtraces.store(false, SeqCst));
+
+    let mut report = String::new();
+    if !records.is_empty() {
+        report.push_str(&format!("Leaked {} allocations\n", records.len()));
+    }
+    for record in &records {
+        report.push_str(&format!("\nAllocation #{}\n", record.index));
+        #[cfg(feature = "allocation-backtraces")]
+        if let Some(backtrace) = &record.backtrace {
+            report.push_str(&format!("{backtrace}\n"));
+        }
+    }
+    fs::write(report_path, report)?;
+    Ok(records.len())
+}
+
+fn start_recording() {
     RECORDER.with(|recorder| {
         recorder.enabled.store(true, SeqCst);
         recorder.allocation_count.store(0, SeqCst);
         recorder.outstanding_allocations.lock().unwrap().clear();
     });
+}
 
-    let value = f();
+fn stop_recording() -> Vec<usize> {
+    stop_recording_with_records()
+        .into_iter()
+        .map(|record| record.index)
+        .collect()
+}
 
-    let outstanding_allocation_indices = RECORDER.with(|recorder| {
+fn stop_recording_with_records() -> Vec<AllocationRecord> {
+    RECORDER.with(|recorder| {
         recorder.enabled.store(false, SeqCst);
         recorder.allocation_count.store(0, SeqCst);
-        recorder
+        let mut records = recorder
             .outstanding_allocations
             .lock()
             .unwrap()
             .drain()
             .map(|e| e.1)
-            .collect::<Vec<_>>()
-    });
-    if !outstanding_allocation_indices.is_empty() {
-        return Err(format!(
-            "Leaked allocation indices: {outstanding_allocation_indices:?}",
-        ));
-    }
-    Ok(value)
+            .collect::<Vec<_>>();
+        records.sort_unstable_by_key(|record| record.index);
+        records
+    })
 }
 
 fn record_alloc(ptr: *mut c_void) {
     RECORDER.with(|recorder| {
         if recorder.enabled.load(SeqCst) {
             let count = recorder.allocation_count.fetch_add(1, SeqCst);
+            let record = AllocationRecord {
+                index: count,
+                #[cfg(feature = "allocation-backtraces")]
+                backtrace: recorder
+                    .capture_backtraces
+                    .load(SeqCst)
+                    .then(Backtrace::force_capture),
+            };
             recorder
                 .outstanding_allocations
                 .lock()
                 .unwrap()
-                .insert(Allocation(ptr), count);
+                .insert(Allocation(ptr), record);
         }
     });
 }
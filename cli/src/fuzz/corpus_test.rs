@@ -1,6 +1,6 @@
 use tree_sitter::{LogType, Node, Parser, Point, Range, Tree};
 
-use super::{scope_sequence::ScopeSequence, LOG_ENABLED, LOG_GRAPH_ENABLED};
+use super::{scope_sequence::ScopeSequence, LOG_ENABLED, LOG_GRAPH_ENABLED, TEST_TIMEOUT_MICROS};
 use crate::util;
 
 pub fn check_consistent_sizes(tree: &Tree, input: &[u8]) {
@@ -127,8 +127,28 @@ fn point_for_offset(text: &[u8], offset: usize) -> Point {
     point
 }
 
+/// Parse a corpus example, reporting a timeout instead of panicking if the
+/// parser's timeout is exceeded. The parser is reset after a timeout.
+pub fn parse_or_report_timeout(
+    parser: &mut Parser,
+    input: &[u8],
+    old_tree: Option<&Tree>,
+    test_name: &str,
+) -> Option<Tree> {
+    let tree = parser.parse(input, old_tree);
+    if tree.is_none() {
+        println!(
+            "Timed out parsing {test_name} after {} ms",
+            parser.timeout_micros() / 1000
+        );
+        parser.reset();
+    }
+    tree
+}
+
 pub fn get_parser(session: &mut Option<util::LogSession>, log_filename: &str) -> Parser {
     let mut parser = Parser::new();
+    parser.set_timeout_micros(*TEST_TIMEOUT_MICROS);
 
     if *LOG_ENABLED {
         parser.set_logger(Some(Box::new(|log_type, msg| {
@@ -1,3 +1,5 @@
+use tree_sitter::{InputEdit, Language, Parser};
+
 use super::random::Rand;
 
 #[derive(Debug)]
@@ -59,3 +61,40 @@ pub fn get_random_edit(rand: &mut Rand, input: &[u8]) -> Edit {
         }
     }
 }
+
+/// Parse `source`, then apply each of the `edits` in turn, replacing the
+/// edit's old byte range with the given text and reparsing incrementally.
+/// Panic if an incremental parse produces a different tree than a full parse
+/// of the edited text.
+///
+/// This checks the invariant that incremental and full parses agree, which
+/// catches incremental-parsing bugs in a grammar.
+///
+/// # Panics
+///
+/// Also panics if the length of an edit's text doesn't match the edit's
+/// `new_end_byte`.
+pub fn assert_incremental_matches(language: &Language, source: &str, edits: &[(InputEdit, &[u8])]) {
+    let mut parser = Parser::new();
+    parser.set_language(language).unwrap();
+
+    let mut input = source.as_bytes().to_vec();
+    let mut tree = parser.parse(&input, None).unwrap();
+    for (i, (edit, text)) in edits.iter().enumerate() {
+        assert_eq!(
+            edit.start_byte + text.len(),
+            edit.new_end_byte,
+            "the text of edit {i} doesn't fit the edit ({edit:?})",
+        );
+        input.splice(edit.start_byte..edit.old_end_byte, text.iter().copied());
+        tree.edit(edit);
+        tree = parser.parse(&input, Some(&tree)).unwrap();
+        let full_tree = parser.parse(&input, None).unwrap();
+        assert_eq!(
+            tree.root_node().to_sexp(),
+            full_tree.root_node().to_sexp(),
+            "incremental parse differs from full parse after edit {i} ({edit:?}) of:\n{}",
+            String::from_utf8_lossy(&input),
+        );
+    }
+}
@@ -14,7 +14,8 @@ pub mod scope_sequence;
 use crate::{
     fuzz::{
         corpus_test::{
-            check_changed_ranges, check_consistent_sizes, get_parser, set_included_ranges,
+            check_changed_ranges, check_consistent_sizes, get_parser, parse_or_report_timeout,
+            set_included_ranges,
         },
         edits::{get_random_edit, invert_edit},
         random::Rand,
@@ -32,6 +33,8 @@ lazy_static! {
     pub static ref START_SEED: usize = new_seed();
     pub static ref EDIT_COUNT: usize = int_env_var("TREE_SITTER_EDITS").unwrap_or(3);
     pub static ref ITERATION_COUNT: usize = int_env_var("TREE_SITTER_ITERATIONS").unwrap_or(10);
+    pub static ref TEST_TIMEOUT_MICROS: u64 =
+        int_env_var("TREE_SITTER_TEST_TIMEOUT_MS").map_or(0, |ms| ms as u64 * 1000);
 }
 
 fn int_env_var(name: &'static str) -> Option<usize> {
@@ -149,7 +152,10 @@ pub fn fuzz_language_corpus(
             parser.set_language(language).unwrap();
             set_included_ranges(&mut parser, &test.input, test.template_delimiters);
 
-            let tree = parser.parse(&test.input, None).unwrap();
+            let Some(tree) = parse_or_report_timeout(&mut parser, &test.input, None, &test_name)
+            else {
+                return false;
+            };
             let mut actual_output = tree.root_node().to_sexp();
             if !test.has_fields {
                 actual_output = strip_sexp_fields(&actual_output);
@@ -157,7 +163,7 @@ pub fn fuzz_language_corpus(
 
             if actual_output != test.output {
                 println!("Incorrect initial parse for {test_name}");
-                print_diff_key();
+                print_diff_key(true);
                 print_diff(&actual_output, &test.output, true);
                 println!();
                 return false;
@@ -221,7 +227,11 @@ pub fn fuzz_language_corpus(
                 }
 
                 set_included_ranges(&mut parser, &input, test.template_delimiters);
-                let mut tree2 = parser.parse(&input, Some(&tree)).unwrap();
+                let Some(mut tree2) =
+                    parse_or_report_timeout(&mut parser, &input, Some(&tree), &test_name)
+                else {
+                    return false;
+                };
 
                 // Check that the new tree is consistent.
                 check_consistent_sizes(&tree2, &input);
@@ -239,7 +249,11 @@ pub fn fuzz_language_corpus(
                 }
 
                 set_included_ranges(&mut parser, &test.input, test.template_delimiters);
-                let tree3 = parser.parse(&input, Some(&tree2)).unwrap();
+                let Some(tree3) =
+                    parse_or_report_timeout(&mut parser, &input, Some(&tree2), &test_name)
+                else {
+                    return false;
+                };
 
                 // Verify that the final tree matches the expectation from the corpus.
                 let mut actual_output = tree3.root_node().to_sexp();
@@ -249,7 +263,7 @@ pub fn fuzz_language_corpus(
 
                 if actual_output != test.output {
                     println!("Incorrect parse for {test_name} - seed {seed}");
-                    print_diff_key();
+                    print_diff_key(true);
                     print_diff(&actual_output, &test.output, true);
                     println!();
                     return false;
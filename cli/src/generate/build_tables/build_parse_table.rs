@@ -23,6 +23,7 @@ use crate::generate::{
         FieldLocation, GotoAction, ParseAction, ParseState, ParseStateId, ParseTable,
         ParseTableEntry, ProductionInfo, ProductionInfoId,
     },
+    Conflict, GenerateError,
 };
 
 // For conflict reporting, each parse state is associated with an example
@@ -63,11 +64,12 @@ struct ParseTableBuilder<'a> {
     parse_state_queue: VecDeque<ParseStateQueueEntry>,
     non_terminal_extra_states: Vec<(Symbol, usize)>,
     actual_conflicts: HashSet<Vec<Symbol>>,
+    collected_conflicts: Option<Vec<Conflict>>,
     parse_table: ParseTable,
 }
 
 impl<'a> ParseTableBuilder<'a> {
-    fn build(mut self) -> Result<(ParseTable, Vec<ParseStateInfo<'a>>)> {
+    fn build(mut self) -> Result<(ParseTable, Vec<ParseStateInfo<'a>>, Option<Vec<Conflict>>)> {
         // Ensure that the empty alias sequence has index 0.
         self.parse_table
             .production_infos
@@ -147,7 +149,11 @@ impl<'a> ParseTableBuilder<'a> {
             }
         }
 
-        Ok((self.parse_table, self.parse_state_info_by_id))
+        Ok((
+            self.parse_table,
+            self.parse_state_info_by_id,
+            self.collected_conflicts,
+        ))
     }
 
     fn add_parse_state(
@@ -769,7 +775,43 @@ impl<'a> ParseTableBuilder<'a> {
         }
         writeln!(&mut msg).unwrap();
 
-        Err(anyhow!(msg))
+        let symbols = actual_conflict
+            .iter()
+            .map(|symbol| self.symbol_name(symbol))
+            .collect();
+        let lookahead = self.symbol_name(&conflicting_lookahead);
+
+        // When collecting conflicts, record this one and leave all of the
+        // conflicting actions in the table so that generation can continue.
+        // The same conflict can occur in many states, but it is only recorded
+        // for the first one.
+        if let Some(mut collected_conflicts) = self.collected_conflicts.take() {
+            if !collected_conflicts
+                .iter()
+                .any(|conflict| conflict.symbols == symbols && conflict.lookahead == lookahead)
+            {
+                let preceding_symbols = preceding_symbols
+                    .iter()
+                    .map(|symbol| self.symbol_name(symbol))
+                    .collect();
+                collected_conflicts.push(Conflict {
+                    symbols,
+                    lookahead,
+                    state_id,
+                    preceding_symbols,
+                    message: msg,
+                });
+            }
+            self.collected_conflicts = Some(collected_conflicts);
+            return Ok(());
+        }
+
+        Err(GenerateError::Conflict {
+            symbols,
+            lookahead,
+            message: msg,
+        }
+        .into())
     }
 
     fn compare_precedence(
@@ -973,6 +1015,7 @@ pub fn build_parse_table<'a>(
     lexical_grammar: &'a LexicalGrammar,
     inlines: &'a InlinedProductionMap,
     variable_info: &'a [VariableInfo],
+    conflicts: Option<&mut Vec<Conflict>>,
 ) -> Result<(ParseTable, Vec<TokenSet>, Vec<ParseStateInfo<'a>>)> {
     let actual_conflicts = syntax_grammar.expected_conflicts.iter().cloned().collect();
     let item_set_builder = ParseItemSetBuilder::new(syntax_grammar, lexical_grammar, inlines);
@@ -984,13 +1027,14 @@ pub fn build_parse_table<'a>(
         &item_set_builder,
     );
 
-    let (table, item_sets) = ParseTableBuilder {
+    let (table, item_sets, collected_conflicts) = ParseTableBuilder {
         syntax_grammar,
         lexical_grammar,
         item_set_builder,
         variable_info,
         non_terminal_extra_states: Vec::new(),
         actual_conflicts,
+        collected_conflicts: conflicts.is_some().then(Vec::new),
         state_ids_by_item_set: IndexMap::default(),
         core_ids_by_core: HashMap::new(),
         parse_state_info_by_id: Vec::new(),
@@ -1005,5 +1049,9 @@ pub fn build_parse_table<'a>(
     }
     .build()?;
 
+    if let (Some(conflicts), Some(collected_conflicts)) = (conflicts, collected_conflicts) {
+        *conflicts = collected_conflicts;
+    }
+
     Ok((table, following_tokens, item_sets))
 }
@@ -6,7 +6,10 @@ mod item_set_builder;
 mod minimize_parse_table;
 mod token_conflicts;
 
-use std::collections::{BTreeSet, HashMap};
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 pub use build_lex_table::LARGE_CHARACTER_RANGE_COUNT;
@@ -25,6 +28,7 @@ use crate::generate::{
     node_types::VariableInfo,
     rules::{AliasMap, Symbol, SymbolType, TokenSet},
     tables::{LexTable, ParseAction, ParseTable, ParseTableEntry},
+    Conflict, GenerateProfile,
 };
 
 pub struct Tables {
@@ -42,9 +46,24 @@ pub fn build_tables(
     variable_info: &[VariableInfo],
     inlines: &InlinedProductionMap,
     report_symbol_name: Option<&str>,
+    conflicts: Option<&mut Vec<Conflict>>,
+    mut profile: Option<&mut GenerateProfile>,
 ) -> Result<Tables> {
-    let (mut parse_table, following_tokens, parse_state_info) =
-        build_parse_table(syntax_grammar, lexical_grammar, inlines, variable_info)?;
+    let mut phase_start_time = Instant::now();
+    let mut end_phase = |duration: fn(&mut GenerateProfile) -> &mut Duration| {
+        if let Some(profile) = profile.as_deref_mut() {
+            *duration(profile) = phase_start_time.elapsed();
+        }
+        phase_start_time = Instant::now();
+    };
+
+    let (mut parse_table, following_tokens, parse_state_info) = build_parse_table(
+        syntax_grammar,
+        lexical_grammar,
+        inlines,
+        variable_info,
+        conflicts,
+    )?;
     let token_conflict_map = TokenConflictMap::new(lexical_grammar, following_tokens);
     let coincident_token_index = CoincidentTokenIndex::new(&parse_table, lexical_grammar);
     let keywords = identify_keywords(
@@ -63,6 +82,8 @@ pub fn build_tables(
         &keywords,
     );
     populate_used_symbols(&mut parse_table, syntax_grammar, lexical_grammar);
+    end_phase(|profile| &mut profile.build_parse_table);
+
     minimize_parse_table(
         &mut parse_table,
         syntax_grammar,
@@ -71,6 +92,8 @@ pub fn build_tables(
         &token_conflict_map,
         &keywords,
     );
+    end_phase(|profile| &mut profile.minimize_parse_table);
+
     let lex_tables = build_lex_table(
         &mut parse_table,
         syntax_grammar,
@@ -81,6 +104,7 @@ pub fn build_tables(
     );
     populate_external_lex_states(&mut parse_table, syntax_grammar);
     mark_fragile_tokens(&mut parse_table, lexical_grammar, &token_conflict_map);
+    end_phase(|profile| &mut profile.build_lex_table);
 
     if let Some(report_symbol_name) = report_symbol_name {
         report_state_info(
@@ -39,6 +39,8 @@ pub struct InputGrammar {
     pub variables_to_inline: Vec<String>,
     pub supertype_symbols: Vec<String>,
     pub word_token: Option<String>,
+    /// The `// doc:` comments above the rules, keyed by rule name.
+    pub rule_docs: HashMap<String, String>,
 }
 
 // Extracted lexical grammar
@@ -1,20 +1,21 @@
 use std::{
-    env, fs,
+    collections::HashMap,
+    env, fmt, fs,
     io::Write,
     path::{Path, PathBuf},
     process::{Command, Stdio},
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Context, Result};
 use build_tables::build_tables;
 use grammar_files::path_in_ignore;
 use grammars::InputGrammar;
-use lazy_static::lazy_static;
 use parse_grammar::parse_grammar;
 use prepare_grammar::prepare_grammar;
-use regex::{Regex, RegexBuilder};
-use render::render_c_code;
+use render::{large_state_count, render_c_code};
 use semver::Version;
+use thiserror::Error;
 
 mod build_tables;
 mod dedup;
@@ -27,19 +28,301 @@ mod prepare_grammar;
 mod render;
 mod rules;
 mod tables;
+mod warnings;
 
 pub use grammar_files::lookup_package_json_for_path;
 
-lazy_static! {
-    static ref JSON_COMMENT_REGEX: Regex = RegexBuilder::new("^\\s*//.*")
-        .multi_line(true)
-        .build()
-        .unwrap();
+/// Represents the reason why generating a parser from a grammar failed.
+#[derive(Debug, Error)]
+pub enum GenerateError {
+    /// The grammar is not valid JSON, or does not match the grammar schema.
+    #[error("{0}")]
+    InvalidJson(#[from] serde_json::Error),
+    /// A rule refers to a symbol that is not defined in the grammar.
+    #[error("Undefined symbol `{0}`")]
+    UndefinedSymbol(String),
+    /// The grammar contains an unresolved parse conflict between `symbols`
+    /// when the next token is `lookahead`. The message describes the possible
+    /// interpretations and resolutions.
+    #[error("{message}")]
+    Conflict {
+        symbols: Vec<String>,
+        lookahead: String,
+        message: String,
+    },
+    /// Two precedence names are ordered differently in different `precedences`
+    /// lists.
+    #[error("Conflicting orderings for precedences {0} and {1}")]
+    PrecedenceCycle(String, String),
+    /// The grammar has warnings, and warnings were denied.
+    #[error(
+        "Grammar has {} warning(s):{}",
+        .0.len(),
+        .0.iter().map(|warning| format!("\n  {warning}")).collect::<String>()
+    )]
+    Warnings(Vec<GenerateWarning>),
+    /// Any other problem, such as an invalid rule in the grammar or a file
+    /// that could not be written. The error's message describes it.
+    #[error(transparent)]
+    Other(anyhow::Error),
 }
 
-struct GeneratedParser {
-    c_code: String,
-    node_types_json: String,
+/// An unresolved parse conflict, as collected when
+/// [`GenerateOptions::collect_conflicts`] is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The names of the rules involved in the conflict.
+    pub symbols: Vec<String>,
+    /// The name of the lookahead token for which the conflict occurs.
+    pub lookahead: String,
+    /// The id of the (unminimized) parse state in which the conflict occurs.
+    pub state_id: usize,
+    /// An example sequence of symbols that leads to the conflicting state.
+    pub preceding_symbols: Vec<String>,
+    /// The same description that [`GenerateError::Conflict`] would carry.
+    pub message: String,
+}
+
+/// A problem in a grammar that doesn't prevent a parser from being generated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerateWarning {
+    pub kind: GenerateWarningKind,
+    /// The name of the rule involved.
+    pub rule: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerateWarningKind {
+    /// The rule can't be reached from the start rule, the extras, the
+    /// external tokens, the word token or the supertypes, so it is not part
+    /// of the parser.
+    UnusedRule,
+    /// The rule contains a `seq` or `choice` of a single string or pattern.
+    SingleElementRule,
+    /// The token is used by the parser, but the lexer never produces it,
+    /// because wherever it is valid, other tokens that match the same text
+    /// take precedence over it.
+    ShadowedToken,
+}
+
+impl fmt::Display for GenerateWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            GenerateWarningKind::UnusedRule => write!(f, "Rule `{}` is unused", self.rule),
+            GenerateWarningKind::SingleElementRule => write!(
+                f,
+                "Rule `{}` is just a `seq` or `choice` rule with a single element. This is unnecessary.",
+                self.rule
+            ),
+            GenerateWarningKind::ShadowedToken => write!(
+                f,
+                "Token `{}` is shadowed by other tokens, so it is never lexed",
+                self.rule.escape_debug()
+            ),
+        }
+    }
+}
+
+/// The time spent in each phase of generating a parser, as measured when
+/// [`GenerateOptions::profile`] is set.
+#[derive(Debug, Clone, Default)]
+pub struct GenerateProfile {
+    /// Preparing the grammar and computing its node types.
+    pub prepare_grammar: Duration,
+    /// Building the parse table, before it is minimized.
+    pub build_parse_table: Duration,
+    /// Minimizing the parse table.
+    pub minimize_parse_table: Duration,
+    /// Building the lex tables.
+    pub build_lex_table: Duration,
+    /// Rendering the C code.
+    pub render: Duration,
+    /// The time taken by the whole generation.
+    pub total: Duration,
+}
+
+/// The sizes of a generated parser's tables.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GrammarStats {
+    /// The number of parse states, written to the parser as `STATE_COUNT`.
+    pub parse_states: usize,
+    /// The number of states in the main lex table and the keyword lex table.
+    pub lex_states: usize,
+    /// The number of parse states stored as full rows of the parse table,
+    /// written to the parser as `LARGE_STATE_COUNT`. The other states use a
+    /// compact representation.
+    pub large_state_count: usize,
+    /// The number of grammar symbols, written to the parser as `SYMBOL_COUNT`.
+    pub symbol_count: usize,
+}
+
+impl From<anyhow::Error> for GenerateError {
+    fn from(error: anyhow::Error) -> Self {
+        error.downcast().unwrap_or_else(Self::Other)
+    }
+}
+
+/// Options for [`generate_parser_for_grammar_with_options`]. The defaults
+/// match [`generate_parser_for_grammar`], and each method changes one of them.
+#[derive(Debug, Clone)]
+pub struct GenerateOptions<'a> {
+    abi_version: usize,
+    report_symbol_name: Option<&'a str>,
+    deny_warnings: bool,
+    collect_conflicts: bool,
+    profile: bool,
+}
+
+impl Default for GenerateOptions<'_> {
+    fn default() -> Self {
+        Self {
+            abi_version: tree_sitter::LANGUAGE_VERSION,
+            report_symbol_name: None,
+            deny_warnings: false,
+            collect_conflicts: false,
+            profile: false,
+        }
+    }
+}
+
+impl<'a> GenerateOptions<'a> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the ABI version of the generated parser. Defaults to
+    /// [`tree_sitter::LANGUAGE_VERSION`].
+    #[must_use]
+    pub const fn abi_version(mut self, abi_version: usize) -> Self {
+        self.abi_version = abi_version;
+        self
+    }
+
+    /// Print the parse items that involve the given symbol while the parse
+    /// table is built.
+    #[must_use]
+    pub const fn report_symbol_name(mut self, report_symbol_name: Option<&'a str>) -> Self {
+        self.report_symbol_name = report_symbol_name;
+        self
+    }
+
+    /// Return any warnings as [`GenerateError::Warnings`] instead of
+    /// generating a parser.
+    #[must_use]
+    pub const fn deny_warnings(mut self, deny_warnings: bool) -> Self {
+        self.deny_warnings = deny_warnings;
+        self
+    }
+
+    /// Collect every unresolved parse conflict into
+    /// [`GeneratedParser::conflicts`], instead of stopping at the first one
+    /// with [`GenerateError::Conflict`]. The conflicting actions are all kept
+    /// in the generated parser.
+    #[must_use]
+    pub const fn collect_conflicts(mut self, collect_conflicts: bool) -> Self {
+        self.collect_conflicts = collect_conflicts;
+        self
+    }
+
+    /// Measure the time spent in each phase of the generation, and report it
+    /// in [`GeneratedParser::profile`].
+    #[must_use]
+    pub const fn profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+}
+
+/// A parser generated by [`generate_parser_for_grammar_with_options`], along
+/// with what was learned about its grammar.
+#[derive(Debug, Clone)]
+pub struct GeneratedParser {
+    /// The name of the grammar.
+    pub name: String,
+    /// The contents of the parser's `parser.c` file.
+    pub c_code: String,
+    /// The contents of the grammar's `node-types.json` file, which describes
+    /// the types of nodes that the parser can produce.
+    pub node_types_json: String,
+    /// The sizes of the parser's tables.
+    pub stats: GrammarStats,
+    /// The grammar's warnings, such as unused rules, in the order of the
+    /// grammar's rules, followed by the shadowed tokens. Nothing prints them,
+    /// so it's up to the caller to report them.
+    pub warnings: Vec<GenerateWarning>,
+    /// The unresolved parse conflicts, if
+    /// [`GenerateOptions::collect_conflicts`] is set. Each combination of
+    /// symbols and lookahead is reported once, for the first state in which
+    /// it occurs. Conflicts that are listed in the grammar's `conflicts` field
+    /// are not reported.
+    pub conflicts: Vec<Conflict>,
+    /// The time spent in each phase, if [`GenerateOptions::profile`] is set.
+    pub profile: Option<GenerateProfile>,
+    /// Maps the name of each supertype declared in the grammar's `supertypes`
+    /// field to the names of the node types that it covers. A supertype's
+    /// subtypes may themselves be supertypes. The subtypes are listed in the
+    /// same order as in `node-types.json`.
+    pub supertype_map: HashMap<String, Vec<String>>,
+    /// The documentation of each rule, written as one or more `// doc:`
+    /// comment lines directly above its entry in the `rules` object. The
+    /// lines are joined with newlines. Rules without such comments have no
+    /// entry.
+    pub rule_docs: HashMap<String, String>,
+    grammar_json: String,
+}
+
+/// Options for [`GeneratedParser::write_to_dir`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteOptions {
+    /// Replace files that already exist, instead of returning an error.
+    pub overwrite: bool,
+    /// Also write the `grammar.json` that the parser was generated from.
+    pub write_grammar_json: bool,
+}
+
+impl GeneratedParser {
+    /// Write the parser's `parser.c` and `node-types.json` files into
+    /// `out_dir`, along with the `tree_sitter` headers that `parser.c`
+    /// includes. Returns the paths of the written files.
+    ///
+    /// `out_dir` is created if it does not exist. Unless `options.overwrite`
+    /// is set, an error is returned if any of the files already exists, and
+    /// no files are written.
+    pub fn write_to_dir(
+        &self,
+        out_dir: &Path,
+        options: &WriteOptions,
+    ) -> Result<Vec<PathBuf>, GenerateError> {
+        let header_dir = out_dir.join("tree_sitter");
+        let mut files = vec![
+            (out_dir.join("parser.c"), self.c_code.as_str()),
+            (
+                out_dir.join("node-types.json"),
+                self.node_types_json.as_str(),
+            ),
+            (header_dir.join("alloc.h"), ALLOC_HEADER),
+            (header_dir.join("array.h"), tree_sitter::ARRAY_HEADER),
+            (header_dir.join("parser.h"), tree_sitter::PARSER_HEADER),
+        ];
+        if options.write_grammar_json {
+            files.push((out_dir.join("grammar.json"), self.grammar_json.as_str()));
+        }
+        if !options.overwrite {
+            if let Some((path, _)) = files.iter().find(|(path, _)| path.exists()) {
+                return Err(anyhow!("{path:?} already exists").into());
+            }
+        }
+
+        fs::create_dir_all(&header_dir)
+            .with_context(|| format!("Failed to create directory {header_dir:?}"))?;
+        let mut paths = Vec::with_capacity(files.len());
+        for (path, contents) in files {
+            write_file(&path, contents)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
 }
 
 pub const ALLOC_HEADER: &str = include_str!("./templates/alloc.h");
@@ -51,7 +334,8 @@ pub fn generate_parser_in_directory(
     generate_bindings: bool,
     report_symbol_name: Option<&str>,
     js_runtime: Option<&str>,
-) -> Result<()> {
+    deny_warnings: bool,
+) -> Result<Vec<GenerateWarning>> {
     let mut repo_path = repo_path.to_owned();
     let mut grammar_path = grammar_path;
 
@@ -92,52 +376,100 @@ pub fn generate_parser_in_directory(
     };
 
     let src_path = repo_path.join("src");
-    let header_path = src_path.join("tree_sitter");
 
-    // Ensure that the output directories exist.
+    // Ensure that the output directory exists.
     fs::create_dir_all(&src_path)?;
-    fs::create_dir_all(&header_path)?;
 
     if grammar_path.is_none() {
         fs::write(src_path.join("grammar.json"), &grammar_json)
             .with_context(|| format!("Failed to write grammar.json to {src_path:?}"))?;
     }
 
-    // Parse and preprocess the grammar.
-    let input_grammar = parse_grammar(&grammar_json)?;
-
     // Generate the parser and related files.
-    let GeneratedParser {
-        c_code,
-        node_types_json,
-    } = generate_parser_for_grammar_with_opts(&input_grammar, abi_version, report_symbol_name)?;
+    let parser = generate_parser_for_grammar_with_options(
+        &grammar_json,
+        &GenerateOptions::new()
+            .abi_version(abi_version)
+            .report_symbol_name(report_symbol_name)
+            .deny_warnings(deny_warnings),
+    )?;
 
-    write_file(&src_path.join("parser.c"), c_code)?;
-    write_file(&src_path.join("node-types.json"), node_types_json)?;
-    write_file(&header_path.join("alloc.h"), ALLOC_HEADER)?;
-    write_file(&header_path.join("array.h"), tree_sitter::ARRAY_HEADER)?;
-    write_file(&header_path.join("parser.h"), tree_sitter::PARSER_HEADER)?;
+    parser.write_to_dir(
+        &src_path,
+        &WriteOptions {
+            overwrite: true,
+            ..Default::default()
+        },
+    )?;
 
     if !path_in_ignore(&repo_path) {
-        grammar_files::generate_grammar_files(&repo_path, &input_grammar.name, generate_bindings)?;
+        grammar_files::generate_grammar_files(&repo_path, &parser.name, generate_bindings)?;
     }
 
-    Ok(())
+    Ok(parser.warnings)
 }
 
-pub fn generate_parser_for_grammar(grammar_json: &str) -> Result<(String, String)> {
-    let grammar_json = JSON_COMMENT_REGEX.replace_all(grammar_json, "\n");
-    let input_grammar = parse_grammar(&grammar_json)?;
-    let parser =
-        generate_parser_for_grammar_with_opts(&input_grammar, tree_sitter::LANGUAGE_VERSION, None)?;
-    Ok((input_grammar.name, parser.c_code))
+/// Generate a parser for a grammar, and return the grammar's name and the
+/// parser's C code. The grammar's warnings are ignored.
+pub fn generate_parser_for_grammar(grammar_json: &str) -> Result<(String, String), GenerateError> {
+    let parser = generate_parser_for_grammar_with_options(grammar_json, &GenerateOptions::new())?;
+    Ok((parser.name, parser.c_code))
 }
 
-fn generate_parser_for_grammar_with_opts(
+/// Like [`generate_parser_for_grammar`], but configured by `options`, and
+/// returning the generated files along with the grammar's warnings, table
+/// sizes, supertypes and rule documentation.
+pub fn generate_parser_for_grammar_with_options(
+    grammar_json: &str,
+    options: &GenerateOptions,
+) -> Result<GeneratedParser, GenerateError> {
+    let input_grammar = parse_grammar(grammar_json)?;
+    let mut warnings = warnings::check_grammar(&input_grammar);
+
+    let mut conflicts = Vec::new();
+    let mut profile = options.profile.then(GenerateProfile::default);
+    let tables = generate_tables(
+        &input_grammar,
+        options.abi_version,
+        options.report_symbol_name,
+        options.collect_conflicts.then_some(&mut conflicts),
+        profile.as_mut(),
+    )?;
+    warnings.extend(tables.warnings);
+    if options.deny_warnings && !warnings.is_empty() {
+        return Err(GenerateError::Warnings(warnings));
+    }
+
+    Ok(GeneratedParser {
+        name: input_grammar.name,
+        c_code: tables.c_code,
+        node_types_json: tables.node_types_json,
+        stats: tables.stats,
+        warnings,
+        conflicts,
+        profile,
+        supertype_map: tables.supertype_map,
+        rule_docs: input_grammar.rule_docs,
+        grammar_json: grammar_json.to_string(),
+    })
+}
+
+struct GeneratedTables {
+    c_code: String,
+    node_types_json: String,
+    stats: GrammarStats,
+    supertype_map: HashMap<String, Vec<String>>,
+    warnings: Vec<GenerateWarning>,
+}
+
+fn generate_tables(
     input_grammar: &InputGrammar,
     abi_version: usize,
     report_symbol_name: Option<&str>,
-) -> Result<GeneratedParser> {
+    conflicts: Option<&mut Vec<Conflict>>,
+    mut profile: Option<&mut GenerateProfile>,
+) -> Result<GeneratedTables> {
+    let start_time = Instant::now();
     let (syntax_grammar, lexical_grammar, inlines, simple_aliases) =
         prepare_grammar(input_grammar)?;
     let variable_info =
@@ -148,6 +480,9 @@ fn generate_parser_for_grammar_with_opts(
         &simple_aliases,
         &variable_info,
     );
+    if let Some(profile) = profile.as_deref_mut() {
+        profile.prepare_grammar = start_time.elapsed();
+    }
     let tables = build_tables(
         &syntax_grammar,
         &lexical_grammar,
@@ -155,7 +490,17 @@ fn generate_parser_for_grammar_with_opts(
         &variable_info,
         &inlines,
         report_symbol_name,
+        conflicts,
+        profile.as_deref_mut(),
     )?;
+    let stats = GrammarStats {
+        parse_states: tables.parse_table.states.len(),
+        lex_states: tables.main_lex_table.states.len() + tables.keyword_lex_table.states.len(),
+        large_state_count: large_state_count(&tables.parse_table),
+        symbol_count: tables.parse_table.symbols.len(),
+    };
+    let warnings = warnings::check_tables(&tables, &lexical_grammar);
+    let render_start_time = Instant::now();
     let c_code = render_c_code(
         &input_grammar.name,
         tables,
@@ -164,9 +509,16 @@ fn generate_parser_for_grammar_with_opts(
         simple_aliases,
         abi_version,
     );
-    Ok(GeneratedParser {
+    if let Some(profile) = profile {
+        profile.render = render_start_time.elapsed();
+        profile.total = start_time.elapsed();
+    }
+    Ok(GeneratedTables {
         c_code,
         node_types_json: serde_json::to_string_pretty(&node_types_json).unwrap(),
+        stats,
+        supertype_map: node_types::get_supertype_map(&node_types_json),
+        warnings,
     })
 }
 
@@ -273,3 +625,529 @@ fn write_file(path: &Path, body: impl AsRef<[u8]>) -> Result<()> {
     fs::write(path, body)
         .with_context(|| format!("Failed to write {:?}", path.file_name().unwrap()))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+
+    use super::{
+        generate_parser_for_grammar, generate_parser_for_grammar_with_options, GenerateError,
+        GenerateOptions, GenerateWarning, GenerateWarningKind, GeneratedParser, GrammarStats,
+        WriteOptions,
+    };
+
+    const SUMS_GRAMMAR: &str = r#"{
+        "name": "sums",
+        "rules": {
+            "program": {"type": "REPEAT", "content": {"type": "SYMBOL", "name": "_expression"}},
+            "_expression": {
+                "type": "CHOICE",
+                "members": [
+                    {"type": "SYMBOL", "name": "sum"},
+                    {"type": "SYMBOL", "name": "number"}
+                ]
+            },
+            "sum": {
+                "type": "PREC_LEFT",
+                "value": 1,
+                "content": {
+                    "type": "SEQ",
+                    "members": [
+                        {"type": "SYMBOL", "name": "_expression"},
+                        {"type": "STRING", "value": "+"},
+                        {"type": "SYMBOL", "name": "_expression"}
+                    ]
+                }
+            },
+            "number": {"type": "PATTERN", "value": "\\d+"}
+        }
+    }"#;
+
+    // A grammar with an unused rule and a single-element `seq`.
+    const WORDS_GRAMMAR: &str = r#"{
+        "name": "words",
+        "extras": [{"type": "SYMBOL", "name": "comment"}],
+        "rules": {
+            "program": {"type": "REPEAT", "content": {"type": "SYMBOL", "name": "word"}},
+            "word": {"type": "PATTERN", "value": "\\w+"},
+            "number": {"type": "PATTERN", "value": "\\d+"},
+            "comment": {"type": "SEQ", "members": [{"type": "STRING", "value": "--"}]}
+        }
+    }"#;
+
+    fn generate(grammar_json: &str, options: &GenerateOptions) -> GeneratedParser {
+        generate_parser_for_grammar_with_options(grammar_json, options).unwrap()
+    }
+
+    #[test]
+    fn test_generate_node_types_for_grammar() {
+        let parser = generate(
+            r#"{
+                "name": "assignments",
+                "supertypes": ["_expression"],
+                "rules": {
+                    "program": {"type": "REPEAT", "content": {"type": "SYMBOL", "name": "assignment"}},
+                    "assignment": {
+                        "type": "SEQ",
+                        "members": [
+                            {
+                                "type": "FIELD",
+                                "name": "left",
+                                "content": {"type": "SYMBOL", "name": "identifier"}
+                            },
+                            {"type": "STRING", "value": "="},
+                            {
+                                "type": "FIELD",
+                                "name": "right",
+                                "content": {"type": "SYMBOL", "name": "_expression"}
+                            }
+                        ]
+                    },
+                    "_expression": {
+                        "type": "CHOICE",
+                        "members": [
+                            {"type": "SYMBOL", "name": "identifier"},
+                            {"type": "SYMBOL", "name": "number"}
+                        ]
+                    },
+                    "identifier": {"type": "PATTERN", "value": "[a-z]+"},
+                    "number": {"type": "PATTERN", "value": "\\d+"}
+                }
+            }"#,
+            &GenerateOptions::new(),
+        );
+
+        assert_eq!(parser.name, "assignments");
+        assert!(parser.c_code.contains("tree_sitter_assignments"));
+
+        let node_types = serde_json::from_str::<Vec<Value>>(&parser.node_types_json).unwrap();
+        let node_type = |kind: &str| {
+            node_types
+                .iter()
+                .find(|node_type| node_type["type"] == kind)
+                .unwrap()
+        };
+
+        assert_eq!(
+            node_type("_expression"),
+            &json!({
+                "type": "_expression",
+                "named": true,
+                "subtypes": [
+                    {"type": "identifier", "named": true},
+                    {"type": "number", "named": true},
+                ],
+            })
+        );
+        assert_eq!(
+            node_type("assignment"),
+            &json!({
+                "type": "assignment",
+                "named": true,
+                "fields": {
+                    "left": {
+                        "multiple": false,
+                        "required": true,
+                        "types": [{"type": "identifier", "named": true}],
+                    },
+                    "right": {
+                        "multiple": false,
+                        "required": true,
+                        "types": [{"type": "_expression", "named": true}],
+                    },
+                },
+            })
+        );
+        assert_eq!(node_type("=")["named"], false);
+    }
+
+    #[test]
+    fn test_generate_parser_for_grammar_with_profile() {
+        let parser = generate(SUMS_GRAMMAR, &GenerateOptions::new().profile(true));
+        assert_eq!(
+            parser.c_code,
+            generate_parser_for_grammar(SUMS_GRAMMAR).unwrap().1
+        );
+        assert!(generate(SUMS_GRAMMAR, &GenerateOptions::new())
+            .profile
+            .is_none());
+
+        let profile = parser.profile.unwrap();
+        let phases = [
+            profile.prepare_grammar,
+            profile.build_parse_table,
+            profile.minimize_parse_table,
+            profile.build_lex_table,
+            profile.render,
+        ];
+        let phase_total = phases.iter().sum::<Duration>();
+        assert!(phases.iter().all(|phase| !phase.is_zero()), "{profile:?}");
+        assert!(phase_total <= profile.total, "{profile:?}");
+        assert!(
+            profile.total - phase_total < profile.total / 10 + Duration::from_millis(1),
+            "{profile:?}"
+        );
+    }
+
+    #[test]
+    fn test_generate_parser_for_grammar_with_stats() {
+        let GeneratedParser { c_code, stats, .. } = generate(SUMS_GRAMMAR, &GenerateOptions::new());
+        assert_eq!(
+            stats,
+            GrammarStats {
+                parse_states: 8,
+                lex_states: 4,
+                large_state_count: 4,
+                symbol_count: 7,
+            }
+        );
+
+        // The counts match the ones written to the parser.
+        for (define, count) in [
+            ("STATE_COUNT", stats.parse_states),
+            ("LARGE_STATE_COUNT", stats.large_state_count),
+            ("SYMBOL_COUNT", stats.symbol_count),
+        ] {
+            assert!(
+                c_code.contains(&format!("#define {define} {count}\n")),
+                "{define}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_parser_for_grammar_with_warnings() {
+        let warnings = generate(WORDS_GRAMMAR, &GenerateOptions::new()).warnings;
+        assert_eq!(
+            warnings,
+            [
+                GenerateWarning {
+                    kind: GenerateWarningKind::UnusedRule,
+                    rule: "number".to_string(),
+                },
+                GenerateWarning {
+                    kind: GenerateWarningKind::SingleElementRule,
+                    rule: "comment".to_string(),
+                },
+            ]
+        );
+
+        let error = generate_parser_for_grammar_with_options(
+            WORDS_GRAMMAR,
+            &GenerateOptions::new().deny_warnings(true),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(&error, GenerateError::Warnings(denied) if *denied == warnings),
+            "unexpected error: {error}"
+        );
+        assert_eq!(
+            error.to_string(),
+            "Grammar has 2 warning(s):\n  Rule `number` is unused\n  Rule `comment` is just a `seq` or `choice` rule with a single element. This is unnecessary."
+        );
+
+        // Without the unused rule, the grammar still has the other warning.
+        let grammar_json =
+            WORDS_GRAMMAR.replace(r#""number": {"type": "PATTERN", "value": "\\d+"},"#, "");
+        let warnings = generate(&grammar_json, &GenerateOptions::new()).warnings;
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, GenerateWarningKind::SingleElementRule);
+    }
+
+    #[test]
+    fn test_generate_parser_for_grammar_with_shadowed_tokens() {
+        // Wherever a `name` is valid, an `identifier` is too, and it takes
+        // precedence over it.
+        let grammar_json = r#"{
+            "name": "names",
+            "rules": {
+                "program": {
+                    "type": "REPEAT",
+                    "content": {
+                        "type": "CHOICE",
+                        "members": [
+                            {"type": "SYMBOL", "name": "identifier"},
+                            {"type": "SYMBOL", "name": "name"}
+                        ]
+                    }
+                },
+                "identifier": {
+                    "type": "TOKEN",
+                    "content": {
+                        "type": "PREC",
+                        "value": 1,
+                        "content": {"type": "PATTERN", "value": "[a-z]+"}
+                    }
+                },
+                "name": {"type": "PATTERN", "value": "[a-z]+"}
+            }
+        }"#;
+        let warnings = generate(grammar_json, &GenerateOptions::new()).warnings;
+        assert_eq!(
+            warnings,
+            [GenerateWarning {
+                kind: GenerateWarningKind::ShadowedToken,
+                rule: "name".to_string(),
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "Token `name` is shadowed by other tokens, so it is never lexed"
+        );
+
+        let error = generate_parser_for_grammar_with_options(
+            grammar_json,
+            &GenerateOptions::new().deny_warnings(true),
+        )
+        .unwrap_err();
+        assert!(matches!(error, GenerateError::Warnings(denied) if denied == warnings));
+
+        // The parser doesn't need `name` when it's only valid on its own.
+        let grammar_json = grammar_json.replace(
+            r#"{"type": "SYMBOL", "name": "identifier"},"#,
+            r#"{"type": "SEQ", "members": [{"type": "STRING", "value": ":"}, {"type": "SYMBOL", "name": "identifier"}]},"#,
+        );
+        assert_eq!(
+            generate(&grammar_json, &GenerateOptions::new()).warnings,
+            []
+        );
+    }
+
+    #[test]
+    fn test_generate_parser_and_write_to_dir() {
+        let parser = generate(WORDS_GRAMMAR, &GenerateOptions::new());
+        let temp_dir = tempfile::tempdir().unwrap();
+        let out_dir = temp_dir.path().join("src");
+
+        let paths = parser
+            .write_to_dir(
+                &out_dir,
+                &WriteOptions {
+                    write_grammar_json: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            paths,
+            [
+                out_dir.join("parser.c"),
+                out_dir.join("node-types.json"),
+                out_dir.join("tree_sitter/alloc.h"),
+                out_dir.join("tree_sitter/array.h"),
+                out_dir.join("tree_sitter/parser.h"),
+                out_dir.join("grammar.json"),
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(out_dir.join("grammar.json")).unwrap(),
+            WORDS_GRAMMAR
+        );
+        let c_code = std::fs::read_to_string(out_dir.join("parser.c")).unwrap();
+        assert!(c_code.contains("tree_sitter_words"));
+        assert!(c_code.contains("#include \"tree_sitter/parser.h\""));
+        assert_eq!(
+            std::fs::read_to_string(out_dir.join("tree_sitter/parser.h")).unwrap(),
+            tree_sitter::PARSER_HEADER
+        );
+
+        // Without `overwrite`, existing files are reported.
+        let error = parser
+            .write_to_dir(&out_dir, &WriteOptions::default())
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("parser.c"),
+            "unexpected error: {error}"
+        );
+
+        let paths = parser
+            .write_to_dir(
+                &out_dir,
+                &WriteOptions {
+                    overwrite: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(paths.len(), 5);
+    }
+
+    #[test]
+    fn test_generate_parser_for_grammar_with_supertypes() {
+        let supertype_map = generate(
+            r#"{
+                "name": "expressions",
+                "supertypes": ["_expression", "_literal"],
+                "rules": {
+                    "program": {"type": "SYMBOL", "name": "_expression"},
+                    "_expression": {
+                        "type": "CHOICE",
+                        "members": [
+                            {"type": "SYMBOL", "name": "binary_expression"},
+                            {"type": "SYMBOL", "name": "call_expression"},
+                            {"type": "SYMBOL", "name": "_literal"}
+                        ]
+                    },
+                    "binary_expression": {
+                        "type": "PREC_LEFT",
+                        "value": 1,
+                        "content": {
+                            "type": "SEQ",
+                            "members": [
+                                {"type": "SYMBOL", "name": "_expression"},
+                                {"type": "STRING", "value": "+"},
+                                {"type": "SYMBOL", "name": "_expression"}
+                            ]
+                        }
+                    },
+                    "call_expression": {
+                        "type": "SEQ",
+                        "members": [
+                            {"type": "SYMBOL", "name": "identifier"},
+                            {"type": "STRING", "value": "("},
+                            {"type": "STRING", "value": ")"}
+                        ]
+                    },
+                    "_literal": {
+                        "type": "CHOICE",
+                        "members": [
+                            {"type": "SYMBOL", "name": "identifier"},
+                            {"type": "SYMBOL", "name": "number"}
+                        ]
+                    },
+                    "identifier": {"type": "PATTERN", "value": "[a-z]+"},
+                    "number": {"type": "PATTERN", "value": "\\d+"}
+                }
+            }"#,
+            &GenerateOptions::new(),
+        )
+        .supertype_map;
+
+        assert_eq!(supertype_map.len(), 2);
+        assert_eq!(
+            supertype_map["_expression"],
+            ["_literal", "binary_expression", "call_expression"]
+        );
+        assert_eq!(supertype_map["_literal"], ["identifier", "number"]);
+    }
+
+    #[test]
+    fn test_generate_parser_for_grammar_with_rule_docs() {
+        let docs = generate(
+            r#"{
+                // doc: This comment is not attached to a rule.
+                "name": "documented",
+                "rules": {
+                    // doc: A sequence of statements.
+                    "program": {"type": "REPEAT", "content": {"type": "SYMBOL", "name": "statement"}},
+                    // doc: A single statement.
+                    // doc: Statements end with a semicolon.
+                    "statement": {
+                        "type": "SEQ",
+                        "members": [
+                            {"type": "SYMBOL", "name": "identifier"},
+                            {"type": "STRING", "value": "="},
+                            {"type": "SYMBOL", "name": "value"},
+                            {
+                                "type": "STRING",
+                                // doc: This comment is inside of a rule.
+                                "value": ";"
+                            }
+                        ]
+                    },
+                    // A comment that is not documentation.
+                    "identifier": {"type": "PATTERN", "value": "[a-z]+"},
+                    "value": {"type": "PATTERN", "value": "\\d+"}
+                }
+            }"#,
+            &GenerateOptions::new(),
+        )
+        .rule_docs;
+
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs["program"], "A sequence of statements.");
+        assert_eq!(
+            docs["statement"],
+            "A single statement.\nStatements end with a semicolon."
+        );
+        assert!(!docs.contains_key("identifier"));
+        assert!(!docs.contains_key("value"));
+    }
+
+    #[test]
+    fn test_generate_parser_for_grammar_with_conflicts() {
+        let grammar_json = r#"{
+            "name": "two_conflicts",
+            "rules": {
+                "program": {
+                    "type": "CHOICE",
+                    "members": [
+                        {"type": "SYMBOL", "name": "sum"},
+                        {"type": "SYMBOL", "name": "concatenation"}
+                    ]
+                },
+                "sum": {
+                    "type": "CHOICE",
+                    "members": [
+                        {
+                            "type": "SEQ",
+                            "members": [
+                                {"type": "SYMBOL", "name": "sum"},
+                                {"type": "STRING", "value": "+"},
+                                {"type": "SYMBOL", "name": "sum"}
+                            ]
+                        },
+                        {"type": "PATTERN", "value": "\\d+"}
+                    ]
+                },
+                "concatenation": {
+                    "type": "CHOICE",
+                    "members": [
+                        {
+                            "type": "SEQ",
+                            "members": [
+                                {"type": "SYMBOL", "name": "concatenation"},
+                                {"type": "STRING", "value": "~"},
+                                {"type": "SYMBOL", "name": "concatenation"}
+                            ]
+                        },
+                        {"type": "PATTERN", "value": "[a-z]+"}
+                    ]
+                }
+            }
+        }"#;
+
+        // Normal generation stops at the first conflict.
+        assert!(matches!(
+            generate_parser_for_grammar(grammar_json),
+            Err(GenerateError::Conflict { .. })
+        ));
+
+        let conflicts = generate(
+            grammar_json,
+            &GenerateOptions::new().collect_conflicts(true),
+        )
+        .conflicts;
+        let reported = conflicts
+            .iter()
+            .map(|conflict| (conflict.symbols.clone(), conflict.lookahead.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reported,
+            [
+                (vec!["sum".to_string()], "'+'"),
+                (vec!["concatenation".to_string()], "'~'"),
+            ]
+        );
+
+        for conflict in &conflicts {
+            assert!(conflict.state_id > 0);
+            assert_eq!(conflict.preceding_symbols.len(), 3);
+            assert!(conflict
+                .message
+                .starts_with("Unresolved conflict for symbol sequence"));
+        }
+    }
+}
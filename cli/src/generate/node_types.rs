@@ -367,6 +367,24 @@ pub fn get_variable_info(
     Ok(result)
 }
 
+/// Map the name of each supertype in the given node types to the names of
+/// its subtypes.
+pub fn get_supertype_map(node_types_json: &[NodeInfoJSON]) -> HashMap<String, Vec<String>> {
+    node_types_json
+        .iter()
+        .filter_map(|node_type| {
+            let subtypes = node_type.subtypes.as_ref()?;
+            Some((
+                node_type.kind.clone(),
+                subtypes
+                    .iter()
+                    .map(|subtype| subtype.kind.clone())
+                    .collect(),
+            ))
+        })
+        .collect()
+}
+
 pub fn generate_node_types_json(
     syntax_grammar: &SyntaxGrammar,
     lexical_grammar: &LexicalGrammar,
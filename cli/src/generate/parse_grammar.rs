@@ -1,12 +1,24 @@
+use std::collections::HashMap;
+
 use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
 use serde::Deserialize;
 use serde_json::{Map, Value};
 
 use super::{
     grammars::{InputGrammar, PrecedenceEntry, Variable, VariableType},
     rules::{Precedence, Rule},
+    GenerateError,
 };
 
+lazy_static! {
+    static ref JSON_COMMENT_REGEX: Regex = RegexBuilder::new("^\\s*//.*")
+        .multi_line(true)
+        .build()
+        .unwrap();
+}
+
 #[derive(Deserialize)]
 #[serde(tag = "type")]
 #[allow(non_camel_case_types)]
@@ -94,15 +106,21 @@ pub(crate) struct GrammarJSON {
     word: Option<String>,
 }
 
+/// Parse a `grammar.json` file. Lines that start with `//` are comments, and
+/// `// doc:` comments directly above an entry of the `rules` object document
+/// that rule.
 pub(crate) fn parse_grammar(input: &str) -> Result<InputGrammar> {
-    let grammar_json = serde_json::from_str::<GrammarJSON>(input)?;
+    let rule_docs = parse_rule_docs(input);
+    let grammar_json =
+        serde_json::from_str::<GrammarJSON>(&JSON_COMMENT_REGEX.replace_all(input, "\n"))
+            .map_err(GenerateError::InvalidJson)?;
 
     let mut variables = Vec::with_capacity(grammar_json.rules.len());
     for (name, value) in grammar_json.rules {
         variables.push(Variable {
             name: name.clone(),
             kind: VariableType::Named,
-            rule: parse_rule(serde_json::from_value(value)?),
+            rule: parse_rule(serde_json::from_value(value).map_err(GenerateError::InvalidJson)?),
         });
     }
 
@@ -151,9 +169,73 @@ pub(crate) fn parse_grammar(input: &str) -> Result<InputGrammar> {
         variables,
         extra_symbols,
         external_tokens,
+        rule_docs,
     })
 }
 
+// Find the `// doc:` comments that directly precede a key of the top-level
+// `rules` object. Consecutive doc comments are joined with newlines, and any
+// other token or comment in between detaches them from the key.
+fn parse_rule_docs(input: &str) -> HashMap<String, String> {
+    let mut docs = HashMap::new();
+    let mut doc_lines = Vec::new();
+    // The open objects and arrays, and whether the next string is a key.
+    let mut containers = Vec::new();
+    let mut expect_key = false;
+    let mut in_rules = false;
+
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        if let Some(comment) = rest.strip_prefix("//") {
+            let end = comment.find('\n').unwrap_or(comment.len());
+            match comment[..end].trim_start().strip_prefix("doc:") {
+                Some(line) => doc_lines.push(line.strip_prefix(' ').unwrap_or(line).trim_end()),
+                None => doc_lines.clear(),
+            }
+            rest = &comment[end..];
+            continue;
+        }
+
+        if c == '"' {
+            let mut escaped = false;
+            let end = rest[1..]
+                .find(|c| {
+                    let is_end = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    is_end
+                })
+                .map_or(rest.len(), |i| i + 2);
+            if expect_key {
+                let key = serde_json::from_str::<String>(&rest[..end]).unwrap_or_default();
+                if containers.len() == 1 {
+                    in_rules = key == "rules";
+                } else if containers.len() == 2 && in_rules && !doc_lines.is_empty() {
+                    docs.insert(key, doc_lines.join("\n"));
+                }
+                expect_key = false;
+            }
+            rest = &rest[end..];
+        } else {
+            match c {
+                '{' | '[' => containers.push(c),
+                '}' | ']' => {
+                    containers.pop();
+                }
+                _ => {}
+            }
+            expect_key = matches!(c, '{' | ',') && containers.last() == Some(&'{');
+            rest = &rest[c.len_utf8()..];
+        }
+        doc_lines.clear();
+    }
+    docs
+}
+
 fn parse_rule(json: RuleJSON) -> Rule {
     match json {
         RuleJSON::ALIAS {
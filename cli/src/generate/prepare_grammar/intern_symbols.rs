@@ -4,6 +4,7 @@ use super::InternedGrammar;
 use crate::generate::{
     grammars::{InputGrammar, Variable, VariableType},
     rules::{Rule, Symbol},
+    GenerateError,
 };
 
 pub(super) fn intern_symbols(grammar: &InputGrammar) -> Result<InternedGrammar> {
@@ -18,13 +19,13 @@ pub(super) fn intern_symbols(grammar: &InputGrammar) -> Result<InternedGrammar>
         variables.push(Variable {
             name: variable.name.clone(),
             kind: variable_type_for_name(&variable.name),
-            rule: interner.intern_rule(&variable.rule, Some(&variable.name))?,
+            rule: interner.intern_rule(&variable.rule)?,
         });
     }
 
     let mut external_tokens = Vec::with_capacity(grammar.external_tokens.len());
     for external_token in &grammar.external_tokens {
-        let rule = interner.intern_rule(external_token, None)?;
+        let rule = interner.intern_rule(external_token)?;
         let (name, kind) = if let Rule::NamedSymbol(name) = external_token {
             (name.clone(), variable_type_for_name(name))
         } else {
@@ -35,7 +36,7 @@ pub(super) fn intern_symbols(grammar: &InputGrammar) -> Result<InternedGrammar>
 
     let mut extra_symbols = Vec::with_capacity(grammar.extra_symbols.len());
     for extra_token in &grammar.extra_symbols {
-        extra_symbols.push(interner.intern_rule(extra_token, None)?);
+        extra_symbols.push(interner.intern_rule(extra_token)?);
     }
 
     let mut supertype_symbols = Vec::with_capacity(grammar.supertype_symbols.len());
@@ -43,7 +44,7 @@ pub(super) fn intern_symbols(grammar: &InputGrammar) -> Result<InternedGrammar>
         supertype_symbols.push(
             interner
                 .intern_name(supertype_symbol_name)
-                .ok_or_else(|| anyhow!("Undefined symbol `{supertype_symbol_name}`"))?,
+                .ok_or_else(|| GenerateError::UndefinedSymbol(supertype_symbol_name.clone()))?,
         );
     }
 
@@ -54,7 +55,7 @@ pub(super) fn intern_symbols(grammar: &InputGrammar) -> Result<InternedGrammar>
             interned_conflict.push(
                 interner
                     .intern_name(name)
-                    .ok_or_else(|| anyhow!("Undefined symbol `{name}`"))?,
+                    .ok_or_else(|| GenerateError::UndefinedSymbol(name.clone()))?,
             );
         }
         expected_conflicts.push(interned_conflict);
@@ -72,7 +73,7 @@ pub(super) fn intern_symbols(grammar: &InputGrammar) -> Result<InternedGrammar>
         word_token = Some(
             interner
                 .intern_name(name)
-                .ok_or_else(|| anyhow!("Undefined symbol `{name}`"))?,
+                .ok_or_else(|| GenerateError::UndefinedSymbol(name.clone()))?,
         );
     }
 
@@ -99,31 +100,29 @@ struct Interner<'a> {
 }
 
 impl<'a> Interner<'a> {
-    fn intern_rule(&self, rule: &Rule, name: Option<&str>) -> Result<Rule> {
+    fn intern_rule(&self, rule: &Rule) -> Result<Rule> {
         match rule {
             Rule::Choice(elements) => {
-                self.check_single(elements, name);
                 let mut result = Vec::with_capacity(elements.len());
                 for element in elements {
-                    result.push(self.intern_rule(element, name)?);
+                    result.push(self.intern_rule(element)?);
                 }
                 Ok(Rule::Choice(result))
             }
             Rule::Seq(elements) => {
-                self.check_single(elements, name);
                 let mut result = Vec::with_capacity(elements.len());
                 for element in elements {
-                    result.push(self.intern_rule(element, name)?);
+                    result.push(self.intern_rule(element)?);
                 }
                 Ok(Rule::Seq(result))
             }
-            Rule::Repeat(content) => Ok(Rule::Repeat(Box::new(self.intern_rule(content, name)?))),
+            Rule::Repeat(content) => Ok(Rule::Repeat(Box::new(self.intern_rule(content)?))),
             Rule::Metadata { rule, params } => Ok(Rule::Metadata {
-                rule: Box::new(self.intern_rule(rule, name)?),
+                rule: Box::new(self.intern_rule(rule)?),
                 params: params.clone(),
             }),
             Rule::NamedSymbol(name) => self.intern_name(name).map_or_else(
-                || Err(anyhow!("Undefined symbol `{name}`")),
+                || Err(GenerateError::UndefinedSymbol(name.clone()).into()),
                 |symbol| Ok(Rule::Symbol(symbol)),
             ),
             _ => Ok(rule.clone()),
@@ -147,17 +146,6 @@ impl<'a> Interner<'a> {
 
         None
     }
-
-    // In the case of a seq or choice rule of 1 element in a hidden rule, weird
-    // inconsistent behavior with queries can occur. So we should warn the user about it.
-    fn check_single(&self, elements: &[Rule], name: Option<&str>) {
-        if elements.len() == 1 && matches!(elements[0], Rule::String(_) | Rule::Pattern(_, _)) {
-            eprintln!(
-                "Warning: rule {} is just a `seq` or `choice` rule with a single element. This is unnecessary.",
-                name.unwrap_or_default()
-            );
-        }
-    }
 }
 
 fn variable_type_for_name(name: &str) -> VariableType {
@@ -245,7 +233,13 @@ mod tests {
         let result = intern_symbols(&build_grammar(vec![Variable::named("x", Rule::named("y"))]));
 
         match result {
-            Err(e) => assert_eq!(e.to_string(), "Undefined symbol `y`"),
+            Err(e) => {
+                assert_eq!(e.to_string(), "Undefined symbol `y`");
+                assert!(matches!(
+                    e.downcast_ref(),
+                    Some(GenerateError::UndefinedSymbol(name)) if name == "y"
+                ));
+            }
             _ => panic!("Expected an error but got none"),
         }
     }
@@ -27,6 +27,7 @@ use super::{
         SyntaxGrammar, Variable,
     },
     rules::{AliasMap, Precedence, Rule, Symbol},
+    GenerateError,
 };
 
 pub struct IntermediateGrammar<T, U> {
@@ -132,9 +133,11 @@ fn validate_precedences(grammar: &InputGrammar) -> Result<()> {
                     }
                     hash_map::Entry::Occupied(e) => {
                         if e.get() != &ordering {
-                            return Err(anyhow!(
-                                "Conflicting orderings for precedences {entry1} and {entry2}",
-                            ));
+                            return Err(GenerateError::PrecedenceCycle(
+                                entry1.to_string(),
+                                entry2.to_string(),
+                            )
+                            .into());
                         }
                     }
                 }
@@ -243,10 +246,14 @@ mod tests {
             ..Default::default()
         };
 
-        let result = validate_precedences(&grammar);
+        let error = validate_precedences(&grammar).unwrap_err();
         assert_eq!(
-            result.unwrap_err().to_string(),
+            error.to_string(),
             "Conflicting orderings for precedences 'a' and 'b'",
         );
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(GenerateError::PrecedenceCycle(..))
+        ));
     }
 }
@@ -266,18 +266,7 @@ impl Generator {
             });
         }
 
-        // Determine which states should use the "small state" representation, and which should
-        // use the normal array representation.
-        let threshold = cmp::min(SMALL_STATE_THRESHOLD, self.parse_table.symbols.len() / 2);
-        self.large_state_count = self
-            .parse_table
-            .states
-            .iter()
-            .enumerate()
-            .take_while(|(i, s)| {
-                *i <= 1 || s.terminal_entries.len() + s.nonterminal_entries.len() > threshold
-            })
-            .count();
+        self.large_state_count = large_state_count(&self.parse_table);
     }
 
     fn add_includes(&mut self) {
@@ -1674,6 +1663,20 @@ impl Generator {
     }
 }
 
+/// Determine how many of the parse table's states should use the normal array representation.
+/// The remaining states use the "small state" representation.
+pub fn large_state_count(parse_table: &ParseTable) -> usize {
+    let threshold = cmp::min(SMALL_STATE_THRESHOLD, parse_table.symbols.len() / 2);
+    parse_table
+        .states
+        .iter()
+        .enumerate()
+        .take_while(|(i, s)| {
+            *i <= 1 || s.terminal_entries.len() + s.nonterminal_entries.len() > threshold
+        })
+        .count()
+}
+
 /// Returns a String of C code for the given components of a parser.
 ///
 /// # Arguments
@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use super::{
+    build_tables::Tables,
+    grammars::{InputGrammar, LexicalGrammar},
+    rules::Rule,
+    GenerateWarning, GenerateWarningKind,
+};
+
+/// Find the problems in a grammar that don't prevent a parser from being
+/// generated, in the order of the grammar's rules.
+pub(super) fn check_grammar(grammar: &InputGrammar) -> Vec<GenerateWarning> {
+    let mut warnings = Vec::new();
+
+    // Rules are used if they can be reached from the start rule, or from the
+    // extras, the external tokens, the word token, or the supertypes.
+    let mut used_rules = HashSet::new();
+    let mut stack = grammar
+        .variables
+        .first()
+        .map(|variable| variable.name.as_str())
+        .into_iter()
+        .chain(grammar.word_token.as_deref())
+        .chain(grammar.supertype_symbols.iter().map(String::as_str))
+        .collect::<Vec<_>>();
+    for rule in grammar.extra_symbols.iter().chain(&grammar.external_tokens) {
+        add_symbol_names(rule, &mut stack);
+    }
+    while let Some(name) = stack.pop() {
+        if used_rules.insert(name) {
+            if let Some(variable) = grammar.variables.iter().find(|v| v.name == name) {
+                add_symbol_names(&variable.rule, &mut stack);
+            }
+        }
+    }
+
+    for variable in &grammar.variables {
+        if !used_rules.contains(variable.name.as_str()) {
+            warnings.push(GenerateWarning {
+                kind: GenerateWarningKind::UnusedRule,
+                rule: variable.name.clone(),
+            });
+        }
+        if has_single_element_seq_or_choice(&variable.rule) {
+            warnings.push(GenerateWarning {
+                kind: GenerateWarningKind::SingleElementRule,
+                rule: variable.name.clone(),
+            });
+        }
+    }
+    warnings
+}
+
+/// Find the tokens that are valid in some parse state, but that the lexer
+/// never produces, in the order of the lexical grammar. The first parse state
+/// is skipped, because it is used for error recovery, where every token is
+/// valid.
+pub(super) fn check_tables(
+    tables: &Tables,
+    lexical_grammar: &LexicalGrammar,
+) -> Vec<GenerateWarning> {
+    let lexed_tokens = tables
+        .main_lex_table
+        .states
+        .iter()
+        .chain(&tables.keyword_lex_table.states)
+        .filter_map(|state| state.accept_action)
+        .collect::<HashSet<_>>();
+    let mut shadowed_tokens = tables.parse_table.states[1..]
+        .iter()
+        .flat_map(|state| state.terminal_entries.keys())
+        .filter(|symbol| symbol.is_terminal() && !lexed_tokens.contains(symbol))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    shadowed_tokens.sort_unstable();
+    shadowed_tokens
+        .into_iter()
+        .map(|symbol| GenerateWarning {
+            kind: GenerateWarningKind::ShadowedToken,
+            rule: lexical_grammar.variables[symbol.index].name.clone(),
+        })
+        .collect()
+}
+
+fn add_symbol_names<'a>(rule: &'a Rule, names: &mut Vec<&'a str>) {
+    match rule {
+        Rule::NamedSymbol(name) => names.push(name),
+        Rule::Choice(elements) | Rule::Seq(elements) => {
+            for element in elements {
+                add_symbol_names(element, names);
+            }
+        }
+        Rule::Metadata { rule, .. } | Rule::Repeat(rule) => add_symbol_names(rule, names),
+        Rule::Blank | Rule::String(_) | Rule::Pattern(..) | Rule::Symbol(_) => {}
+    }
+}
+
+// In the case of a seq or choice rule of 1 element, weird inconsistent
+// behavior with queries can occur.
+fn has_single_element_seq_or_choice(rule: &Rule) -> bool {
+    match rule {
+        Rule::Choice(elements) | Rule::Seq(elements) => {
+            (elements.len() == 1 && matches!(elements[0], Rule::String(_) | Rule::Pattern(..)))
+                || elements.iter().any(has_single_element_seq_or_choice)
+        }
+        Rule::Metadata { rule, .. } | Rule::Repeat(rule) => has_single_element_seq_or_choice(rule),
+        Rule::Blank
+        | Rule::String(_)
+        | Rule::Pattern(..)
+        | Rule::NamedSymbol(_)
+        | Rule::Symbol(_) => false,
+    }
+}
@@ -13,7 +13,9 @@ use anyhow::Result;
 use lazy_static::lazy_static;
 use serde::{ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{json, Value};
-use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter, HtmlRenderer};
+use tree_sitter_highlight::{
+    Error, HighlightConfiguration, HighlightEvent, Highlighter, HtmlRenderer,
+};
 use tree_sitter_loader::Loader;
 
 pub const HTML_HEADER: &str = "
@@ -426,6 +428,72 @@ pub fn html(
     Ok(())
 }
 
+pub fn scopes(
+    loader: &Loader,
+    theme: &Theme,
+    source: &[u8],
+    config: &HighlightConfiguration,
+    print_time: bool,
+    cancellation_flag: Option<&AtomicUsize>,
+) -> Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let time = Instant::now();
+    let mut highlighter = Highlighter::new();
+
+    let events = highlighter.highlight(config, source, cancellation_flag, |string| {
+        loader.highlight_config_for_injection_string(string)
+    })?;
+    write_scopes(&mut stdout, source, events, &theme.highlight_names)?;
+
+    if print_time {
+        eprintln!("Time: {}ms", time.elapsed().as_millis());
+    }
+
+    Ok(())
+}
+
+/// Write one `start_row start_col length highlight_name` line for each
+/// highlighted token in `source`, using the innermost highlight of each token.
+///
+/// Rows and columns are zero-based, and columns and lengths are measured in
+/// bytes. Tokens that span several lines are split at each line break, so
+/// that every token lies on a single line.
+pub fn write_scopes(
+    output: &mut impl io::Write,
+    source: &[u8],
+    events: impl Iterator<Item = Result<HighlightEvent, Error>>,
+    highlight_names: &[String],
+) -> Result<()> {
+    let mut highlight_stack = Vec::new();
+    let mut row = 0;
+    let mut column = 0;
+    for event in events {
+        match event? {
+            HighlightEvent::HighlightStart(highlight) => highlight_stack.push(highlight),
+            HighlightEvent::HighlightEnd => {
+                highlight_stack.pop();
+            }
+            HighlightEvent::Source { start, end } => {
+                for (i, line) in source[start..end].split(|byte| *byte == b'\n').enumerate() {
+                    if i > 0 {
+                        row += 1;
+                        column = 0;
+                    }
+                    if let Some(highlight) = highlight_stack.last() {
+                        if !line.is_empty() {
+                            let name = &highlight_names[highlight.0];
+                            writeln!(output, "{row} {column} {} {name}", line.len())?;
+                        }
+                    }
+                    column += line.len();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::env;
@@ -1,6 +1,7 @@
 use std::{
     collections::HashSet,
     env, fs,
+    io::{self, IsTerminal},
     path::{Path, PathBuf},
 };
 
@@ -100,6 +101,10 @@ struct Generate {
         help = "The name or path of the JavaScript runtime to use for generating parsers"
     )]
     pub js_runtime: Option<String>,
+    #[arg(long, help = "Fail if the grammar has warnings, such as unused rules")]
+    pub deny_warnings: bool,
+    #[arg(long, help = "Don't print the grammar's warnings")]
+    pub no_warnings: bool,
 }
 
 #[derive(Args)]
@@ -161,12 +166,37 @@ struct Parse {
         help = "Output the parse data in XML format"
     )]
     pub output_xml: bool,
+    #[arg(long, help = "Output the parse tree in JSON format")]
+    pub output_json: bool,
+    #[arg(
+        long,
+        conflicts_with = "indent",
+        help = "Output the JSON parse tree on a single line"
+    )]
+    pub compact: bool,
+    #[arg(
+        long,
+        default_value_t = 2,
+        help = "The number of spaces used to indent the JSON parse tree"
+    )]
+    pub indent: usize,
     #[arg(long, short, help = "Show parsing statistic")]
     pub stat: bool,
     #[arg(long, help = "Interrupt the parsing process by timeout (µs)")]
     pub timeout: Option<u64>,
     #[arg(long, short, help = "Measure execution time")]
     pub time: bool,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Parse each file the given number of times, reapplying any edits to the previous tree"
+    )]
+    pub repeat: usize,
+    #[arg(
+        long = "stats",
+        help = "Show the time taken by each repeated parse, and the final node count"
+    )]
+    pub repeat_stats: bool,
     #[arg(long, short, help = "Suppress main output")]
     pub quiet: bool,
     #[arg(
@@ -187,6 +217,11 @@ struct Parse {
     #[arg(long, short = 'n', help = "Parse the contents of a specific test")]
     #[clap(conflicts_with = "paths", conflicts_with = "paths_file")]
     pub test_number: Option<u32>,
+    #[arg(
+        long,
+        help = "Compare the parse tree with an s-expression saved in the given file, and fail if they differ"
+    )]
+    pub baseline: Option<PathBuf>,
 }
 
 #[derive(Args)]
@@ -234,6 +269,12 @@ struct Test {
     pub config_path: Option<PathBuf>,
     #[arg(long, help = "Force showing fields in test diffs")]
     pub show_fields: bool,
+    #[arg(
+        long,
+        env = "TREE_SITTER_TEST_JSON",
+        help = "Write the result of each corpus test to the given file as JSON lines, instead of printing diffs"
+    )]
+    pub json_output: Option<PathBuf>,
 }
 
 #[derive(Args)]
@@ -297,6 +338,12 @@ struct Query {
     pub captures: bool,
     #[arg(long, help = "Whether to run query tests or not")]
     pub test: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["paths", "paths_file"],
+        help = "Only check that the query is valid for the language, without running it"
+    )]
+    pub check: bool,
     #[arg(long, help = "The path to an alternative config.json file")]
     pub config_path: Option<PathBuf>,
 }
@@ -306,6 +353,12 @@ struct Query {
 struct Highlight {
     #[arg(long, short = 'H', help = "Generate highlighting as an HTML document")]
     pub html: bool,
+    #[arg(
+        long,
+        conflicts_with = "html",
+        help = "List each highlighted token as `start_row start_col length highlight_name`"
+    )]
+    pub scopes: bool,
     #[arg(
         long,
         help = "Check that highlighting captures conform strictly to standards"
@@ -427,7 +480,7 @@ fn run() -> Result<()> {
     let current_dir = env::current_dir().unwrap();
     let mut loader = loader::Loader::new()?;
 
-    let color = env::var("NO_COLOR").map_or(true, |v| v != "1");
+    let color = env::var("NO_COLOR").map_or(true, |v| v.is_empty()) && io::stdout().is_terminal();
 
     match command {
         Commands::InitConfig(_) => {
@@ -461,14 +514,20 @@ fn run() -> Result<()> {
                     }
                 },
             );
-            generate::generate_parser_in_directory(
+            let warnings = generate::generate_parser_in_directory(
                 &current_dir,
                 generate_options.grammar_path.as_deref(),
                 abi_version,
                 !generate_options.no_bindings,
                 generate_options.report_states_for_rule.as_deref(),
                 generate_options.js_runtime.as_deref(),
+                generate_options.deny_warnings,
             )?;
+            if !generate_options.no_warnings {
+                for warning in warnings {
+                    eprintln!("Warning: {warning}");
+                }
+            }
             if generate_options.build {
                 if let Some(path) = generate_options.libdir {
                     loader = loader::Loader::with_parser_lib_path(PathBuf::from(path));
@@ -538,6 +597,8 @@ fn run() -> Result<()> {
                 ParseOutput::Dot
             } else if parse_options.output_xml {
                 ParseOutput::Xml
+            } else if parse_options.output_json {
+                ParseOutput::Json
             } else if parse_options.quiet {
                 ParseOutput::Quiet
             } else {
@@ -571,6 +632,13 @@ fn run() -> Result<()> {
             }
 
             let timeout = parse_options.timeout.unwrap_or_default();
+            let baseline = parse_options
+                .baseline
+                .map(|path| {
+                    fs::read_to_string(&path)
+                        .with_context(|| format!("Failed to read baseline file {path:?}"))
+                })
+                .transpose()?;
 
             let (paths, language) = if let Some(target_test) = parse_options.test_number {
                 let (test_path, language_names) = test::get_tmp_test_file(target_test, color)?;
@@ -619,12 +687,17 @@ fn run() -> Result<()> {
                     max_path_length,
                     output,
                     print_time: time,
+                    repeat: parse_options.repeat,
+                    print_repeat_stats: parse_options.repeat_stats,
                     timeout,
                     debug: parse_options.debug,
                     debug_graph: parse_options.debug_graph,
                     cancellation_flag: Some(&cancellation_flag),
                     encoding,
                     open_log: parse_options.open_log,
+                    baseline: baseline.as_deref(),
+                    json_indent: (!parse_options.compact).then_some(parse_options.indent),
+                    use_color: color,
                 };
 
                 let parse_result = parse::parse_file_at_path(&mut parser, &opts)?;
@@ -634,13 +707,16 @@ fn run() -> Result<()> {
                     if parse_result.successful {
                         stats.successful_parses += 1;
                     }
+                    if !parse_result.matches_baseline {
+                        stats.baseline_mismatches += 1;
+                    }
                     if let Some(duration) = parse_result.duration {
                         stats.total_bytes += parse_result.bytes;
                         stats.total_duration += duration;
                     }
                 }
 
-                has_error |= !parse_result.successful;
+                has_error |= !parse_result.successful || !parse_result.matches_baseline;
             }
 
             if should_track_stats {
@@ -669,10 +745,9 @@ fn run() -> Result<()> {
             }
 
             let languages = loader.languages_at_path(&current_dir)?;
-            let language = &languages
+            let (language, language_name) = languages
                 .first()
-                .ok_or_else(|| anyhow!("No language found"))?
-                .0;
+                .ok_or_else(|| anyhow!("No language found"))?;
             parser.set_language(language)?;
 
             let test_dir = current_dir.join("test");
@@ -689,9 +764,12 @@ fn run() -> Result<()> {
                     update: test_options.update,
                     open_log: test_options.open_log,
                     languages: languages.iter().map(|(l, n)| (n.as_str(), l)).collect(),
+                    default_language: language_name,
                     color,
                     test_num: 1,
                     show_fields: test_options.show_fields,
+                    json_output: test_options.json_output,
+                    fail_fast: test::fail_fast_from_env()?,
                 };
 
                 test::run_tests_at_path(&mut parser, &mut opts)?;
@@ -759,15 +837,26 @@ fn run() -> Result<()> {
 
         Commands::Query(query_options) => {
             let config = Config::load(query_options.config_path)?;
-            let paths = collect_paths(query_options.paths_file.as_deref(), query_options.paths)?;
             let loader_config = config.get()?;
             loader.find_all_languages(&loader_config)?;
+            let query_path = Path::new(&query_options.query_path);
+
+            if query_options.check {
+                let language = loader.select_language(
+                    query_path,
+                    &current_dir,
+                    query_options.scope.as_deref(),
+                )?;
+                query::check_query_at_path(&language, query_path)?;
+                return Ok(());
+            }
+
+            let paths = collect_paths(query_options.paths_file.as_deref(), query_options.paths)?;
             let language = loader.select_language(
                 Path::new(&paths[0]),
                 &current_dir,
                 query_options.scope.as_deref(),
             )?;
-            let query_path = Path::new(&query_options.query_path);
 
             let byte_range = query_options.byte_range.as_ref().and_then(|range| {
                 let mut parts = range.split(':');
@@ -875,7 +964,16 @@ fn run() -> Result<()> {
                     }
 
                     let source = fs::read(path)?;
-                    if html_mode {
+                    if highlight_options.scopes {
+                        highlight::scopes(
+                            &loader,
+                            &theme_config.theme,
+                            &source,
+                            highlight_config,
+                            highlight_options.time,
+                            Some(&cancellation_flag),
+                        )?;
+                    } else if html_mode {
                         highlight::html(
                             &loader,
                             &theme_config.theme,
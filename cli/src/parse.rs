@@ -7,10 +7,21 @@ use std::{
 };
 
 use anyhow::{anyhow, Context, Result};
-use tree_sitter::{ffi, InputEdit, Language, LogType, Parser, Point, Tree};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter, PrettyFormatter};
+use tree_sitter::{ffi, format_sexp, InputEdit, Language, LogType, Parser, Point, Tree};
 
 use super::util;
-use crate::fuzz::edits::Edit;
+use crate::{
+    fuzz::edits::Edit,
+    test::{normalize_sexp, print_diff, print_diff_key, strip_sexp_fields},
+};
+
+lazy_static! {
+    static ref NODE_RANGE_REGEX: Regex = Regex::new(r"\s*\[\d+, \d+\] - \[\d+, \d+\]").unwrap();
+}
 
 #[derive(Debug, Default)]
 pub struct Stats {
@@ -18,12 +29,15 @@ pub struct Stats {
     pub total_parses: usize,
     pub total_bytes: usize,
     pub total_duration: Duration,
+    /// The number of parses whose tree differed from the `--baseline`. These
+    /// are counted separately from failed parses, which contain errors.
+    pub baseline_mismatches: usize,
 }
 
 impl fmt::Display for Stats {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let duration_us = self.total_duration.as_micros();
-        writeln!(
+        write!(
             f,
             "Total parses: {}; successful parses: {}; failed parses: {}; success percentage: {:.2}%; average speed: {} bytes/ms",
             self.total_parses,
@@ -35,7 +49,11 @@ impl fmt::Display for Stats {
             } else {
                 0
             }
-        )
+        )?;
+        if self.baseline_mismatches > 0 {
+            write!(f, "; baseline mismatches: {}", self.baseline_mismatches)?;
+        }
+        writeln!(f)
     }
 }
 
@@ -44,6 +62,7 @@ pub enum ParseOutput {
     Normal,
     Quiet,
     Xml,
+    Json,
     Dot,
 }
 
@@ -54,19 +73,60 @@ pub struct ParseFileOptions<'a> {
     pub max_path_length: usize,
     pub output: ParseOutput,
     pub print_time: bool,
+    pub repeat: usize,
+    pub print_repeat_stats: bool,
     pub timeout: u64,
     pub debug: bool,
     pub debug_graph: bool,
     pub cancellation_flag: Option<&'a AtomicUsize>,
     pub encoding: Option<u32>,
     pub open_log: bool,
+    pub baseline: Option<&'a str>,
+    pub json_indent: Option<usize>,
+    pub use_color: bool,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct ParseResult {
     pub successful: bool,
+    /// Whether the tree matched the baseline. This is also true if there was
+    /// no baseline, or no tree to compare with it. A parse can be successful
+    /// and still differ from the baseline.
+    pub matches_baseline: bool,
     pub bytes: usize,
     pub duration: Option<Duration>,
+    pub repeat_stats: Option<RepeatStats>,
+}
+
+/// The timing of each parse of a file that was parsed repeatedly, along with
+/// the number of nodes in the final tree.
+#[derive(Clone, Debug)]
+pub struct RepeatStats {
+    pub durations: Vec<Duration>,
+    pub node_count: usize,
+}
+
+impl fmt::Display for RepeatStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_micros() as f64 / 1e3;
+        for (i, duration) in self.durations.iter().enumerate() {
+            writeln!(f, "parse {}: {:.2} ms", i + 1, ms(*duration))?;
+        }
+        let min = self.durations.iter().min().copied().unwrap_or_default();
+        let max = self.durations.iter().max().copied().unwrap_or_default();
+        let total = self.durations.iter().sum::<Duration>();
+        let mean = total
+            .checked_div(self.durations.len() as u32)
+            .unwrap_or_default();
+        writeln!(
+            f,
+            "min: {:.2} ms; mean: {:.2} ms; max: {:.2} ms; nodes: {}",
+            ms(min),
+            ms(mean),
+            ms(max),
+            self.node_count
+        )
+    }
 }
 
 pub fn parse_file_at_path(parser: &mut Parser, opts: &ParseFileOptions) -> Result<ParseResult> {
@@ -96,14 +156,12 @@ pub fn parse_file_at_path(parser: &mut Parser, opts: &ParseFileOptions) -> Resul
         })));
     }
 
-    let time = Instant::now();
-
     #[inline(always)]
     fn is_utf16_bom(bom_bytes: &[u8]) -> bool {
         bom_bytes == [0xFF, 0xFE] || bom_bytes == [0xFE, 0xFF]
     }
 
-    let tree = match opts.encoding {
+    let parse_source = |parser: &mut Parser, source_code: &[u8]| match opts.encoding {
         Some(encoding) if encoding == ffi::TSInputEncodingUTF16 => {
             let source_code_utf16 = source_code
                 .chunks_exact(2)
@@ -118,30 +176,51 @@ pub fn parse_file_at_path(parser: &mut Parser, opts: &ParseFileOptions) -> Resul
                 .collect::<Vec<_>>();
             parser.parse_utf16(&source_code_utf16, None)
         }
-        _ => parser.parse(&source_code, None),
+        _ => parser.parse(source_code, None),
     };
 
-    parser.stop_printing_dot_graphs();
-
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
 
-    if let Some(mut tree) = tree {
-        if opts.debug_graph && !opts.edits.is_empty() {
+    // With `--repeat`, the file is parsed several times. If there are edits,
+    // every parse after the first applies them again, reusing the previous tree.
+    let mut durations = Vec::with_capacity(opts.repeat.max(1));
+    let mut tree = None;
+    let mut time = Instant::now();
+    for iteration in 0..opts.repeat.max(1) {
+        let mut current_tree = match tree.take() {
+            Some(tree) if !opts.edits.is_empty() => tree,
+            _ => {
+                let tree = parse_source(parser, &source_code);
+                parser.stop_printing_dot_graphs();
+                match tree {
+                    Some(tree) => tree,
+                    None => break,
+                }
+            }
+        };
+
+        if opts.debug_graph && !opts.edits.is_empty() && iteration == 0 {
             println!("BEFORE:\n{}", String::from_utf8_lossy(&source_code));
         }
 
         for (i, edit) in opts.edits.iter().enumerate() {
             let edit = parse_edit_flag(&source_code, edit)?;
-            perform_edit(&mut tree, &mut source_code, &edit)?;
-            tree = parser.parse(&source_code, Some(&tree)).unwrap();
+            perform_edit(&mut current_tree, &mut source_code, &edit)?;
+            current_tree = parser.parse(&source_code, Some(&current_tree)).unwrap();
 
             if opts.debug_graph {
                 println!("AFTER {i}:\n{}", String::from_utf8_lossy(&source_code));
             }
         }
 
-        let duration = time.elapsed();
+        durations.push(time.elapsed());
+        tree = Some(current_tree);
+        time = Instant::now();
+    }
+
+    if let Some(tree) = tree {
+        let duration = *durations.last().unwrap();
         let duration_ms = duration.as_micros() as f64 / 1e3;
         let mut cursor = tree.walk();
 
@@ -295,6 +374,11 @@ pub fn parse_file_at_path(parser: &mut Parser, opts: &ParseFileOptions) -> Resul
             println!();
         }
 
+        if opts.output == ParseOutput::Json {
+            write_tree_json(&mut stdout, &tree, opts.json_indent)?;
+            writeln!(&mut stdout)?;
+        }
+
         if opts.output == ParseOutput::Dot {
             util::print_tree_graph(&tree, "log.html", opts.open_log).unwrap();
         }
@@ -349,10 +433,31 @@ pub fn parse_file_at_path(parser: &mut Parser, opts: &ParseFileOptions) -> Resul
             writeln!(&mut stdout)?;
         }
 
+        let mut matches_baseline = true;
+        if let Some(baseline) = opts.baseline {
+            if let Some((actual, expected)) = diff_against_baseline(&tree, baseline) {
+                matches_baseline = false;
+                println!("{}: differs from the baseline", opts.path.display());
+                print_diff_key(opts.use_color);
+                print_diff(&actual, &expected, opts.use_color);
+                println!();
+            }
+        }
+
+        let repeat_stats = opts.print_repeat_stats.then(|| RepeatStats {
+            durations,
+            node_count: tree.root_node().descendant_count(),
+        });
+        if let Some(repeat_stats) = &repeat_stats {
+            write!(&mut stdout, "{repeat_stats}")?;
+        }
+
         return Ok(ParseResult {
             successful: first_error.is_none(),
+            matches_baseline,
             bytes: source_code.len(),
             duration: Some(duration),
+            repeat_stats,
         });
     }
 
@@ -369,11 +474,148 @@ pub fn parse_file_at_path(parser: &mut Parser, opts: &ParseFileOptions) -> Resul
 
     Ok(ParseResult {
         successful: false,
+        matches_baseline: true,
         bytes: source_code.len(),
         duration: None,
+        repeat_stats: None,
     })
 }
 
+/// Compare a tree with a previously saved s-expression of it, returning the
+/// formatted actual and expected s-expressions if they differ.
+///
+/// The baseline may be in the format printed by the `parse` command, with
+/// node ranges, or in the format of a corpus test. As in corpus tests, field
+/// names are only compared if the baseline contains any.
+#[must_use]
+pub fn diff_against_baseline(tree: &Tree, baseline: &str) -> Option<(String, String)> {
+    let expected = normalize_sexp(&NODE_RANGE_REGEX.replace_all(baseline, ""));
+    let mut actual = tree.root_node().to_sexp();
+    if expected == strip_sexp_fields(&expected) {
+        actual = strip_sexp_fields(&actual);
+    }
+    (actual != expected).then(|| (format_sexp(&actual, 0), format_sexp(&expected, 0)))
+}
+
+/// Write a syntax tree as a JSON object, in which each node lists its
+/// position, its field name within its parent, and all of its children,
+/// including anonymous ones.
+///
+/// The JSON is pretty-printed with the given number of spaces per level of
+/// indentation, or written on a single line if `indent` is `None`.
+pub fn write_tree_json(writer: &mut impl Write, tree: &Tree, indent: Option<usize>) -> Result<()> {
+    if let Some(indent) = indent {
+        let indent = " ".repeat(indent);
+        write_tree_json_with_formatter(
+            writer,
+            tree,
+            PrettyFormatter::with_indent(indent.as_bytes()),
+        )?;
+    } else {
+        write_tree_json_with_formatter(writer, tree, CompactFormatter)?;
+    }
+    Ok(())
+}
+
+// The tree is walked with a cursor rather than recursively, so that deeply
+// nested trees can't overflow the stack. Only the nodes and their `children`
+// arrays are written by hand; each of the other values is serialized with a
+// copy of the formatter, which indents it at the current depth.
+fn write_tree_json_with_formatter<F: Formatter + Clone>(
+    writer: &mut impl Write,
+    tree: &Tree,
+    mut formatter: F,
+) -> io::Result<()> {
+    let mut cursor = tree.walk();
+    loop {
+        let node = cursor.node();
+        formatter.begin_object(writer)?;
+        write_json_entry(writer, &mut formatter, "type", node.kind(), true)?;
+        write_json_entry(
+            writer,
+            &mut formatter,
+            "start_byte",
+            &node.start_byte(),
+            false,
+        )?;
+        write_json_entry(writer, &mut formatter, "end_byte", &node.end_byte(), false)?;
+        write_json_entry(
+            writer,
+            &mut formatter,
+            "start_point",
+            &node.start_position(),
+            false,
+        )?;
+        write_json_entry(
+            writer,
+            &mut formatter,
+            "end_point",
+            &node.end_position(),
+            false,
+        )?;
+        write_json_entry(writer, &mut formatter, "field", &cursor.field_name(), false)?;
+        write_json_entry(writer, &mut formatter, "named", &node.is_named(), false)?;
+        write_json_entry(writer, &mut formatter, "is_error", &node.is_error(), false)?;
+        write_json_entry(
+            writer,
+            &mut formatter,
+            "is_missing",
+            &node.is_missing(),
+            false,
+        )?;
+        formatter.begin_object_key(writer, false)?;
+        write_json_value(writer, &formatter, "children")?;
+        formatter.end_object_key(writer)?;
+        formatter.begin_object_value(writer)?;
+        formatter.begin_array(writer)?;
+
+        if cursor.goto_first_child() {
+            formatter.begin_array_value(writer, true)?;
+            continue;
+        }
+
+        // Close the node, along with each ancestor whose last child it is.
+        loop {
+            formatter.end_array(writer)?;
+            formatter.end_object_value(writer)?;
+            formatter.end_object(writer)?;
+            if cursor.goto_next_sibling() {
+                formatter.end_array_value(writer)?;
+                formatter.begin_array_value(writer, false)?;
+                break;
+            }
+            if !cursor.goto_parent() {
+                return Ok(());
+            }
+            formatter.end_array_value(writer)?;
+        }
+    }
+}
+
+fn write_json_entry<F: Formatter + Clone>(
+    writer: &mut impl Write,
+    formatter: &mut F,
+    key: &str,
+    value: &(impl Serialize + ?Sized),
+    first: bool,
+) -> io::Result<()> {
+    formatter.begin_object_key(writer, first)?;
+    write_json_value(writer, formatter, key)?;
+    formatter.end_object_key(writer)?;
+    formatter.begin_object_value(writer)?;
+    write_json_value(writer, formatter, value)?;
+    formatter.end_object_value(writer)
+}
+
+fn write_json_value<F: Formatter + Clone>(
+    writer: &mut impl Write,
+    formatter: &F,
+    value: &(impl Serialize + ?Sized),
+) -> io::Result<()> {
+    let mut serializer = serde_json::Serializer::with_formatter(&mut *writer, formatter.clone());
+    value.serialize(&mut serializer).map_err(io::Error::from)
+}
+
 pub fn perform_edit(tree: &mut Tree, input: &mut Vec<u8>, edit: &Edit) -> Result<InputEdit> {
     let start_byte = edit.position;
     let old_end_byte = edit.position + edit.deleted_length;
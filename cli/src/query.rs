@@ -1,4 +1,5 @@
 use std::{
+    collections::HashSet,
     fs,
     io::{self, Write},
     ops::Range,
@@ -6,11 +7,116 @@ use std::{
     time::Instant,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
 use tree_sitter::{Language, Parser, Point, Query, QueryCursor};
 
 use crate::query_testing;
 
+lazy_static! {
+    static ref INHERITS_REGEX: Regex = Regex::new(r"^\s*;+\s*inherits\s*:\s*(.*)$").unwrap();
+}
+
+/// Check that the query at the given path compiles for the given language,
+/// without running it on any source files.
+///
+/// The returned error gives the position, byte offset, and kind of the first
+/// problem in the query, such as a node type or field that the language does
+/// not define.
+pub fn check_query_at_path(language: &Language, query_path: &Path) -> Result<()> {
+    let query_source = fs::read_to_string(query_path)
+        .with_context(|| format!("Error reading query file {query_path:?}"))?;
+    Query::new(language, &query_source).map_err(|e| {
+        anyhow!(
+            "{}:{}:{}: {:?} error at byte offset {}\n{e}",
+            query_path.display(),
+            e.row + 1,
+            e.column + 1,
+            e.kind,
+            e.offset,
+        )
+    })?;
+    Ok(())
+}
+
+/// Compile a query whose source may inherit from other queries.
+///
+/// The leading comment lines of a query can contain `; inherits: name1,name2`
+/// directives. The source of each named query, as returned by `resolve`, is
+/// placed before the query's own source, in order. Inherited queries can
+/// inherit from other queries in turn, but each one is only included once.
+/// An error is returned if a query can't be resolved, or if a query
+/// inherits from itself. The positions in query errors refer to the combined
+/// source, as returned by [`resolve_query_inherits`].
+pub fn query_with_inherits(
+    language: &Language,
+    source: &str,
+    resolve: impl FnMut(&str) -> Option<String>,
+) -> Result<Query> {
+    let source = resolve_query_inherits(source, resolve)?;
+    Ok(Query::new(language, &source)?)
+}
+
+/// Concatenate a query's source with the sources of the queries that it
+/// inherits from, as described in [`query_with_inherits`].
+pub fn resolve_query_inherits(
+    source: &str,
+    mut resolve: impl FnMut(&str) -> Option<String>,
+) -> Result<String> {
+    fn add_query(
+        source: &str,
+        resolve: &mut dyn FnMut(&str) -> Option<String>,
+        stack: &mut Vec<String>,
+        included: &mut HashSet<String>,
+        result: &mut String,
+    ) -> Result<()> {
+        let inherited_names = source
+            .lines()
+            .take_while(|line| line.trim_start().starts_with(';'))
+            .filter_map(|line| INHERITS_REGEX.captures(line))
+            .flat_map(|captures| {
+                captures[1]
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+        for name in inherited_names {
+            if stack.contains(&name) {
+                return Err(anyhow!(
+                    "Circular query inheritance: {} -> {name}",
+                    stack.join(" -> ")
+                ));
+            }
+            if included.contains(&name) {
+                continue;
+            }
+            let inherited_source =
+                resolve(&name).ok_or_else(|| anyhow!("Failed to resolve query {name:?}"))?;
+            stack.push(name);
+            add_query(&inherited_source, resolve, stack, included, result)?;
+            included.insert(stack.pop().unwrap());
+        }
+        result.push_str(source);
+        if !result.ends_with('\n') {
+            result.push('\n');
+        }
+        Ok(())
+    }
+
+    let mut result = String::new();
+    add_query(
+        source,
+        &mut resolve,
+        &mut vec!["<query>".to_string()],
+        &mut HashSet::new(),
+        &mut result,
+    )?;
+    Ok(result)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn query_files_at_paths(
     language: &Language,
@@ -32,10 +138,10 @@ pub fn query_files_at_paths(
 
     let mut query_cursor = QueryCursor::new();
     if let Some(range) = byte_range {
-        query_cursor.set_byte_range(range);
+        query_cursor.set_byte_range(range)?;
     }
     if let Some(range) = point_range {
-        query_cursor.set_point_range(range);
+        query_cursor.set_point_range(range)?;
     }
 
     let mut parser = Parser::new();
@@ -1,6 +1,8 @@
 use std::{
     collections::BTreeMap,
+    env,
     ffi::OsStr,
+    fmt::{self, Write as _},
     fs,
     io::{self, Write},
     path::{Path, PathBuf},
@@ -9,12 +11,14 @@ use std::{
 
 use anstyle::{AnsiColor, Color, Style};
 use anyhow::{anyhow, Context, Result};
+use glob::Pattern;
 use indoc::indoc;
 use lazy_static::lazy_static;
 use regex::{
     bytes::{Regex as ByteRegex, RegexBuilder as ByteRegexBuilder},
     Regex,
 };
+use serde::Serialize;
 use similar::{ChangeTag, TextDiff};
 use tree_sitter::{format_sexp, Language, LogType, Parser, Query};
 use walkdir::WalkDir;
@@ -83,6 +87,32 @@ impl Default for TestEntry {
     }
 }
 
+/// Renders the group and example hierarchy as an indented outline, with the
+/// length of each example's input.
+impl fmt::Display for TestEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_outline(f, 0)
+    }
+}
+
+impl TestEntry {
+    fn fmt_outline(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        match self {
+            Self::Group { name, children, .. } => {
+                writeln!(f, "{indent}{name}/")?;
+                for child in children {
+                    child.fmt_outline(f, depth + 1)?;
+                }
+                Ok(())
+            }
+            Self::Example { name, input, .. } => {
+                writeln!(f, "{indent}{name} ({} bytes)", input.len())
+            }
+        }
+    }
+}
+
 impl Default for TestAttributes {
     fn default() -> Self {
         Self {
@@ -104,9 +134,48 @@ pub struct TestOptions<'a> {
     pub update: bool,
     pub open_log: bool,
     pub languages: BTreeMap<&'a str, &'a Language>,
+    /// The name of the language in `languages` that the parser starts with,
+    /// and that examples without a `:language` attribute are parsed with.
+    pub default_language: &'a str,
     pub color: bool,
     pub test_num: usize,
     pub show_fields: bool,
+    pub json_output: Option<PathBuf>,
+    /// Stop at the first failing example, and panic with its diff once the
+    /// results have been written. See [`fail_fast_from_env`].
+    pub fail_fast: bool,
+}
+
+/// Read the `TREE_SITTER_TEST_FAIL_FAST` environment variable. `1` and `true`
+/// enable fail-fast mode, and `0`, `false` or an empty value disable it, as
+/// does leaving the variable unset.
+pub fn fail_fast_from_env() -> Result<bool> {
+    const NAME: &str = "TREE_SITTER_TEST_FAIL_FAST";
+    match env::var(NAME) {
+        Ok(value) => parse_bool_env_value(&value)
+            .ok_or_else(|| anyhow!("Invalid value for {NAME}: {value:?}")),
+        Err(env::VarError::NotPresent) => Ok(false),
+        Err(error) => Err(error).with_context(|| format!("Failed to read {NAME}")),
+    }
+}
+
+fn parse_bool_env_value(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" => Some(true),
+        "" | "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// The outcome of running a single corpus example, as written to the file
+/// given by [`TestOptions::json_output`].
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct TestResult {
+    pub language: String,
+    pub name: String,
+    pub passed: bool,
+    pub expected_sexp: String,
+    pub actual_sexp: String,
 }
 
 pub fn run_tests_at_path(parser: &mut Parser, opts: &mut TestOptions) -> Result<()> {
@@ -126,6 +195,7 @@ pub fn run_tests_at_path(parser: &mut Parser, opts: &mut TestOptions) -> Result<
 
     let mut failures = Vec::new();
     let mut corrected_entries = Vec::new();
+    let mut results = Vec::new();
     let mut has_parse_errors = false;
     run_tests(
         parser,
@@ -134,11 +204,24 @@ pub fn run_tests_at_path(parser: &mut Parser, opts: &mut TestOptions) -> Result<
         0,
         &mut failures,
         &mut corrected_entries,
+        &mut results,
         &mut has_parse_errors,
     )?;
 
     parser.stop_printing_dot_graphs();
 
+    if let Some(json_output) = &opts.json_output {
+        let mut file = fs::File::create(json_output)
+            .with_context(|| format!("Failed to create {json_output:?}"))?;
+        write_results_to_buffer(&mut file, &results)?;
+    }
+
+    if opts.fail_fast {
+        if let Some((name, actual, expected)) = failures.first() {
+            panic!("{name}:\n{}", failure_message(actual, expected));
+        }
+    }
+
     if failures.is_empty() {
         Ok(())
     } else {
@@ -167,9 +250,12 @@ pub fn run_tests_at_path(parser: &mut Parser, opts: &mut TestOptions) -> Result<
                 }
             }
 
-            if opts.color {
-                print_diff_key();
+            // The results file already describes every failure in detail.
+            if opts.json_output.is_some() {
+                return Err(anyhow!(""));
             }
+
+            print_diff_key(opts.color);
             for (i, (name, actual, expected)) in failures.iter().enumerate() {
                 if expected == "NO ERROR" {
                     println!("\n  {}. {name}:\n", i + 1);
@@ -279,49 +365,72 @@ pub fn check_queries_at_path(language: &Language, path: &Path) -> Result<()> {
     Ok(())
 }
 
-pub fn print_diff_key() {
-    println!(
-        "\ncorrect / {} / {}",
-        paint(Some(AnsiColor::Green), "expected"),
-        paint(Some(AnsiColor::Red), "unexpected")
-    );
+pub fn print_diff_key(use_color: bool) {
+    if use_color {
+        println!(
+            "\ncorrect / {} / {}",
+            paint(Some(AnsiColor::Green), "expected"),
+            paint(Some(AnsiColor::Red), "unexpected")
+        );
+    } else {
+        println!("\n correct / +expected / -unexpected");
+    }
 }
 
 pub fn print_diff(actual: &str, expected: &str, use_color: bool) {
+    println!("{}", format_diff(actual, expected, use_color));
+}
+
+/// Format the line-by-line difference between an actual and an expected
+/// S-expression, as printed by [`print_diff`].
+#[must_use]
+pub fn format_diff(actual: &str, expected: &str, use_color: bool) -> String {
+    let mut result = String::new();
     let diff = TextDiff::from_lines(actual, expected);
     for diff in diff.iter_all_changes() {
         match diff.tag() {
             ChangeTag::Equal => {
                 if use_color {
-                    print!("{diff}");
+                    write!(result, "{diff}").unwrap();
                 } else {
-                    print!(" {diff}");
+                    write!(result, " {diff}").unwrap();
                 }
             }
             ChangeTag::Insert => {
                 if use_color {
-                    print!("{}", paint(Some(AnsiColor::Green), diff.as_str().unwrap()));
+                    result.push_str(&paint(Some(AnsiColor::Green), diff.as_str().unwrap()));
                 } else {
-                    print!("+{diff}");
+                    write!(result, "+{diff}").unwrap();
                 }
                 if diff.missing_newline() {
-                    println!();
+                    result.push('\n');
                 }
             }
             ChangeTag::Delete => {
                 if use_color {
-                    print!("{}", paint(Some(AnsiColor::Red), diff.as_str().unwrap()));
+                    result.push_str(&paint(Some(AnsiColor::Red), diff.as_str().unwrap()));
                 } else {
-                    print!("-{diff}");
+                    write!(result, "-{diff}").unwrap();
                 }
                 if diff.missing_newline() {
-                    println!();
+                    result.push('\n');
                 }
             }
         }
     }
+    result
+}
 
-    println!();
+// Describe a failing example without color, for fail-fast mode's panic.
+fn failure_message(actual: &str, expected: &str) -> String {
+    if expected == "NO ERROR" {
+        format!(
+            "Expected an ERROR node, but got:\n{}",
+            format_sexp(actual, 2)
+        )
+    } else {
+        format_diff(&format_sexp(actual, 2), &format_sexp(expected, 2), false)
+    }
 }
 
 pub fn paint(color: Option<AnsiColor>, text: &str) -> String {
@@ -338,6 +447,7 @@ fn run_tests(
     mut indent_level: i32,
     failures: &mut Vec<(String, String, String)>,
     corrected_entries: &mut Vec<(String, String, String, String, usize, usize)>,
+    results: &mut Vec<TestResult>,
     has_parse_errors: &mut bool,
 ) -> Result<bool> {
     match test_entry {
@@ -371,6 +481,9 @@ fn run_tests(
                 return Ok(true);
             }
 
+            // An example without a `:language` attribute, or with an empty
+            // one, is parsed with the last language that was set.
+            let mut current_language = opts.default_language;
             for (i, language_name) in attributes.languages.iter().enumerate() {
                 if !language_name.is_empty() {
                     let language = opts
@@ -378,10 +491,22 @@ fn run_tests(
                         .get(language_name.as_ref())
                         .ok_or_else(|| anyhow!("Language not found: {language_name}"))?;
                     parser.set_language(language)?;
+                    current_language = language_name;
                 }
                 let tree = parser.parse(&input, None).unwrap();
+                let language_name = current_language;
 
                 if attributes.error {
+                    if opts.json_output.is_some() {
+                        results.push(TestResult {
+                            language: language_name.to_string(),
+                            name: name.clone(),
+                            passed: tree.root_node().has_error(),
+                            expected_sexp: output.clone(),
+                            actual_sexp: tree.root_node().to_sexp(),
+                        });
+                    }
+
                     if tree.root_node().has_error() {
                         println!(
                             "{:>3}.  {}",
@@ -426,7 +551,7 @@ fn run_tests(
                         ));
                     }
 
-                    if attributes.fail_fast {
+                    if attributes.fail_fast || opts.fail_fast {
                         return Ok(false);
                     }
                 } else {
@@ -435,6 +560,16 @@ fn run_tests(
                         actual = strip_sexp_fields(&actual);
                     }
 
+                    if opts.json_output.is_some() {
+                        results.push(TestResult {
+                            language: language_name.to_string(),
+                            name: name.clone(),
+                            passed: actual == output,
+                            expected_sexp: output.clone(),
+                            actual_sexp: actual.clone(),
+                        });
+                    }
+
                     if actual == output {
                         println!(
                             "{:>3}. ✓ {}",
@@ -500,15 +635,15 @@ fn run_tests(
                         }
                         failures.push((name.clone(), actual, output.clone()));
 
-                        if attributes.fail_fast {
+                        if attributes.fail_fast || opts.fail_fast {
                             return Ok(false);
                         }
                     }
                 }
 
                 if i == attributes.languages.len() - 1 {
-                    // reset to the first language
-                    parser.set_language(opts.languages.values().next().unwrap())?;
+                    // reset to the default language
+                    parser.set_language(opts.languages[opts.default_language])?;
                 }
             }
             opts.test_num += 1;
@@ -590,6 +725,7 @@ fn run_tests(
                     indent_level,
                     failures,
                     corrected_entries,
+                    results,
                     has_parse_errors,
                 )? {
                     // fail fast
@@ -654,6 +790,14 @@ fn write_tests_to_buffer(
     Ok(())
 }
 
+fn write_results_to_buffer(buffer: &mut impl Write, results: &[TestResult]) -> Result<()> {
+    for result in results {
+        serde_json::to_writer(&mut *buffer, result)?;
+        writeln!(buffer)?;
+    }
+    Ok(())
+}
+
 pub fn parse_tests(path: &Path) -> io::Result<TestEntry> {
     let name = path
         .file_stem()
@@ -689,11 +833,115 @@ pub fn parse_tests(path: &Path) -> io::Result<TestEntry> {
     }
 }
 
+/// Like [`parse_tests`], but only keeps the examples whose names contain
+/// `filter`, or match it as a glob pattern.
+///
+/// Groups are kept, except those that no longer contain any examples.
+pub fn parse_tests_filtered(path: &Path, filter: Option<&str>) -> io::Result<TestEntry> {
+    let mut entry = parse_tests(path)?;
+    if let Some(filter) = filter {
+        let pattern = Pattern::new(filter).ok();
+        let matches =
+            |name: &str| name.contains(filter) || pattern.as_ref().is_some_and(|p| p.matches(name));
+        if let TestEntry::Group { children, .. } = &mut entry {
+            retain_matching_examples(children, &matches);
+        }
+    }
+    Ok(entry)
+}
+
+fn retain_matching_examples(entries: &mut Vec<TestEntry>, matches: &impl Fn(&str) -> bool) {
+    entries.retain_mut(|entry| match entry {
+        TestEntry::Group { children, .. } => {
+            retain_matching_examples(children, matches);
+            !children.is_empty()
+        }
+        TestEntry::Example { name, .. } => matches(name),
+    });
+}
+
+/// Like [`parse_tests`], but yields the examples one at a time, in the same
+/// order, instead of building the whole group hierarchy up front.
+///
+/// Directories are walked lazily, and each corpus file is only read once the
+/// examples of the previous files have been consumed.
+pub fn parse_tests_iter(path: &Path) -> impl Iterator<Item = io::Result<TestEntry>> {
+    TestExamples {
+        pending_paths: vec![path.to_path_buf()],
+        examples: Vec::new().into_iter(),
+    }
+}
+
+struct TestExamples {
+    pending_paths: Vec<PathBuf>,
+    examples: std::vec::IntoIter<TestEntry>,
+}
+
+impl TestExamples {
+    fn visit(&mut self, path: PathBuf) -> io::Result<()> {
+        if path.is_dir() {
+            let mut children = Vec::new();
+            for entry in fs::read_dir(&path)? {
+                let entry = entry?;
+                let hidden = entry.file_name().to_str().unwrap_or("").starts_with('.');
+                if !hidden {
+                    children.push(entry.path());
+                }
+            }
+            // The paths are popped from the end, so push them in reverse order.
+            children.sort_by(|a, b| {
+                b.file_name()
+                    .unwrap_or_default()
+                    .cmp(a.file_name().unwrap_or_default())
+            });
+            self.pending_paths.extend(children);
+        } else {
+            let name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_string();
+            let content = fs::read_to_string(&path)?;
+            if let TestEntry::Group { children, .. } =
+                parse_test_content(name, &content, Some(path))
+            {
+                self.examples = children.into_iter();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for TestExamples {
+    type Item = io::Result<TestEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(example) = self.examples.next() {
+                return Some(Ok(example));
+            }
+            let path = self.pending_paths.pop()?;
+            if let Err(e) = self.visit(path) {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
 #[must_use]
 pub fn strip_sexp_fields(sexp: &str) -> String {
     SEXP_FIELD_REGEX.replace_all(sexp, " (").to_string()
 }
 
+/// Remove the comments from an expected s-expression and normalize its
+/// whitespace, so that it can be compared with the output of `to_sexp`.
+#[must_use]
+pub fn normalize_sexp(sexp: &str) -> String {
+    let sexp = COMMENT_REGEX.replace_all(sexp, "");
+    let sexp = WHITESPACE_REGEX.replace_all(sexp.trim(), " ");
+    sexp.replace(" )", ")")
+}
+
 #[must_use]
 pub fn strip_points(sexp: &str) -> String {
     POINT_REGEX.replace_all(sexp, "").to_string()
@@ -859,12 +1107,7 @@ fn parse_test_content(name: String, content: &str, file_path: Option<PathBuf>) -
                         input.pop();
                     }
 
-                    // Remove all comments
-                    let output = COMMENT_REGEX.replace_all(output, "").to_string();
-
-                    // Normalize the whitespace in the expected output.
-                    let output = WHITESPACE_REGEX.replace_all(output.trim(), " ");
-                    let output = output.replace(" )", ")");
+                    let output = normalize_sexp(output);
 
                     // Identify if the expected output has fields indicated. If not, then
                     // fields will not be checked.
@@ -1116,6 +1359,94 @@ output 2
         );
     }
 
+    #[test]
+    fn test_write_tests_to_buffer_preserves_delimiters_and_attributes() {
+        let content = r"
+=====
+title 1
+:skip
+=====
+input 1
+---
+
+(old_output)
+
+==========
+title 2
+==========
+input 2
+--------
+
+(old_output)
+"
+        .trim_start();
+        let TestEntry::Group { children, .. } =
+            parse_test_content("the-filename".to_string(), content, None)
+        else {
+            panic!("expected a group");
+        };
+
+        let corrected_entries = children
+            .into_iter()
+            .map(|child| match child {
+                TestEntry::Example {
+                    name,
+                    input,
+                    header_delim_len,
+                    divider_delim_len,
+                    attributes_str,
+                    ..
+                } => (
+                    name,
+                    String::from_utf8(input).unwrap(),
+                    "(new_output)".to_string(),
+                    attributes_str,
+                    header_delim_len,
+                    divider_delim_len,
+                ),
+                TestEntry::Group { .. } => panic!("expected an example"),
+            })
+            .collect::<Vec<_>>();
+
+        let mut buffer = Vec::new();
+        write_tests_to_buffer(&mut buffer, &corrected_entries).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            content.replace("(old_output)", "(new_output)")
+        );
+    }
+
+    #[test]
+    fn test_write_results_to_buffer() {
+        let mut buffer = Vec::new();
+        let results = vec![
+            TestResult {
+                language: "json".to_string(),
+                name: "title 1".to_string(),
+                passed: true,
+                expected_sexp: "(document)".to_string(),
+                actual_sexp: "(document)".to_string(),
+            },
+            TestResult {
+                language: "json".to_string(),
+                name: "title 2".to_string(),
+                passed: false,
+                expected_sexp: "(document (array))".to_string(),
+                actual_sexp: "(document (object))".to_string(),
+            },
+        ];
+        write_results_to_buffer(&mut buffer, &results).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            concat!(
+                r#"{"language":"json","name":"title 1","passed":true,"expected_sexp":"(document)","actual_sexp":"(document)"}"#,
+                "\n",
+                r#"{"language":"json","name":"title 2","passed":false,"expected_sexp":"(document (array))","actual_sexp":"(document (object))"}"#,
+                "\n",
+            )
+        );
+    }
+
     #[test]
     fn test_parse_test_content_with_comments_in_sexp() {
         let entry = parse_test_content(
@@ -1465,4 +1796,205 @@ a
             }
         );
     }
+
+    #[test]
+    fn test_display_test_entry_outline() {
+        let corpus_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            corpus_dir.path().join("expressions.txt"),
+            indoc! {"
+                =======
+                Numbers
+                =======
+                1 2
+                ---
+                (a)
+
+                =======
+                Strings
+                =======
+                \"hello\"
+                ---
+                (b)
+            "},
+        )
+        .unwrap();
+        fs::create_dir(corpus_dir.path().join("statements")).unwrap();
+        fs::write(
+            corpus_dir.path().join("statements").join("loops.txt"),
+            indoc! {"
+                =====
+                While
+                =====
+                while (x) {}
+                ---
+                (c)
+            "},
+        )
+        .unwrap();
+
+        let mut entry = parse_tests(corpus_dir.path()).unwrap();
+        if let TestEntry::Group { name, .. } = &mut entry {
+            *name = "corpus".to_string();
+        }
+        assert_eq!(
+            entry.to_string(),
+            indoc! {"
+                corpus/
+                  expressions/
+                    Numbers (3 bytes)
+                    Strings (7 bytes)
+                  statements/
+                    loops/
+                      While (12 bytes)
+            "}
+        );
+    }
+
+    #[test]
+    fn test_parse_tests_filtered() {
+        let corpus_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            corpus_dir.path().join("expressions.txt"),
+            indoc! {"
+                =======
+                Numbers
+                =======
+                1
+                ---
+                (a)
+
+                =======
+                Strings
+                =======
+                \"a\"
+                ---
+                (b)
+            "},
+        )
+        .unwrap();
+        fs::write(
+            corpus_dir.path().join("statements.txt"),
+            indoc! {"
+                =====
+                While
+                =====
+                while (x) {}
+                ---
+                (c)
+            "},
+        )
+        .unwrap();
+
+        let outline = |filter| {
+            let mut entry = parse_tests_filtered(corpus_dir.path(), filter).unwrap();
+            if let TestEntry::Group { name, .. } = &mut entry {
+                *name = "corpus".to_string();
+            }
+            entry.to_string()
+        };
+
+        assert_eq!(
+            outline(Some("Strings")),
+            indoc! {"
+                corpus/
+                  expressions/
+                    Strings (3 bytes)
+            "}
+        );
+        assert_eq!(
+            outline(Some("W*e")),
+            indoc! {"
+                corpus/
+                  statements/
+                    While (12 bytes)
+            "}
+        );
+        assert_eq!(outline(Some("Nothing")), "corpus/\n");
+
+        let entry = parse_tests_filtered(corpus_dir.path(), None).unwrap();
+        assert_eq!(entry, parse_tests(corpus_dir.path()).unwrap());
+    }
+
+    #[test]
+    fn test_parse_tests_iter() {
+        let corpus_dir = tempfile::tempdir().unwrap();
+        fs::create_dir(corpus_dir.path().join("b_statements")).unwrap();
+        fs::write(
+            corpus_dir.path().join("a_expressions.txt"),
+            indoc! {"
+                =======
+                Numbers
+                =======
+                1
+                ---
+                (a)
+
+                =======
+                Strings
+                =======
+                \"a\"
+                ---
+                (b)
+            "},
+        )
+        .unwrap();
+        fs::write(
+            corpus_dir.path().join("b_statements").join("loops.txt"),
+            indoc! {"
+                =====
+                While
+                =====
+                while (x) {}
+                ---
+                (c)
+            "},
+        )
+        .unwrap();
+        fs::write(
+            corpus_dir.path().join("c_types.txt"),
+            indoc! {"
+                ====
+                Enum
+                ====
+                enum A {}
+                ---
+                (d)
+            "},
+        )
+        .unwrap();
+        fs::write(corpus_dir.path().join(".hidden.txt"), "").unwrap();
+
+        let names = parse_tests_iter(corpus_dir.path())
+            .map(|entry| match entry.unwrap() {
+                TestEntry::Example { name, .. } => name,
+                TestEntry::Group { .. } => panic!("unexpected group"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Numbers", "Strings", "While", "Enum"]);
+
+        let mut examples = parse_tests_iter(corpus_dir.path());
+        assert!(matches!(
+            examples.next(),
+            Some(Ok(TestEntry::Example { name, .. })) if name == "Numbers"
+        ));
+
+        assert!(parse_tests_iter(&corpus_dir.path().join("missing"))
+            .next()
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_bool_env_value() {
+        for value in ["1", "true", "TRUE", " true\n"] {
+            assert_eq!(parse_bool_env_value(value), Some(true), "{value:?}");
+        }
+        for value in ["", "0", "false", "False"] {
+            assert_eq!(parse_bool_env_value(value), Some(false), "{value:?}");
+        }
+        for value in ["2", "yes", "off"] {
+            assert_eq!(parse_bool_env_value(value), None, "{value:?}");
+        }
+    }
 }
@@ -0,0 +1,123 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use indoc::indoc;
+use tree_sitter::Parser;
+
+use super::helpers::fixtures::{fixtures_dir, get_language, scratch_dir};
+use crate::benchmark::{
+    find_corpus_path, find_grammar_paths, largest_corpus_example, measure_throughput, Throughput,
+};
+
+#[test]
+fn test_measuring_throughput_for_each_language() {
+    let grammars_dir = fixtures_dir().join("grammars");
+    let grammar_paths = find_grammar_paths(&grammars_dir).unwrap();
+    assert!(grammar_paths.contains(&PathBuf::from("json")));
+    assert!(grammar_paths.contains(&Path::new("typescript").join("tsx")));
+
+    let mut parser = Parser::new();
+    for grammar_path in grammar_paths {
+        let language_name = grammar_path.to_str().unwrap();
+
+        // The corpus files are only present once the fixtures are fetched.
+        let Some(corpus_path) = find_corpus_path(&grammars_dir, &grammar_path) else {
+            eprintln!("Skipping {language_name}: no corpus");
+            continue;
+        };
+        let source = largest_corpus_example(&corpus_path)
+            .unwrap()
+            .unwrap_or_else(|| panic!("No examples in the corpus at {corpus_path:?}"));
+
+        parser.set_language(&get_language(language_name)).unwrap();
+        let throughput = measure_throughput(&mut parser, language_name, &source, 1);
+        assert_eq!(throughput.bytes, source.len());
+        assert!(throughput.nodes > 0);
+        assert!(throughput
+            .to_string()
+            .starts_with(&format!("{language_name}\t")));
+    }
+}
+
+#[test]
+fn test_find_corpus_path() {
+    let grammars_dir = scratch_dir().join("find_corpus_path");
+    let corpus_path = grammars_dir.join("outer").join("test").join("corpus");
+    fs::create_dir_all(&corpus_path).unwrap();
+    fs::create_dir_all(grammars_dir.join("outer").join("inner")).unwrap();
+    fs::create_dir_all(grammars_dir.join("other")).unwrap();
+
+    // Grammars in subdirectories use their ancestor's corpus.
+    assert_eq!(
+        find_corpus_path(&grammars_dir, Path::new("outer")),
+        Some(corpus_path.clone())
+    );
+    assert_eq!(
+        find_corpus_path(&grammars_dir, &Path::new("outer").join("inner")),
+        Some(corpus_path)
+    );
+    assert_eq!(find_corpus_path(&grammars_dir, Path::new("other")), None);
+
+    // The grammars directory itself is not an ancestor.
+    fs::create_dir_all(grammars_dir.join("test").join("corpus")).unwrap();
+    assert_eq!(find_corpus_path(&grammars_dir, Path::new("other")), None);
+}
+
+#[test]
+fn test_largest_corpus_example() {
+    let corpus_path = scratch_dir().join("largest_corpus_example.txt");
+    fs::write(
+        &corpus_path,
+        indoc! {"
+            =====
+            Short
+            =====
+            [1]
+            ---
+            (document (array (number)))
+
+            ====
+            Long
+            ====
+            [1, 2]
+            ---
+            (document (array (number) (number)))
+
+            ====
+            Also long
+            ====
+            [3, 4]
+            ---
+            (document (array (number) (number)))
+        "},
+    )
+    .unwrap();
+    assert_eq!(
+        largest_corpus_example(&corpus_path).unwrap(),
+        Some(b"[1, 2]".to_vec())
+    );
+
+    fs::write(&corpus_path, "").unwrap();
+    assert_eq!(largest_corpus_example(&corpus_path).unwrap(), None);
+}
+
+#[test]
+fn test_throughput_display() {
+    let throughput = Throughput {
+        language_name: "json".to_string(),
+        bytes: 1000,
+        nodes: 50,
+        iterations: 4,
+        duration: Duration::from_millis(2),
+    };
+    assert_eq!(throughput.bytes_per_second(), 2_000_000);
+    assert_eq!(throughput.nodes_per_second(), 100_000);
+    assert_eq!(throughput.to_string(), "json\t1000\t50\t4\t2000000\t100000");
+    assert_eq!(
+        Throughput::HEADER.split('\t').count(),
+        throughput.to_string().split('\t').count()
+    );
+}
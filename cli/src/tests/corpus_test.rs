@@ -1,12 +1,18 @@
-use std::{collections::HashMap, env, fs};
+use std::{
+    collections::HashMap,
+    env, fs,
+    panic::{self, AssertUnwindSafe},
+};
 
+use indoc::indoc;
 use tree_sitter::Parser;
 use tree_sitter_proc_macro::test_with_seed;
 
 use crate::{
     fuzz::{
         corpus_test::{
-            check_changed_ranges, check_consistent_sizes, get_parser, set_included_ranges,
+            check_changed_ranges, check_consistent_sizes, get_parser, parse_or_report_timeout,
+            set_included_ranges,
         },
         edits::{get_random_edit, invert_edit},
         flatten_tests, new_seed,
@@ -14,9 +20,11 @@ use crate::{
         EDIT_COUNT, EXAMPLE_EXCLUDE, EXAMPLE_INCLUDE, ITERATION_COUNT, LANGUAGE_FILTER,
         LOG_GRAPH_ENABLED, START_SEED,
     },
-    generate,
+    generate::{self, GenerateError},
     parse::perform_edit,
-    test::{parse_tests, print_diff, print_diff_key, strip_sexp_fields},
+    test::{
+        parse_tests, print_diff, print_diff_key, run_tests_at_path, strip_sexp_fields, TestOptions,
+    },
     tests::{
         allocations,
         helpers::fixtures::{fixtures_dir, get_language, get_test_language, SCRATCH_BASE_DIR},
@@ -169,7 +177,7 @@ pub fn test_language_corpus(
     let dump_edits = env::var("TREE_SITTER_DUMP_EDITS").is_ok();
 
     if log_seed {
-        println!("  start seed: {start_seed}");
+        println!("  {language_name} start seed: {start_seed}");
     }
 
     println!();
@@ -191,7 +199,10 @@ pub fn test_language_corpus(
             parser.set_language(&language).unwrap();
             set_included_ranges(&mut parser, &test.input, test.template_delimiters);
 
-            let tree = parser.parse(&test.input, None).unwrap();
+            let Some(tree) = parse_or_report_timeout(&mut parser, &test.input, None, &test_name)
+            else {
+                return false;
+            };
             let mut actual_output = tree.root_node().to_sexp();
             if !test.has_fields {
                 actual_output = strip_sexp_fields(&actual_output);
@@ -199,7 +210,7 @@ pub fn test_language_corpus(
 
             if actual_output != test.output {
                 println!("Incorrect initial parse for {test_name}");
-                print_diff_key();
+                print_diff_key(true);
                 print_diff(&actual_output, &test.output, true);
                 println!();
                 return false;
@@ -242,7 +253,7 @@ pub fn test_language_corpus(
                 }
 
                 if log_seed {
-                    println!("   {test_index}.{trial:<2} seed: {seed}");
+                    println!("   {language_name} {test_index}.{trial:<2} seed: {seed}");
                 }
 
                 if dump_edits {
@@ -259,12 +270,16 @@ pub fn test_language_corpus(
                 }
 
                 set_included_ranges(&mut parser, &input, test.template_delimiters);
-                let mut tree2 = parser.parse(&input, Some(&tree)).unwrap();
+                let Some(mut tree2) =
+                    parse_or_report_timeout(&mut parser, &input, Some(&tree), &test_name)
+                else {
+                    return false;
+                };
 
                 // Check that the new tree is consistent.
                 check_consistent_sizes(&tree2, &input);
                 if let Err(message) = check_changed_ranges(&tree, &tree2, &input) {
-                    println!("\nUnexpected scope change in seed {seed} with start seed {start_seed}\n{message}\n\n",);
+                    println!("\nUnexpected scope change for {test_name} in seed {seed} with start seed {start_seed}\n{message}\n\n",);
                     return false;
                 }
 
@@ -277,7 +292,11 @@ pub fn test_language_corpus(
                 }
 
                 set_included_ranges(&mut parser, &test.input, test.template_delimiters);
-                let tree3 = parser.parse(&input, Some(&tree2)).unwrap();
+                let Some(tree3) =
+                    parse_or_report_timeout(&mut parser, &input, Some(&tree2), &test_name)
+                else {
+                    return false;
+                };
 
                 // Verify that the final tree matches the expectation from the corpus.
                 let mut actual_output = tree3.root_node().to_sexp();
@@ -287,7 +306,7 @@ pub fn test_language_corpus(
 
                 if actual_output != test.output {
                     println!("Incorrect parse for {test_name} - seed {seed}");
-                    print_diff_key();
+                    print_diff_key(true);
                     print_diff(&actual_output, &test.output, true);
                     println!();
                     return false;
@@ -296,7 +315,7 @@ pub fn test_language_corpus(
                 // Check that the edited tree is consistent.
                 check_consistent_sizes(&tree3, &input);
                 if let Err(message) = check_changed_ranges(&tree2, &tree3, &input) {
-                    println!("Unexpected scope change in seed {seed} with start seed {start_seed}\n{message}\n\n");
+                    println!("Unexpected scope change for {test_name} in seed {seed} with start seed {start_seed}\n{message}\n\n");
                     return false;
                 }
 
@@ -328,6 +347,184 @@ pub fn test_language_corpus(
     }
 }
 
+#[test]
+fn test_corpus_example_timeout_is_reported_as_failure() {
+    let mut log_session = None;
+    let mut parser = get_parser(&mut log_session, "log.html");
+    parser.set_language(&get_language("json")).unwrap();
+    parser.set_timeout_micros(1000);
+
+    let input = format!("[{}0]", "0,".repeat(1_000_000));
+    assert!(parse_or_report_timeout(&mut parser, input.as_bytes(), None, "json - slow").is_none());
+
+    // After a timeout, the parser starts the next example from scratch.
+    parser.set_timeout_micros(0);
+    let tree = parse_or_report_timeout(&mut parser, b"[1]", None, "json - fast").unwrap();
+    assert_eq!(tree.root_node().to_sexp(), "(document (array (number)))");
+}
+
+#[test]
+fn test_run_tests_with_fail_fast() {
+    let corpus_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        corpus_dir.path().join("values.txt"),
+        indoc! {"
+            ======
+            Number
+            ======
+            1
+            ---
+            (document (string))
+
+            ======
+            String
+            ======
+            \"a\"
+            ---
+            (document (number))
+
+            =====
+            Array
+            =====
+            []
+            ---
+            (document (array))
+        "},
+    )
+    .unwrap();
+
+    let language = get_language("json");
+    let run = |fail_fast| {
+        let json_output = corpus_dir.path().join("results.json");
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let mut opts = TestOptions {
+            path: corpus_dir.path().join("values.txt"),
+            debug: false,
+            debug_graph: false,
+            include: None,
+            exclude: None,
+            update: false,
+            open_log: false,
+            languages: [("json", &language)].into_iter().collect(),
+            default_language: "json",
+            color: false,
+            test_num: 1,
+            show_fields: false,
+            json_output: Some(json_output.clone()),
+            fail_fast,
+        };
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_tests_at_path(&mut parser, &mut opts)
+        }));
+        let results = fs::read_to_string(json_output)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let result = serde_json::from_str::<serde_json::Value>(line).unwrap();
+                (result["name"].clone(), result["passed"].clone())
+            })
+            .collect::<Vec<_>>();
+        (outcome, results)
+    };
+
+    let (outcome, results) = run(false);
+    assert!(outcome.unwrap().is_err());
+    assert_eq!(
+        results,
+        [
+            ("Number".into(), false.into()),
+            ("String".into(), false.into()),
+            ("Array".into(), true.into()),
+        ]
+    );
+
+    // In fail-fast mode, the results of the examples that ran are written,
+    // and then the runner panics with the diff of the first failure only.
+    let (outcome, results) = run(true);
+    assert_eq!(results, [("Number".into(), false.into())]);
+    let message = outcome.unwrap_err().downcast::<String>().unwrap();
+    assert_eq!(
+        *message,
+        "Number:\n \n     (document\n-      (number))\n\n+      (string))\n\n"
+    );
+}
+
+#[test]
+fn test_run_tests_with_json_output_and_language_attributes() {
+    let corpus_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        corpus_dir.path().join("values.txt"),
+        indoc! {"
+            ==========
+            JSON value
+            ==========
+            1
+            ---
+            (document (number))
+
+            ================
+            JavaScript value
+            :language(javascript)
+            ================
+            1
+            ---
+            (program (expression_statement (number)))
+
+            =================
+            JSON value, again
+            =================
+            2
+            ---
+            (document (number))
+        "},
+    )
+    .unwrap();
+
+    // The default language is not the first one in alphabetical order.
+    let json = get_language("json");
+    let javascript = get_language("javascript");
+    let json_output = corpus_dir.path().join("results.json");
+    let mut parser = Parser::new();
+    parser.set_language(&json).unwrap();
+    let mut opts = TestOptions {
+        path: corpus_dir.path().join("values.txt"),
+        debug: false,
+        debug_graph: false,
+        include: None,
+        exclude: None,
+        update: false,
+        open_log: false,
+        languages: [("javascript", &javascript), ("json", &json)]
+            .into_iter()
+            .collect(),
+        default_language: "json",
+        color: false,
+        test_num: 1,
+        show_fields: false,
+        json_output: Some(json_output.clone()),
+        fail_fast: false,
+    };
+    run_tests_at_path(&mut parser, &mut opts).unwrap();
+
+    let results = fs::read_to_string(json_output)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let result = serde_json::from_str::<serde_json::Value>(line).unwrap();
+            (result["language"].clone(), result["passed"].clone())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        results,
+        [
+            ("json".into(), true.into()),
+            ("javascript".into(), true.into()),
+            ("json".into(), true.into()),
+        ]
+    );
+}
+
 #[test]
 fn test_feature_corpus_files() {
     let test_grammars_dir = fixtures_dir().join("test_grammars");
@@ -352,25 +549,42 @@ fn test_feature_corpus_files() {
         if !grammar_path.exists() {
             grammar_path = test_path.join("grammar.json");
         }
+        // The expected error message is either given in full, or as a
+        // substring that the message must contain.
         let error_message_path = test_path.join("expected_error.txt");
+        let error_substring_path = test_path.join("expected_error_contains.txt");
         let grammar_json = generate::load_grammar_file(&grammar_path, None).unwrap();
         let generate_result = generate::generate_parser_for_grammar(&grammar_json);
 
-        if error_message_path.exists() {
+        if error_message_path.exists() || error_substring_path.exists() {
             if EXAMPLE_INCLUDE.is_some() || EXAMPLE_EXCLUDE.is_some() {
                 continue;
             }
 
             eprintln!("test language: {language_name:?}");
 
-            let expected_message = fs::read_to_string(&error_message_path)
-                .unwrap()
-                .replace("\r\n", "\n");
+            let is_substring = !error_message_path.exists();
+            let mut expected_message = fs::read_to_string(if is_substring {
+                &error_substring_path
+            } else {
+                &error_message_path
+            })
+            .unwrap()
+            .replace("\r\n", "\n");
+            if is_substring {
+                expected_message.truncate(expected_message.trim_end().len());
+            }
             if let Err(e) = generate_result {
                 let actual_message = e.to_string().replace("\r\n", "\n");
-                if expected_message != actual_message {
+                let matches = if is_substring {
+                    actual_message.contains(&expected_message)
+                } else {
+                    expected_message == actual_message
+                };
+                if !matches {
                     eprintln!(
-                        "Unexpected error message.\n\nExpected:\n\n{expected_message}\nActual:\n\n{actual_message}\n",
+                        "Unexpected error message.\n\nExpected{}:\n\n{expected_message}\nActual:\n\n{actual_message}\n",
+                        if is_substring { " to contain" } else { "" },
                     );
                     failure_count += 1;
                 }
@@ -402,7 +616,11 @@ fn test_feature_corpus_files() {
                     let mut log_session = None;
                     let mut parser = get_parser(&mut log_session, "log.html");
                     parser.set_language(&language).unwrap();
-                    let tree = parser.parse(&test.input, None).unwrap();
+                    let Some(tree) =
+                        parse_or_report_timeout(&mut parser, &test.input, None, &test.name)
+                    else {
+                        return false;
+                    };
                     let mut actual_output = tree.root_node().to_sexp();
                     if !test.has_fields {
                         actual_output = strip_sexp_fields(&actual_output);
@@ -410,7 +628,7 @@ fn test_feature_corpus_files() {
                     if actual_output == test.output {
                         true
                     } else {
-                        print_diff_key();
+                        print_diff_key(true);
                         print_diff(&actual_output, &test.output, true);
                         println!();
                         false
@@ -428,3 +646,30 @@ fn test_feature_corpus_files() {
 
     assert!(failure_count == 0, "{failure_count} corpus tests failed");
 }
+
+#[test]
+fn test_feature_corpus_errors_are_structured() {
+    let test_grammars_dir = fixtures_dir().join("test_grammars");
+
+    let grammar_json = generate::load_grammar_file(
+        &test_grammars_dir
+            .join("conflicting_precedence")
+            .join("grammar.js"),
+        None,
+    )
+    .unwrap();
+    match generate::generate_parser_for_grammar(&grammar_json) {
+        Err(GenerateError::Conflict {
+            symbols, lookahead, ..
+        }) => {
+            assert_eq!(symbols, ["sum", "product", "other_thing"]);
+            assert_eq!(lookahead, "'*'");
+        }
+        result => panic!("Expected a conflict error, got {result:?}"),
+    }
+
+    match generate::generate_parser_for_grammar("{\"name\": ") {
+        Err(GenerateError::InvalidJson(_)) => {}
+        result => panic!("Expected an invalid JSON error, got {result:?}"),
+    }
+}
@@ -1,6 +1,8 @@
 use std::{
+    collections::HashMap,
     env, fs,
     path::{Path, PathBuf},
+    sync::Mutex,
 };
 
 use anyhow::Context;
@@ -22,6 +24,7 @@ lazy_static! {
         }
         loader
     };
+    static ref LANGUAGES: Mutex<HashMap<String, Language>> = Mutex::default();
 }
 
 pub fn test_loader() -> &'static Loader {
@@ -37,10 +40,37 @@ pub fn scratch_dir() -> &'static Path {
 }
 
 pub fn get_language(name: &str) -> Language {
+    if let Some(language) = LANGUAGES.lock().unwrap().get(name) {
+        return language.clone();
+    }
+
+    // Load the language without holding the lock, since compiling a grammar
+    // can take a while.
     let src_dir = GRAMMARS_DIR.join(name).join("src");
     let mut config = CompileConfig::new(&src_dir, None, None);
     config.header_paths.push(&HEADER_DIR);
-    TEST_LOADER.load_language_at_path(config).unwrap()
+    let language = TEST_LOADER.load_language_at_path(config).unwrap();
+    LANGUAGES
+        .lock()
+        .unwrap()
+        .insert(name.to_string(), language.clone());
+    language
+}
+
+/// Load the given fixture grammars ahead of time, so that the compile and load
+/// cost is not counted against the first test or benchmark that uses each one.
+/// Later calls to [`get_language`] for these grammars return immediately.
+pub fn preload_languages(names: &[&str]) {
+    for name in names {
+        get_language(name);
+    }
+}
+
+pub fn compile_language_to_dylib(name: &str, output_path: &Path) {
+    let src_dir = GRAMMARS_DIR.join(name).join("src");
+    let mut config = CompileConfig::new(&src_dir, None, Some(output_path.to_path_buf()));
+    config.header_paths.push(&HEADER_DIR);
+    TEST_LOADER.load_language_at_path(config).unwrap();
 }
 
 pub fn get_language_queries_path(language_name: &str) -> PathBuf {
@@ -90,10 +120,10 @@ pub fn get_test_language(name: &str, parser_code: &str, path: Option<&Path>) ->
     }
 
     let scanner_path = if let Some(path) = path {
-        let scanner_path = path.join("scanner.c");
-        if scanner_path.exists() {
+        let scanner_path = TEST_LOADER.get_scanner_path(path);
+        if let Some(scanner_path) = scanner_path {
             let scanner_code = fs::read_to_string(&scanner_path).unwrap();
-            let scanner_copy_path = src_dir.join("scanner.c");
+            let scanner_copy_path = src_dir.join(scanner_path.file_name().unwrap());
             if !fs::read_to_string(&scanner_copy_path)
                 .map_or(false, |content| content == scanner_code)
             {
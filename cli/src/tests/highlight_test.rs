@@ -1,17 +1,25 @@
 use std::{
     ffi::CString,
-    fs,
+    fs, io,
     os::raw::c_char,
     ptr, slice, str,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
 };
 
+use indoc::indoc;
 use lazy_static::lazy_static;
+use tree_sitter::Parser;
 use tree_sitter_highlight::{
-    c, Error, Highlight, HighlightConfiguration, HighlightEvent, Highlighter, HtmlRenderer,
+    c, Error, Highlight, HighlightConfiguration, HighlightEvent, HighlightRegion, Highlighter,
+    HtmlRenderer,
 };
 
 use super::helpers::fixtures::{get_highlight_config, get_language, get_language_queries_path};
+use crate::{fuzz::edits::Edit, highlight, parse::perform_edit};
 
 lazy_static! {
     static ref JS_HIGHLIGHT: HighlightConfiguration =
@@ -96,6 +104,228 @@ fn test_highlighting_javascript() {
     );
 }
 
+#[test]
+fn test_highlighting_javascript_to_scopes() {
+    let source = "// add\nfunction add(a, b) {\n  return a + b;\n}\n";
+    let mut highlighter = Highlighter::new();
+    let events = highlighter
+        .highlight(&JS_HIGHLIGHT, source.as_bytes(), None, |_| None)
+        .unwrap();
+    let mut output = Vec::new();
+    highlight::write_scopes(&mut output, source.as_bytes(), events, &HIGHLIGHT_NAMES).unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        indoc! {"
+            0 0 6 comment
+            1 0 8 keyword
+            1 9 3 function
+            1 12 1 punctuation.bracket
+            1 13 1 variable
+            1 14 1 punctuation.delimiter
+            1 16 1 variable
+            1 17 1 punctuation.bracket
+            1 19 1 punctuation.bracket
+            2 2 6 keyword
+            2 9 1 variable
+            2 11 1 operator
+            2 13 1 variable
+            2 14 1 punctuation.delimiter
+            3 0 1 punctuation.bracket
+        "}
+    );
+}
+
+#[test]
+fn test_highlighting_a_byte_range() {
+    let source = indoc! {"
+        let a = 1;
+        const s = `one
+        two`;
+        const t = html `<div>${a}</div>`;
+        /* three
+        four */
+        function f(b) { return b; }
+    "};
+    let start = source.find("two").unwrap();
+    let end = source.find("four").unwrap();
+
+    // Collect each span of source code along with the highlights that apply to it.
+    fn spans(
+        events: impl Iterator<Item = Result<HighlightEvent, Error>>,
+    ) -> Vec<(usize, usize, Vec<&'static str>)> {
+        let mut highlights = Vec::new();
+        let mut result = Vec::new();
+        for event in events {
+            match event.unwrap() {
+                HighlightEvent::HighlightStart(s) => highlights.push(HIGHLIGHT_NAMES[s.0].as_str()),
+                HighlightEvent::HighlightEnd => {
+                    highlights.pop().unwrap();
+                }
+                HighlightEvent::Source { start, end } => {
+                    result.push((start, end, highlights.clone()));
+                }
+            }
+        }
+        assert_eq!(highlights, Vec::<&str>::new());
+        result
+    }
+
+    let mut highlighter = Highlighter::new();
+    let range_spans = spans(
+        highlighter
+            .highlight_range(
+                &JS_HIGHLIGHT,
+                source.as_bytes(),
+                start..end,
+                None,
+                &test_language_for_injection_string,
+            )
+            .unwrap(),
+    );
+
+    // The spans are the same as when highlighting the whole document, clipped to the range.
+    let document_spans = spans(
+        highlighter
+            .highlight(
+                &JS_HIGHLIGHT,
+                source.as_bytes(),
+                None,
+                &test_language_for_injection_string,
+            )
+            .unwrap(),
+    );
+    let clipped_spans = document_spans
+        .into_iter()
+        .filter_map(|(span_start, span_end, highlights)| {
+            let span_start = span_start.max(start);
+            let span_end = span_end.min(end);
+            (span_start < span_end).then_some((span_start, span_end, highlights))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(range_spans, clipped_spans);
+
+    assert_eq!(
+        range_spans.first().unwrap(),
+        &(start, start + 4, vec!["string"])
+    );
+    assert_eq!(
+        range_spans.last().unwrap(),
+        &(source.find("/*").unwrap(), end, vec!["comment"])
+    );
+    assert!(range_spans
+        .iter()
+        .any(|(_, _, highlights)| highlights.contains(&"tag")));
+}
+
+#[test]
+fn test_highlighting_incrementally() {
+    let mut source = indoc! {"
+        let a = 1;
+        let b = 2;
+        function f() { return a; }
+    "}
+    .as_bytes()
+    .to_vec();
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    let mut old_tree = parser.parse(&source, None).unwrap();
+
+    // Replace the number on the second line with a string.
+    let position = source.iter().position(|&b| b == b'2').unwrap();
+    perform_edit(
+        &mut old_tree,
+        &mut source,
+        &Edit {
+            position,
+            deleted_length: 1,
+            inserted_text: b"\"two\"".to_vec(),
+        },
+    )
+    .unwrap();
+    let new_tree = parser.parse(&source, Some(&old_tree)).unwrap();
+
+    let mut highlighter = Highlighter::new();
+    let regions = highlighter
+        .highlight_incremental(
+            &JS_HIGHLIGHT,
+            &source,
+            &old_tree,
+            &new_tree,
+            None,
+            &test_language_for_injection_string,
+        )
+        .unwrap();
+
+    let second_line = 11..26;
+    assert_eq!(&source[second_line.clone()], b"let b = \"two\";\n");
+    assert_eq!(regions.len(), 3);
+    assert!(matches!(&regions[0], HighlightRegion::Unchanged(range) if *range == (0..11)));
+    assert!(
+        matches!(&regions[2], HighlightRegion::Unchanged(range) if *range == (26..source.len()))
+    );
+
+    // Only the second line is highlighted again, with the same events as when
+    // highlighting that line of the new document.
+    let HighlightRegion::Changed { range, events } = &regions[1] else {
+        panic!("expected a changed region, got {:?}", regions[1]);
+    };
+    assert_eq!(*range, second_line);
+    let expected_events = highlighter
+        .highlight_range(
+            &JS_HIGHLIGHT,
+            &source,
+            second_line,
+            None,
+            &test_language_for_injection_string,
+        )
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(format!("{events:?}"), format!("{expected_events:?}"));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        HighlightEvent::HighlightStart(h) if HIGHLIGHT_NAMES[h.0] == "string"
+    )));
+    assert!(events.iter().all(|event| match event {
+        HighlightEvent::Source { start, end } => (11..=26).contains(start) && *end <= 26,
+        _ => true,
+    }));
+
+    // Changing the text of a token without changing the syntax of the document
+    // still affects the token's line.
+    let mut old_tree = new_tree;
+    let position = source.iter().position(|&b| b == b'1').unwrap();
+    perform_edit(
+        &mut old_tree,
+        &mut source,
+        &Edit {
+            position,
+            deleted_length: 1,
+            inserted_text: b"7".to_vec(),
+        },
+    )
+    .unwrap();
+    let new_tree = parser.parse(&source, Some(&old_tree)).unwrap();
+    assert_eq!(old_tree.changed_ranges(&new_tree).len(), 0);
+
+    let regions = highlighter
+        .highlight_incremental(
+            &JS_HIGHLIGHT,
+            &source,
+            &old_tree,
+            &new_tree,
+            None,
+            &test_language_for_injection_string,
+        )
+        .unwrap();
+    assert_eq!(regions.len(), 2);
+    assert!(matches!(&regions[0], HighlightRegion::Changed { range, .. } if *range == (0..11)));
+    assert!(
+        matches!(&regions[1], HighlightRegion::Unchanged(range) if *range == (11..source.len()))
+    );
+}
+
 #[test]
 fn test_highlighting_injected_html_in_javascript() {
     let source = ["const s = html `<div>${a < b}</div>`;"].join("\n");
@@ -215,6 +445,37 @@ fn test_highlighting_injected_javascript_in_html() {
     );
 }
 
+#[test]
+fn test_highlighting_many_files_with_a_shared_config() {
+    let config = Arc::new(get_highlight_config(
+        "javascript",
+        Some("injections.scm"),
+        &HIGHLIGHT_NAMES,
+    ));
+
+    let threads = (0..4)
+        .map(|thread_index| {
+            let config = config.clone();
+            thread::spawn(move || {
+                let mut highlighter = Highlighter::new();
+                for file_index in 0..25 {
+                    let source = format!("const a{thread_index}_{file_index} = b({file_index});");
+                    let highlight_count = highlighter
+                        .highlight(&config, source.as_bytes(), None, |_| None)
+                        .unwrap()
+                        .filter(|event| matches!(event, Ok(HighlightEvent::HighlightStart(_))))
+                        .count();
+                    assert_eq!(highlight_count, 7, "unexpected highlights for {source:?}");
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
 #[test]
 fn test_highlighting_multiline_nodes_to_html() {
     let source = [
@@ -683,6 +944,61 @@ fn test_decode_utf8_lossy() {
     assert_eq!(parts, vec!["hello", "\u{fffd}", "\u{fffd}"]);
 }
 
+#[test]
+fn test_highlighting_via_html_renderer_streaming() {
+    let source = "const a = `${b}`;\n// <c>\n".repeat(1000);
+
+    let mut highlighter = Highlighter::new();
+    let mut renderer = HtmlRenderer::new();
+    let mut streamed = Vec::new();
+    renderer
+        .render_to(
+            highlighter
+                .highlight(&JS_HIGHLIGHT, source.as_bytes(), None, |_| None)
+                .unwrap(),
+            source.as_bytes(),
+            &|highlight| HTML_ATTRS[highlight.0].as_bytes(),
+            &mut streamed,
+        )
+        .unwrap();
+
+    renderer
+        .render(
+            highlighter
+                .highlight(&JS_HIGHLIGHT, source.as_bytes(), None, |_| None)
+                .unwrap(),
+            source.as_bytes(),
+            &|highlight| HTML_ATTRS[highlight.0].as_bytes(),
+        )
+        .unwrap();
+    assert_eq!(streamed, renderer.html);
+
+    // Errors from the writer are propagated.
+    struct ClosedSocket;
+
+    impl io::Write for ClosedSocket {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let error = renderer
+        .render_to(
+            highlighter
+                .highlight(&JS_HIGHLIGHT, source.as_bytes(), None, |_| None)
+                .unwrap(),
+            source.as_bytes(),
+            &|highlight| HTML_ATTRS[highlight.0].as_bytes(),
+            ClosedSocket,
+        )
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+}
+
 fn c_string(s: &str) -> CString {
     CString::new(s.as_bytes().to_vec()).unwrap()
 }
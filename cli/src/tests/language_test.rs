@@ -1,6 +1,11 @@
-use tree_sitter::Parser;
+use std::{env::consts::DLL_EXTENSION, fs, path::Path};
 
-use super::helpers::fixtures::get_language;
+use tree_sitter::{Language, LoadError, Parser};
+use tree_sitter_loader::CompileConfig;
+
+use super::helpers::fixtures::{
+    compile_language_to_dylib, get_language, preload_languages, scratch_dir, test_loader,
+};
 
 #[test]
 fn test_lookahead_iterator() {
@@ -63,3 +68,133 @@ fn test_lookahead_iterator_modifiable_only_by_mut() {
     let mut names = lookahead.iter_names();
     let _ = names.next();
 }
+
+#[test]
+fn test_language_field_introspection() {
+    let language = get_language("python");
+    let field_count = language.field_count();
+    assert!(field_count > 0);
+
+    for id in 1..=field_count as u16 {
+        let name = language.field_name_for_id(id).unwrap();
+        assert!(!name.is_empty());
+        assert_eq!(language.field_id_for_name(name).unwrap().get(), id);
+    }
+
+    assert_eq!(language.field_name_for_id(0), None);
+    assert_eq!(language.field_name_for_id(field_count as u16 + 1), None);
+    assert_eq!(language.field_id_for_name("not_a_field"), None);
+
+    let body = language.field_id_for_name("body").unwrap();
+    assert_eq!(language.field_name_for_id(body.get()), Some("body"));
+}
+
+#[test]
+fn test_language_node_kind_introspection() {
+    let language = get_language("c");
+    assert!(language.node_kind_count() > 0);
+
+    for (kind, named) in [
+        ("translation_unit", true),
+        ("function_definition", true),
+        ("identifier", true),
+        ("if", false),
+        (";", false),
+    ] {
+        let id = language.id_for_node_kind(kind, named);
+        assert_ne!(id, 0, "{kind}");
+        assert!((id as usize) < language.node_kind_count());
+        assert_eq!(language.node_kind_for_id(id), Some(kind));
+        assert_eq!(language.node_kind_is_named(id), named, "{kind}");
+        assert!(language.node_kind_is_visible(id), "{kind}");
+    }
+
+    // Node kinds are looked up by both name and namedness.
+    assert_eq!(language.id_for_node_kind("if", true), 0);
+    assert_eq!(language.id_for_node_kind("not_a_kind", true), 0);
+}
+
+#[test]
+fn test_language_load_from_path() {
+    let path = scratch_dir().join(format!("json-dynamic.{DLL_EXTENSION}"));
+    compile_language_to_dylib("json", &path);
+
+    let language = Language::load_from_path(&path, "tree_sitter_json").unwrap();
+    let mut parser = Parser::new();
+    parser.set_language(&language).unwrap();
+    let tree = parser.parse("[1, null]", None).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        "(document (array (number) (null)))"
+    );
+
+    assert_eq!(
+        Language::load_from_path(&path, "tree_sitter_not_json").unwrap_err(),
+        LoadError::SymbolMissing("tree_sitter_not_json".to_string())
+    );
+
+    let missing_path = Path::new("does-not-exist.so");
+    assert_eq!(
+        Language::load_from_path(missing_path, "tree_sitter_json").unwrap_err(),
+        LoadError::FileNotFound(missing_path.to_path_buf())
+    );
+}
+
+#[test]
+fn test_language_load_from_path_with_a_null_language() {
+    let src_dir = scratch_dir().join("null-language");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(
+        src_dir.join("parser.c"),
+        "#include <stddef.h>\nconst void *tree_sitter_null_language(void) { return NULL; }\n",
+    )
+    .unwrap();
+
+    let path = scratch_dir().join(format!("null-language.{DLL_EXTENSION}"));
+    let mut config = CompileConfig::new(&src_dir, None, Some(path.clone()));
+    config.name = "null_language".to_string();
+    assert_eq!(
+        test_loader()
+            .load_language_at_path_with_name(config)
+            .unwrap_err()
+            .to_string(),
+        "Symbol tree_sitter_null_language returned a null language"
+    );
+
+    assert_eq!(
+        Language::load_from_path(&path, "tree_sitter_null_language").unwrap_err(),
+        LoadError::NullLanguage("tree_sitter_null_language".to_string())
+    );
+}
+
+#[test]
+fn test_preloading_languages() {
+    let names = ["c", "json", "python"];
+    preload_languages(&names);
+    let languages = names.map(get_language);
+
+    // Preloaded languages are returned from the cache, so each later call
+    // returns the same loaded grammar instance.
+    for (name, language) in names.iter().zip(&languages) {
+        assert_eq!(get_language(name), *language);
+    }
+}
+
+#[test]
+fn test_language_equality() {
+    let json = get_language("json");
+    let python = get_language("python");
+    assert_eq!(json, json);
+    assert_eq!(json.clone(), json);
+    assert_ne!(json, python);
+
+    let mut parser = Parser::new();
+    parser.set_language(&json).unwrap();
+    assert_eq!(parser.language().unwrap(), json);
+    let tree = parser.parse("[1]", None).unwrap();
+    assert_eq!(*tree.language(), json);
+
+    parser.set_language(&python).unwrap();
+    assert_ne!(parser.language().unwrap(), json);
+    assert_eq!(parser.language().unwrap(), python);
+}
@@ -1,10 +1,12 @@
 mod async_context_test;
+mod benchmark_test;
 mod corpus_test;
 mod detect_language;
 mod helpers;
 mod highlight_test;
 mod language_test;
 mod node_test;
+mod parse_test;
 mod parser_hang_test;
 mod parser_test;
 mod pathological_test;
@@ -1,4 +1,10 @@
-use tree_sitter::{Node, Parser, Point, Tree};
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, RandomState},
+};
+
+use indoc::indoc;
+use tree_sitter::{InputEdit, Node, Parser, Point, SexpOptions, Tree};
 
 use super::{
     get_random_edit,
@@ -187,6 +193,47 @@ fn test_node_child() {
     assert_eq!(pair_node.child_containing_descendant(null_node), None);
 }
 
+#[test]
+fn test_node_parent_and_named_siblings_round_trip() {
+    let tree = parse_json_example();
+    let root = tree.root_node();
+    assert_eq!(root.parent(), None);
+
+    let position = JSON_EXAMPLE.find("null").unwrap();
+    let leaf = root
+        .descendant_for_byte_range(position, position + 4)
+        .unwrap();
+    assert_eq!(leaf.kind(), "null");
+
+    // Walk up to the root, recording the index of each node within its parent.
+    let mut path = Vec::new();
+    let mut node = leaf;
+    while let Some(parent) = node.parent() {
+        let index = (0..parent.named_child_count())
+            .find(|&i| parent.named_child(i) == Some(node))
+            .unwrap();
+        path.push(index);
+        node = parent;
+    }
+    assert_eq!(node, root);
+
+    // Walk back down the same path, checking the named siblings along the way.
+    for &index in path.iter().rev() {
+        let child = node.named_child(index).unwrap();
+        assert_eq!(child.parent(), Some(node));
+        assert_eq!(
+            child.prev_named_sibling(),
+            index.checked_sub(1).and_then(|i| node.named_child(i))
+        );
+        assert_eq!(child.next_named_sibling(), node.named_child(index + 1));
+        node = child;
+    }
+    assert_eq!(node, leaf);
+
+    assert_eq!(root.next_named_sibling(), None);
+    assert_eq!(root.prev_named_sibling(), None);
+}
+
 #[test]
 fn test_node_children() {
     let tree = parse_json_example();
@@ -219,6 +266,95 @@ fn test_node_children() {
     );
 }
 
+#[test]
+fn test_node_utf8_text() {
+    let source = "let naïve = 1;\nconst 東京 = naïve + 2;\n";
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    let tree = parser.parse(source, None).unwrap();
+
+    let mut identifiers = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if node.kind() == "identifier" {
+            identifiers.push(node.utf8_text(source.as_bytes()).unwrap());
+        }
+        let mut cursor = node.walk();
+        stack.extend(
+            node.children(&mut cursor)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev(),
+        );
+    }
+    assert_eq!(identifiers, ["naïve", "東京", "naïve"]);
+
+    // Invalid UTF8 within the node's range is reported as an error.
+    let mut bytes = source.as_bytes().to_vec();
+    bytes[7] = 0xff;
+    let first_identifier = tree
+        .root_node()
+        .named_child(0)
+        .unwrap()
+        .named_child(0)
+        .unwrap()
+        .child_by_field_name("name")
+        .unwrap();
+    assert!(first_identifier.utf8_text(&bytes).is_err());
+}
+
+#[test]
+fn test_node_children_match_manual_cursor_traversal() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    let source = "
+        // a comment
+        function a(b, c) {
+            if (b) { return c.d(1, 2); }
+            return [b, c, 'three'];
+        }
+    ";
+    let tree = parser.parse(source, None).unwrap();
+
+    // A single cursor is shared by every call to `children`, including calls
+    // on nodes in sibling subtrees of the node it last visited.
+    let mut cursor = tree.walk();
+    let mut stack = vec![tree.root_node()];
+    let mut node_count = 0;
+    while let Some(node) = stack.pop() {
+        node_count += 1;
+
+        let mut expected_children = Vec::new();
+        let mut manual_cursor = node.walk();
+        if manual_cursor.goto_first_child() {
+            loop {
+                expected_children.push(manual_cursor.node());
+                if !manual_cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+        }
+        let expected_named_children = expected_children
+            .iter()
+            .copied()
+            .filter(|n| n.is_named())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            node.children(&mut cursor).collect::<Vec<_>>(),
+            expected_children
+        );
+        assert_eq!(
+            node.named_children(&mut cursor).collect::<Vec<_>>(),
+            expected_named_children
+        );
+
+        stack.extend(expected_children);
+    }
+    assert_eq!(node_count, tree.root_node().descendant_count());
+}
+
 #[test]
 fn test_node_children_by_field_name() {
     let mut parser = Parser::new();
@@ -378,6 +514,27 @@ fn test_node_field_name_for_named_child() {
     assert_eq!(binary_expression_node.field_name_for_named_child(3), None);
 }
 
+#[test]
+fn test_node_child_by_field_name_in_python() {
+    let language = get_language("python");
+    let mut parser = Parser::new();
+    parser.set_language(&language).unwrap();
+    let tree = parser
+        .parse("def foo(a, b):\n    return a + b\n", None)
+        .unwrap();
+    let function_node = tree.root_node().child(0).unwrap();
+    assert_eq!(function_node.kind(), "function_definition");
+
+    let body = function_node.child_by_field_name("body").unwrap();
+    assert_eq!(body.kind(), "block");
+    let body_id = language.field_id_for_name("body").unwrap();
+    assert_eq!(function_node.child_by_field_id(body_id.get()), Some(body));
+
+    // Unknown field names are not an error, they just have no child.
+    assert_eq!(function_node.child_by_field_name("not_a_field"), None);
+    assert_eq!(language.field_id_for_name("not_a_field"), None);
+}
+
 #[test]
 fn test_node_child_by_field_name_with_extra_hidden_children() {
     let mut parser = Parser::new();
@@ -526,6 +683,36 @@ fn test_node_descendant_count() {
     }
 }
 
+#[test]
+fn test_node_named_descendant_count() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("json")).unwrap();
+    let tree = parser.parse("[1, null, {\"a\": true}]", None).unwrap();
+    let root_node = tree.root_node();
+
+    // (document (array (number) (null) (object (pair key: (string (string_content)) value: (true)))))
+    assert_eq!(root_node.named_descendant_count(), 9);
+    assert_eq!(root_node.descendant_count(), 18);
+
+    let object_node = root_node.child(0).unwrap().named_child(2).unwrap();
+    assert_eq!(object_node.kind(), "object");
+    assert_eq!(object_node.named_descendant_count(), 5);
+    assert_eq!(object_node.descendant_count(), 10);
+
+    let comma_node = root_node.child(0).unwrap().child(2).unwrap();
+    assert_eq!(comma_node.kind(), ",");
+    assert_eq!(comma_node.named_descendant_count(), 0);
+    assert_eq!(comma_node.descendant_count(), 1);
+
+    assert_eq!(
+        root_node.named_descendant_count(),
+        get_all_nodes(&tree)
+            .iter()
+            .filter(|node| node.is_named())
+            .count()
+    );
+}
+
 #[test]
 fn test_descendant_count_single_node_tree() {
     let mut parser = Parser::new();
@@ -660,6 +847,122 @@ fn test_node_descendant_for_range() {
     assert_eq!(pair_node.end_position(), Point::new(6, 13));
 }
 
+#[test]
+fn test_node_descendant_for_point_range_in_html() {
+    let source = "<div>\n  <p>one <b>two</b></p>\n</div>\n";
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("html")).unwrap();
+    let tree = parser.parse(source, None).unwrap();
+    let root = tree.root_node();
+
+    // A selection from `one` through `two` is enclosed by the `p` element.
+    let node = root
+        .named_descendant_for_point_range(Point::new(1, 5), Point::new(1, 15))
+        .unwrap();
+    assert_eq!(node.kind(), "element");
+    assert_eq!(node.start_position(), Point::new(1, 2));
+    assert_eq!(node.end_position(), Point::new(1, 23));
+
+    // A selection across lines is enclosed by the `div` element.
+    let node = root
+        .descendant_for_point_range(Point::new(0, 2), Point::new(1, 4))
+        .unwrap();
+    assert_eq!(node.kind(), "element");
+    assert_eq!(node.start_position(), Point::new(0, 0));
+
+    // Points beyond the end of the document are clamped to the tree's extent.
+    let node = root
+        .descendant_for_point_range(Point::new(1, 0), Point::new(100, 100))
+        .unwrap();
+    assert_eq!(node, root);
+}
+
+#[test]
+fn test_node_descendant_for_byte_range_spanning_tokens() {
+    let source = "int main() { return x + y; }";
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("c")).unwrap();
+    let tree = parser.parse(source, None).unwrap();
+    let root = tree.root_node();
+
+    // A selection from `x` through `+` is covered by the binary expression.
+    let start = source.find('x').unwrap();
+    let end = source.find('+').unwrap() + 1;
+    let node = root.descendant_for_byte_range(start, end).unwrap();
+    assert_eq!(node.kind(), "binary_expression");
+    let node = root.named_descendant_for_byte_range(start, end).unwrap();
+    assert_eq!(node.kind(), "binary_expression");
+
+    // A selection from `return` through `x` is covered by the return statement.
+    let start = source.find("return").unwrap();
+    let end = source.find('x').unwrap() + 1;
+    let node = root.descendant_for_byte_range(start, end).unwrap();
+    assert_eq!(node.kind(), "return_statement");
+
+    // An empty range returns the node at that position.
+    let position = source.find('y').unwrap();
+    let node = root.descendant_for_byte_range(position, position).unwrap();
+    assert_eq!(node.kind(), "identifier");
+    assert_eq!(node.start_byte(), position);
+
+    // The named variant skips the anonymous `;` token.
+    let position = source.find(';').unwrap();
+    let node = root.descendant_for_byte_range(position, position).unwrap();
+    assert_eq!(node.kind(), ";");
+    let node = root
+        .named_descendant_for_byte_range(position, position)
+        .unwrap();
+    assert_eq!(node.kind(), "return_statement");
+}
+
+#[test]
+fn test_node_first_child_for_byte() {
+    let source = format!(
+        "[{}]",
+        (0..500)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("json")).unwrap();
+    let tree = parser.parse(&source, None).unwrap();
+    let array = tree.root_node().child(0).unwrap();
+    assert_eq!(array.kind(), "array");
+    assert_eq!(array.named_child_count(), 500);
+
+    let mut cursor = array.walk();
+    let children = array.children(&mut cursor).collect::<Vec<_>>();
+    for byte in 0..source.len() {
+        // The child containing the offset, or else the next child, which is
+        // the case for the spaces between the elements.
+        let child = array.first_child_for_byte(byte).unwrap();
+        if !child.byte_range().contains(&byte) {
+            assert_eq!(&source[byte..child.start_byte()], " ", "byte {byte}");
+        }
+        assert_eq!(
+            Some(child),
+            children.iter().find(|c| c.end_byte() > byte).copied()
+        );
+
+        let named_child = array.first_named_child_for_byte(byte);
+        assert_eq!(
+            named_child,
+            children
+                .iter()
+                .find(|c| c.is_named() && c.end_byte() > byte)
+                .copied(),
+            "byte {byte}"
+        );
+        if child.is_named() {
+            assert_eq!(named_child, Some(child));
+        }
+    }
+    assert_eq!(array.first_child_for_byte(source.len()), None);
+}
+
 #[test]
 fn test_node_edit() {
     let mut code = JSON_EXAMPLE.as_bytes().to_vec();
@@ -692,6 +995,91 @@ fn test_node_edit() {
     }
 }
 
+#[test]
+fn test_node_edit_before_node() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("json")).unwrap();
+    let tree = parser.parse("[1, 2]", None).unwrap();
+    let mut node = tree.root_node().child(0).unwrap().named_child(1).unwrap();
+    assert_eq!(node.byte_range(), 4..5);
+
+    // Insert a line containing three characters before the array.
+    node.edit(&InputEdit {
+        start_byte: 0,
+        old_end_byte: 0,
+        new_end_byte: 4,
+        start_position: Point::new(0, 0),
+        old_end_position: Point::new(0, 0),
+        new_end_position: Point::new(1, 0),
+    });
+    assert_eq!(node.kind(), "number");
+    assert_eq!(node.byte_range(), 8..9);
+    assert_eq!(node.start_position(), Point::new(1, 4));
+    assert_eq!(node.end_position(), Point::new(1, 5));
+
+    // Delete two characters from the start of that line.
+    node.edit(&InputEdit {
+        start_byte: 4,
+        old_end_byte: 6,
+        new_end_byte: 4,
+        start_position: Point::new(1, 0),
+        old_end_position: Point::new(1, 2),
+        new_end_position: Point::new(1, 0),
+    });
+    assert_eq!(node.byte_range(), 6..7);
+    assert_eq!(node.start_position(), Point::new(1, 2));
+    assert_eq!(node.end_position(), Point::new(1, 3));
+}
+
+#[test]
+fn test_node_equality_and_hashing() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("json")).unwrap();
+    let tree = parser.parse(JSON_EXAMPLE, None).unwrap();
+
+    // The same nodes are equal and hash the same, however they are reached.
+    let nodes = get_all_nodes(&tree);
+    let hash_builder = RandomState::new();
+    let mut nodes_by_key = HashMap::new();
+    for (i, node) in nodes.iter().enumerate() {
+        nodes_by_key.insert(*node, i);
+    }
+    assert_eq!(nodes_by_key.len(), nodes.len());
+    for (i, node) in nodes.iter().enumerate() {
+        let Some(parent) = node.parent() else {
+            assert_eq!(*node, tree.root_node());
+            continue;
+        };
+        let index = (0..parent.child_count())
+            .find(|&j| parent.child(j).unwrap().byte_range() == node.byte_range())
+            .unwrap();
+        let same_node = parent.child(index).unwrap();
+        assert_eq!(*node, same_node);
+        assert_eq!(
+            hash_builder.hash_one(node),
+            hash_builder.hash_one(same_node)
+        );
+        assert_eq!(nodes_by_key.get(&same_node), Some(&i));
+    }
+
+    // A node is not equal to its child, even if they span the same bytes.
+    let array_tree = parser.parse("[1]", None).unwrap();
+    let root_node = array_tree.root_node();
+    let array_node = root_node.child(0).unwrap();
+    assert_eq!(root_node.byte_range(), array_node.byte_range());
+    assert_ne!(root_node, array_node);
+
+    // Nodes are compared by identity, so the nodes of a copy of the tree, or
+    // of a separately parsed tree with the same structure, are not equal.
+    let tree_copy = tree.clone();
+    for (node, copied_node) in nodes.iter().zip(&get_all_nodes(&tree_copy)) {
+        assert_eq!(node.kind(), copied_node.kind());
+        assert_ne!(node, copied_node);
+    }
+    let other_tree = parser.parse(JSON_EXAMPLE, None).unwrap();
+    assert_ne!(tree.root_node(), other_tree.root_node());
+}
+
 #[test]
 fn test_root_node_with_offset() {
     let mut parser = Parser::new();
@@ -720,6 +1108,61 @@ fn test_root_node_with_offset() {
     assert_eq!(child.end_position(), Point::new(2, 10));
 }
 
+#[test]
+fn test_root_node_with_offset_for_embedded_fragment() {
+    let document = indoc! {"
+        <p>Hello</p>
+        <script>  let a = 1;
+        let bb = a;
+        </script>
+    "};
+    let start_byte = document.find("let").unwrap();
+    let end_byte = document.find("</script>").unwrap();
+    let start_point = Point::new(1, 10);
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    let tree = parser.parse(&document[start_byte..end_byte], None).unwrap();
+    let root_node = tree.root_node_with_offset(start_byte, start_point);
+    assert_eq!(root_node.start_position(), start_point);
+
+    // Each identifier's positions refer to its text in the whole document. Only
+    // nodes on the fragment's first row have their columns shifted.
+    let lines = document.lines().collect::<Vec<_>>();
+    let mut identifiers = Vec::new();
+    let mut cursor = root_node.walk();
+    let mut visited_children = false;
+    loop {
+        if visited_children {
+            if cursor.goto_next_sibling() {
+                visited_children = false;
+            } else if !cursor.goto_parent() {
+                break;
+            }
+        } else {
+            let node = cursor.node();
+            if node.kind() == "identifier" {
+                let text = &document[node.byte_range()];
+                let start = node.start_position();
+                let end = node.end_position();
+                assert_eq!(&lines[start.row][start.column..end.column], text);
+                identifiers.push((text, start));
+            }
+            if !cursor.goto_first_child() {
+                visited_children = true;
+            }
+        }
+    }
+    assert_eq!(
+        identifiers,
+        [
+            ("a", Point::new(1, 14)),
+            ("bb", Point::new(2, 4)),
+            ("a", Point::new(2, 9)),
+        ]
+    );
+}
+
 #[test]
 fn test_node_is_extra() {
     let mut parser = Parser::new();
@@ -735,6 +1178,40 @@ fn test_node_is_extra() {
     assert!(comment_node.is_extra());
 }
 
+#[test]
+fn test_node_is_missing_in_c() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("c")).unwrap();
+    let tree = parser.parse("int a = 1\nint b = 2;\n", None).unwrap();
+
+    let root_node = tree.root_node();
+    let declaration = root_node.child(0).unwrap();
+    let semicolon = declaration.child(declaration.child_count() - 1).unwrap();
+
+    assert_eq!(declaration.kind(), "declaration");
+    assert_eq!(semicolon.kind(), ";");
+    assert!(semicolon.is_missing());
+    assert!(!semicolon.is_error());
+    assert!(!semicolon.is_extra());
+    assert_eq!(semicolon.byte_range(), 9..9);
+
+    // The missing node makes its ancestors report an error, but they are not
+    // missing themselves.
+    assert!(declaration.has_error());
+    assert!(root_node.has_error());
+    assert!(!declaration.is_missing());
+    assert!(!root_node.is_missing());
+    assert!(!root_node.has_changes());
+
+    let second_declaration = root_node.child(1).unwrap();
+    assert!(!second_declaration.has_error());
+    let second_semicolon = second_declaration
+        .child(second_declaration.child_count() - 1)
+        .unwrap();
+    assert_eq!(second_semicolon.kind(), ";");
+    assert!(!second_semicolon.is_missing());
+}
+
 #[test]
 fn test_node_sexp() {
     let mut parser = Parser::new();
@@ -752,6 +1229,233 @@ fn test_node_sexp() {
     assert_eq!(identifier_node.to_sexp(), "(identifier)");
 }
 
+#[test]
+fn test_node_write_sexp() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    let tree = parser.parse("if (a) { b(c, d); }", None).unwrap();
+    let root_node = tree.root_node();
+
+    let mut buffer = String::new();
+    root_node.write_sexp(&mut buffer);
+    assert_eq!(buffer, root_node.to_sexp());
+
+    // Later calls reuse the buffer's capacity.
+    let capacity = buffer.capacity();
+    let call_node = root_node
+        .descendant_for_byte_range(9, 9)
+        .unwrap()
+        .parent()
+        .unwrap();
+    buffer.clear();
+    call_node.write_sexp(&mut buffer);
+    assert_eq!(buffer, call_node.to_sexp());
+    assert_eq!(buffer.capacity(), capacity);
+
+    // The S-expression is appended to any existing contents.
+    buffer.insert_str(0, "call: ");
+    let identifier_node = call_node.child(0).unwrap();
+    identifier_node.write_sexp(&mut buffer);
+    assert_eq!(
+        buffer,
+        "call: (call_expression function: (identifier) arguments: (arguments (identifier) (identifier)))(identifier)"
+    );
+
+    // A buffer whose spare capacity is too small is grown to fit.
+    let mut buffer = String::with_capacity(16);
+    buffer.push_str("root: ");
+    root_node.write_sexp(&mut buffer);
+    assert_eq!(buffer, format!("root: {}", root_node.to_sexp()));
+}
+
+const WITH_RANGES: SexpOptions = SexpOptions {
+    include_ranges: true,
+    named_only: false,
+    max_depth: None,
+};
+
+const NAMED_ONLY: SexpOptions = SexpOptions {
+    include_ranges: false,
+    named_only: true,
+    max_depth: None,
+};
+
+fn with_limit(max_depth: usize) -> SexpOptions {
+    SexpOptions {
+        max_depth: Some(max_depth),
+        ..SexpOptions::default()
+    }
+}
+
+#[test]
+fn test_node_sexp_with_ranges() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("go")).unwrap();
+    let source = "package main\n\nfunc add(a int) int {\n\treturn a + 1\n}\n";
+    let tree = parser.parse(source, None).unwrap();
+    let root_node = tree.root_node();
+    assert_eq!(
+        root_node.to_sexp_with_options(WITH_RANGES),
+        concat!(
+            "(source_file [0, 52] ",
+            "(package_clause [0, 12] (package_identifier [8, 12])) ",
+            "(function_declaration [14, 51] ",
+            "name: (identifier [19, 22]) ",
+            "parameters: (parameter_list [22, 29] ",
+            "(parameter_declaration [23, 28] name: (identifier [23, 24]) type: (type_identifier [25, 28]))) ",
+            "result: (type_identifier [30, 33]) ",
+            "body: (block [34, 51] ",
+            "(return_statement [37, 49] ",
+            "(expression_list [44, 49] ",
+            "(binary_expression [44, 49] left: (identifier [44, 45]) right: (int_literal [48, 49])))))))",
+        )
+    );
+
+    // Ranges are absolute, even when starting from a descendant.
+    let function_node = root_node.named_child(1).unwrap();
+    let func_node = function_node.child(0).unwrap();
+    assert_eq!(
+        func_node.to_sexp_with_options(WITH_RANGES),
+        "(\"func\" [14, 18])"
+    );
+    assert_eq!(
+        function_node
+            .child_by_field_name("result")
+            .unwrap()
+            .to_sexp_with_options(WITH_RANGES),
+        "(type_identifier [30, 33])"
+    );
+}
+
+#[test]
+fn test_node_debug_tree() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("python")).unwrap();
+    let source = "def foo(a):\n    return a + 100000000000000000000\n";
+    let tree = parser.parse(source, None).unwrap();
+
+    assert_eq!(
+        tree.root_node().debug_tree(source.as_bytes()),
+        indoc! {r#"
+            module "def foo(a):\n    return a…" [0..49]
+              function_definition "def foo(a):\n    return a…" [0..48]
+                name: identifier "foo" [4..7]
+                parameters: parameters "(a)" [7..10]
+                  identifier "a" [8..9]
+                body: block "return a + 1000000000000…" [16..48]
+                  return_statement "return a + 1000000000000…" [16..48]
+                    binary_operator "a + 10000000000000000000…" [23..48]
+                      left: identifier "a" [23..24]
+                      right: integer "100000000000000000000" [27..48]
+        "#}
+    );
+
+    // The listing starts at the given node, without its field name.
+    let parameters = tree
+        .root_node()
+        .named_child(0)
+        .unwrap()
+        .child_by_field_name("parameters")
+        .unwrap();
+    assert_eq!(
+        parameters.debug_tree(source.as_bytes()),
+        "parameters \"(a)\" [7..10]\n  identifier \"a\" [8..9]\n"
+    );
+}
+
+#[test]
+fn test_node_sexp_with_limit() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("json")).unwrap();
+
+    let tree = parser.parse("[1, [2, [3, []]]]", None).unwrap();
+    let root_node = tree.root_node();
+    assert_eq!(
+        root_node.to_sexp_with_options(with_limit(usize::MAX)),
+        root_node.to_sexp()
+    );
+    assert_eq!(
+        root_node.to_sexp_with_options(with_limit(3)),
+        "(document (array (number) (array (number) (array ...))))"
+    );
+    assert_eq!(
+        root_node.to_sexp_with_options(with_limit(0)),
+        "(document ...)"
+    );
+
+    // Nodes without named children are written in full at the limit.
+    let inner_node = root_node.descendant_for_byte_range(12, 14).unwrap();
+    assert_eq!(inner_node.kind(), "array");
+    assert_eq!(inner_node.to_sexp_with_options(with_limit(0)), "(array)");
+
+    // Deeply nested input must not overflow the stack.
+    let depth = 100_000;
+    let source = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let tree = parser.parse(&source, None).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp_with_options(with_limit(3)),
+        "(document (array (array (array ...))))"
+    );
+}
+
+#[test]
+fn test_node_sexp_named_only() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+
+    let tree = parser.parse("if (a) { b(c, d);", None).unwrap();
+    let root_node = tree.root_node();
+    assert_eq!(
+        root_node.to_sexp(),
+        concat!(
+            "(program (if_statement condition: (parenthesized_expression (identifier)) ",
+            "consequence: (statement_block (expression_statement (call_expression ",
+            "function: (identifier) arguments: (arguments (identifier) (identifier)))) ",
+            "(MISSING \"}\"))))",
+        )
+    );
+    assert_eq!(
+        root_node.to_sexp_with_options(NAMED_ONLY),
+        concat!(
+            "(program (if_statement condition: (parenthesized_expression (identifier)) ",
+            "consequence: (statement_block (expression_statement (call_expression ",
+            "function: (identifier) arguments: (arguments (identifier) (identifier)))))))",
+        )
+    );
+
+    // Without missing tokens, the output is the same as `to_sexp`.
+    let tree = parser.parse("if (a) { b(c, d); }", None).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp_with_options(NAMED_ONLY),
+        root_node.to_sexp_with_options(NAMED_ONLY)
+    );
+    assert_eq!(
+        tree.root_node().to_sexp_with_options(NAMED_ONLY),
+        tree.root_node().to_sexp()
+    );
+}
+
+#[test]
+fn test_node_sexp_with_combined_options() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+
+    let tree = parser.parse("if (a) { b(c);", None).unwrap();
+    let options = SexpOptions {
+        include_ranges: true,
+        named_only: true,
+        max_depth: Some(3),
+    };
+    assert_eq!(
+        tree.root_node().to_sexp_with_options(options),
+        concat!(
+            "(program [0, 14] (if_statement [0, 14] ",
+            "condition: (parenthesized_expression [3, 6] (identifier [4, 5])) ",
+            "consequence: (statement_block [7, 14] (expression_statement [9, 14] ...))))",
+        )
+    );
+}
+
 #[test]
 fn test_node_field_names() {
     let (parser_name, parser_code) = generate_parser_for_grammar(
@@ -942,6 +1646,74 @@ fn test_node_is_named_but_aliased_as_anonymous() {
     assert_eq!(root_node.named_child(0).unwrap().kind(), "c");
 }
 
+#[test]
+fn test_node_grammar_name_and_id_ignore_aliases() {
+    let (parser_name, parser_code) = generate_parser_for_grammar(
+        r#"
+        {
+            "name": "test_grammar_with_aliases",
+            "extras": [
+                {"type": "PATTERN", "value": "\\s+"}
+            ],
+            "rules": {
+                "program": {
+                    "type": "SEQ",
+                    "members": [
+                        {
+                            "type": "ALIAS",
+                            "content": {"type": "SYMBOL", "name": "word"},
+                            "named": true,
+                            "value": "name"
+                        },
+                        {
+                            "type": "ALIAS",
+                            "content": {"type": "STRING", "value": "+"},
+                            "named": false,
+                            "value": "plus"
+                        },
+                        {"type": "SYMBOL", "name": "word"},
+                        {"type": "STRING", "value": "+"}
+                    ]
+                },
+                "word": {"type": "PATTERN", "value": "[a-z]+"}
+            }
+        }
+    "#,
+    )
+    .unwrap();
+
+    let mut parser = Parser::new();
+    let language = get_test_language(&parser_name, &parser_code, None);
+    parser.set_language(&language).unwrap();
+    let tree = parser.parse("a + b +", None).unwrap();
+    let root_node = tree.root_node();
+    assert_eq!(root_node.to_sexp(), "(program (name) (word))");
+
+    let name_node = root_node.child(0).unwrap();
+    let plus_node = root_node.child(1).unwrap();
+    let word_node = root_node.child(2).unwrap();
+    let last_plus_node = root_node.child(3).unwrap();
+
+    assert_eq!(name_node.kind(), "name");
+    assert_eq!(name_node.grammar_name(), "word");
+    assert_eq!(name_node.grammar_id(), word_node.grammar_id());
+    assert_ne!(name_node.kind_id(), word_node.kind_id());
+
+    assert_eq!(plus_node.kind(), "plus");
+    assert_eq!(plus_node.grammar_name(), "+");
+    assert!(!plus_node.is_named());
+    assert_eq!(plus_node.grammar_id(), last_plus_node.grammar_id());
+    assert_ne!(plus_node.kind_id(), last_plus_node.kind_id());
+
+    // Without an alias, the grammar name and id are the node's kind. The
+    // tokens above are also used without aliases; otherwise the generator
+    // would rename the symbols themselves.
+    for node in [word_node, last_plus_node] {
+        assert_eq!(node.grammar_name(), node.kind());
+        assert_eq!(node.grammar_id(), node.kind_id());
+    }
+}
+
 #[test]
 fn test_node_numeric_symbols_respect_simple_aliases() {
     let mut parser = Parser::new();
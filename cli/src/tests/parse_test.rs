@@ -0,0 +1,232 @@
+use std::fs;
+
+use serde_json::{json, Value};
+use tree_sitter::Parser;
+
+use super::helpers::fixtures::{get_language, scratch_dir};
+use crate::parse::{
+    diff_against_baseline, parse_file_at_path, write_tree_json, ParseFileOptions, ParseOutput,
+};
+
+#[test]
+fn test_write_tree_json() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("c")).unwrap();
+    let tree = parser.parse("int x", None).unwrap();
+
+    let mut buffer = Vec::new();
+    write_tree_json(&mut buffer, &tree, None).unwrap();
+    let json = serde_json::from_slice::<Value>(&buffer).unwrap();
+
+    let point = |row: usize, column: usize| json!({ "row": row, "column": column });
+    assert_eq!(
+        json,
+        json!({
+            "type": "translation_unit",
+            "start_byte": 0,
+            "end_byte": 5,
+            "start_point": point(0, 0),
+            "end_point": point(0, 5),
+            "field": null,
+            "named": true,
+            "is_error": false,
+            "is_missing": false,
+            "children": [{
+                "type": "declaration",
+                "start_byte": 0,
+                "end_byte": 5,
+                "start_point": point(0, 0),
+                "end_point": point(0, 5),
+                "field": null,
+                "named": true,
+                "is_error": false,
+                "is_missing": false,
+                "children": [
+                    {
+                        "type": "primitive_type",
+                        "start_byte": 0,
+                        "end_byte": 3,
+                        "start_point": point(0, 0),
+                        "end_point": point(0, 3),
+                        "field": "type",
+                        "named": true,
+                        "is_error": false,
+                        "is_missing": false,
+                        "children": [],
+                    },
+                    {
+                        "type": "identifier",
+                        "start_byte": 4,
+                        "end_byte": 5,
+                        "start_point": point(0, 4),
+                        "end_point": point(0, 5),
+                        "field": "declarator",
+                        "named": true,
+                        "is_error": false,
+                        "is_missing": false,
+                        "children": [],
+                    },
+                    {
+                        "type": ";",
+                        "start_byte": 5,
+                        "end_byte": 5,
+                        "start_point": point(0, 5),
+                        "end_point": point(0, 5),
+                        "field": null,
+                        "named": false,
+                        "is_error": false,
+                        "is_missing": true,
+                        "children": [],
+                    },
+                ],
+            }],
+        })
+    );
+}
+
+#[test]
+fn test_write_tree_json_with_indentation() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("c")).unwrap();
+    let tree = parser.parse("int x", None).unwrap();
+    let write = |indent| {
+        let mut buffer = Vec::new();
+        write_tree_json(&mut buffer, &tree, indent).unwrap();
+        String::from_utf8(buffer).unwrap()
+    };
+
+    let compact = write(None);
+    assert!(!compact.contains('\n'));
+
+    let pretty = write(Some(4));
+    assert!(pretty.contains("\n    \"type\": \"translation_unit\""));
+    assert!(pretty.contains("\n        {\n            \"type\": \"declaration\""));
+    assert!(!pretty.contains("\n  \"type\""));
+
+    // The indentation doesn't change the tree.
+    assert_eq!(
+        serde_json::from_str::<Value>(&compact).unwrap(),
+        serde_json::from_str::<Value>(&pretty).unwrap()
+    );
+    assert_eq!(write(Some(2)).lines().count(), pretty.lines().count());
+}
+
+#[test]
+fn test_write_tree_json_with_deeply_nested_tree() {
+    let depth = 100_000;
+    let source = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("json")).unwrap();
+    let tree = parser.parse(&source, None).unwrap();
+
+    let mut buffer = Vec::new();
+    write_tree_json(&mut buffer, &tree, None).unwrap();
+    let json = String::from_utf8(buffer).unwrap();
+    assert_eq!(json.matches(r#""type":"array""#).count(), depth);
+    assert!(json.starts_with(r#"{"type":"document","start_byte":0,"#));
+    assert!(json.ends_with("]}]}"));
+}
+
+#[test]
+fn test_parse_file_with_repeat_stats() {
+    let path = scratch_dir().join("repeat_stats.json");
+    fs::write(&path, "[1, {\"a\": null}]").unwrap();
+
+    let mut parser = Parser::new();
+    let mut opts = ParseFileOptions {
+        language: get_language("json"),
+        path: &path,
+        edits: &[],
+        max_path_length: 0,
+        output: ParseOutput::Quiet,
+        print_time: false,
+        repeat: 3,
+        print_repeat_stats: true,
+        timeout: 0,
+        debug: false,
+        debug_graph: false,
+        cancellation_flag: None,
+        encoding: None,
+        open_log: false,
+        baseline: None,
+        json_indent: None,
+        use_color: false,
+    };
+
+    let result = parse_file_at_path(&mut parser, &opts).unwrap();
+    assert!(result.successful);
+    let stats = result.repeat_stats.unwrap();
+    assert_eq!(stats.durations.len(), 3);
+    assert_eq!(stats.node_count, 16);
+
+    let report = stats.to_string();
+    let lines = report.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    for (i, line) in lines[..3].iter().enumerate() {
+        assert!(line.starts_with(&format!("parse {}: ", i + 1)), "{line}");
+        assert!(line.ends_with(" ms"), "{line}");
+    }
+    assert!(lines[3].starts_with("min: "));
+    assert!(lines[3].ends_with("; nodes: 16"));
+
+    // With edits, each repeated parse reuses the previous tree.
+    let edits = ["0,1 1 2"];
+    opts.edits = &edits;
+    let result = parse_file_at_path(&mut parser, &opts).unwrap();
+    let stats = result.repeat_stats.unwrap();
+    assert_eq!(stats.durations.len(), 3);
+    assert_eq!(stats.node_count, 16);
+}
+
+#[test]
+fn test_parse_file_with_baseline() {
+    let path = scratch_dir().join("baseline.json");
+    fs::write(&path, "[1, null]").unwrap();
+
+    let matching_baseline = "
+        (document [0, 0] - [0, 9]
+          (array [0, 0] - [0, 9]
+            (number [0, 1] - [0, 2])
+            (null [0, 4] - [0, 8])))
+    ";
+    let differing_baseline = "
+        ; The second element used to be parsed as a number.
+        (document (array (number) (number)))
+    ";
+
+    let mut parser = Parser::new();
+    let mut opts = ParseFileOptions {
+        language: get_language("json"),
+        path: &path,
+        edits: &[],
+        max_path_length: 0,
+        output: ParseOutput::Quiet,
+        print_time: false,
+        repeat: 1,
+        print_repeat_stats: false,
+        timeout: 0,
+        debug: false,
+        debug_graph: false,
+        cancellation_flag: None,
+        encoding: None,
+        open_log: false,
+        baseline: Some(matching_baseline),
+        json_indent: None,
+        use_color: false,
+    };
+    let result = parse_file_at_path(&mut parser, &opts).unwrap();
+    assert!(result.successful);
+    assert!(result.matches_baseline);
+
+    // A tree that differs from the baseline is still a successful parse.
+    opts.baseline = Some(differing_baseline);
+    let result = parse_file_at_path(&mut parser, &opts).unwrap();
+    assert!(result.successful);
+    assert!(!result.matches_baseline);
+
+    let tree = parser.parse("[1, null]", None).unwrap();
+    assert_eq!(diff_against_baseline(&tree, matching_baseline), None);
+    let (actual, expected) = diff_against_baseline(&tree, differing_baseline).unwrap();
+    assert!(actual.contains("(null)"));
+    assert!(!expected.contains("(null)"));
+}
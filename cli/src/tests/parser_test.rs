@@ -1,21 +1,33 @@
 use std::{
-    sync::atomic::{AtomicUsize, Ordering},
+    env, fs, io,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     thread, time,
 };
 
-use tree_sitter::{IncludedRangesError, InputEdit, LogType, Parser, Point, Range};
+use indoc::indoc;
+use rand::{prelude::StdRng, Rng, SeedableRng};
+use tree_sitter::{
+    ChunkedText, IncludedRangesError, InputEdit, LanguageError, LogEvent, LogType, MultiParser,
+    ParseError, Parser, Point, Query, Range, LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION,
+};
+use tree_sitter_loader::CompileConfig;
 use tree_sitter_proc_macro::retry;
 
 use super::helpers::{
     allocations,
     edits::ReadRecorder,
-    fixtures::{get_language, get_test_language},
+    fixtures::{get_language, get_test_language, scratch_dir, test_loader},
 };
 use crate::{
-    fuzz::edits::Edit,
+    fuzz::edits::{assert_incremental_matches, Edit},
     generate::{generate_parser_for_grammar, load_grammar_file},
-    parse::perform_edit,
+    parse::{perform_edit, position_for_offset},
     tests::{helpers::fixtures::fixtures_dir, invert_edit},
+    util,
 };
 
 #[test]
@@ -85,6 +97,28 @@ fn test_parsing_with_logging() {
     assert!(row_starts_from_0);
 }
 
+#[test]
+fn test_parsing_with_structured_logging() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("json")).unwrap();
+
+    let mut events = Vec::new();
+    parser.set_structured_logger(Box::new(|event| events.push(event)));
+    parser.parse("[1]", None).unwrap();
+
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, LogEvent::Shift { .. })));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, LogEvent::Lex { character: '[', .. })));
+    assert!(events.contains(&LogEvent::Reduce {
+        symbol: "array".to_string(),
+        count: 3,
+    }));
+    assert!(events.contains(&LogEvent::Raw("done".to_string())));
+}
+
 #[test]
 #[cfg(unix)]
 fn test_parsing_with_debug_graph_enabled() {
@@ -111,6 +145,22 @@ fn test_parsing_with_debug_graph_enabled() {
     }
 }
 
+#[test]
+#[cfg(any(unix, windows))]
+fn test_parsing_with_captured_debug_graphs() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("json")).unwrap();
+
+    let capture = util::capture_graphs(&mut parser).unwrap();
+    parser.parse("[1, null]", None).unwrap();
+    let graphs = capture.finish(&mut parser).unwrap();
+
+    assert!(graphs.contains("digraph stack {"));
+    assert!(graphs.contains(r#"label="lexed_lookahead sym:number, size:1""#));
+    assert!(graphs.contains(r#"label="lexed_lookahead sym:null, size:5""#));
+    assert!(graphs.contains(r#"[label="'['""#));
+}
+
 #[test]
 fn test_parsing_with_custom_utf8_input() {
     let mut parser = Parser::new();
@@ -154,6 +204,335 @@ fn test_parsing_with_custom_utf8_input() {
     assert_eq!(root.child(0).unwrap().kind(), "function_item");
 }
 
+#[test]
+fn test_parsing_a_prefix_of_a_document() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+
+    let source = "const a = 1;\nfunction b(c) { return c + 1; }\nlet d = [1, 2, 3];\n".repeat(10);
+    let tree = parser.parse_prefix(&source, 100, None).unwrap();
+    let root = tree.root_node();
+    assert_eq!(root.end_byte(), source.len());
+    assert_eq!(root.end_position(), Point::new(30, 0));
+
+    // The text after the boundary is a single error node.
+    let remainder = root.child(root.child_count() - 1).unwrap();
+    assert!(remainder.is_error());
+    assert!(remainder.is_extra());
+    assert_eq!(remainder.byte_range(), 100..source.len());
+    assert_eq!(remainder.start_position(), Point::new(4, 23));
+    assert_eq!(remainder.child_count(), 0);
+
+    // The last statement is cut off at the boundary.
+    let last_child = root.child(root.child_count() - 2).unwrap();
+    assert_eq!(last_child.start_byte(), 77);
+    assert!(last_child.end_byte() <= 100);
+
+    // The statements before the boundary match a parse of the whole document.
+    let full_tree = parser.parse(&source, None).unwrap();
+    for i in 0..root.child_count() - 2 {
+        assert_eq!(
+            root.child(i).unwrap().to_sexp(),
+            full_tree.root_node().child(i).unwrap().to_sexp()
+        );
+    }
+
+    // A limit past the end of the text parses the whole text.
+    let tree = parser.parse_prefix(&source, usize::MAX, None).unwrap();
+    assert_eq!(tree.root_node().to_sexp(), full_tree.root_node().to_sexp());
+
+    // A limit of zero leaves the whole text unparsed.
+    let tree = parser.parse_prefix(&source, 0, None).unwrap();
+    let root = tree.root_node();
+    assert_eq!(root.named_child_count(), 1);
+    assert_eq!(root.named_child(0).unwrap().byte_range(), 0..source.len());
+    assert!(root.named_child(0).unwrap().is_error());
+}
+
+#[test]
+fn test_parsing_progressively_larger_prefixes_of_a_document() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+
+    let source = "const a = 1;\nfunction b(c) { return c + 1; }\nlet d = [1, 2, 3];\n".repeat(10);
+    let mut tree = parser.parse_prefix(&source, 50, None).unwrap();
+    for byte_limit in [100, 150, 400, source.len()] {
+        tree = parser
+            .parse_prefix(&source, byte_limit, Some(&tree))
+            .unwrap();
+        assert_eq!(
+            tree.root_node().to_sexp(),
+            parser
+                .parse_prefix(&source, byte_limit, None)
+                .unwrap()
+                .root_node()
+                .to_sexp()
+        );
+    }
+    assert!(!tree.root_node().has_error());
+}
+
+#[test]
+fn test_parsing_a_prefix_keeps_the_parser_settings() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+
+    // The parser's own included ranges are cut off at the limit.
+    let source = "a;\nb;\nc;\nd;\n";
+    let ranges = [
+        Range {
+            start_byte: 3,
+            end_byte: 5,
+            start_point: Point::new(1, 0),
+            end_point: Point::new(1, 2),
+        },
+        Range {
+            start_byte: 6,
+            end_byte: 11,
+            start_point: Point::new(2, 0),
+            end_point: Point::new(3, 2),
+        },
+    ];
+    parser.set_included_ranges(&ranges).unwrap();
+    let tree = parser.parse_prefix(source, 8, None).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        "(program (expression_statement (identifier)) (expression_statement (identifier)) (UNEXPECTED '\\n'))"
+    );
+    assert_eq!(tree.root_node().child(2).unwrap().byte_range(), 8..12);
+    assert_eq!(parser.included_ranges(), ranges);
+    assert!(!parser.wrap_remainder());
+
+    // The remainder can also be wrapped without parsing a prefix.
+    parser.set_wrap_remainder(true);
+    let tree = parser.parse(source, None).unwrap();
+    let root = tree.root_node();
+    assert_eq!(root.start_byte(), 3);
+    assert_eq!(root.end_byte(), source.len());
+    assert_eq!(
+        root.child(root.child_count() - 1).unwrap().byte_range(),
+        11..12
+    );
+
+    // The remainder of UTF16 text is measured in bytes, like the rest of it.
+    let utf16_source = source.encode_utf16().collect::<Vec<_>>();
+    parser
+        .set_included_ranges(&[Range {
+            start_byte: 0,
+            end_byte: 6,
+            start_point: Point::new(0, 0),
+            end_point: Point::new(1, 0),
+        }])
+        .unwrap();
+    let tree = parser.parse_utf16(&utf16_source, None).unwrap();
+    let root = tree.root_node();
+    assert_eq!(root.end_byte(), 2 * utf16_source.len());
+    assert_eq!(root.end_position(), Point::new(4, 0));
+    assert_eq!(root.child(root.child_count() - 1).unwrap().start_byte(), 6);
+}
+
+#[test]
+fn test_parsing_random_bytes_without_panicking() {
+    let mut parser = Parser::new();
+    assert_eq!(
+        parser.try_parse_utf8("x", None).unwrap_err(),
+        ParseError::NoLanguage
+    );
+
+    let mut rand = StdRng::seed_from_u64(0);
+    for language_name in ["html", "javascript", "json", "python", "rust"] {
+        parser.set_language(&get_language(language_name)).unwrap();
+        for _ in 0..200 {
+            let len = rand.gen_range(0..256);
+            let text = (0..len)
+                .map(|_| {
+                    // Mostly printable ASCII, so that some tokens are recognized.
+                    if rand.gen_bool(0.8) {
+                        rand.gen_range(b' '..=b'~')
+                    } else {
+                        rand.gen()
+                    }
+                })
+                .collect::<Vec<u8>>();
+            let tree = parser.try_parse_utf8(&text, None).unwrap_or_else(|e| {
+                panic!("failed to parse {text:?} as {language_name}: {e}");
+            });
+            assert!(tree.root_node().end_byte() <= text.len());
+        }
+    }
+}
+
+#[test]
+fn test_parsing_chunked_text() {
+    struct Rope {
+        left: String,
+        right: String,
+    }
+
+    impl ChunkedText for Rope {
+        fn chunk(&self, byte_offset: usize) -> &[u8] {
+            if byte_offset < self.left.len() {
+                &self.left.as_bytes()[byte_offset..]
+            } else {
+                self.right.chunk(byte_offset - self.left.len())
+            }
+        }
+    }
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+
+    // The seam between the two pieces falls inside an identifier.
+    let source = "const value = compute(1, 2);\nconsole.log(value);\n";
+    let rope = Rope {
+        left: source[..18].to_string(),
+        right: source[18..].to_string(),
+    };
+    assert_eq!(rope.left, "const value = comp");
+
+    let tree = parser.parse_chunked(&rope, None).unwrap();
+    let expected_tree = parser.parse(source, None).unwrap();
+    assert!(!tree.root_node().has_error());
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        expected_tree.root_node().to_sexp()
+    );
+    assert_eq!(tree.root_node().end_byte(), source.len());
+
+    // Strings and byte slices can be parsed directly.
+    let tree = parser.parse_chunked(source, None).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        expected_tree.root_node().to_sexp()
+    );
+    let tree = parser.parse_chunked(source.as_bytes(), None).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        expected_tree.root_node().to_sexp()
+    );
+}
+
+#[test]
+fn test_parsing_with_chunks_that_split_characters() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+
+    let source = "const s = \"héllo 😀\"; // ünïcode\nlet 变量 = 1;\n";
+    let bytes = source.as_bytes();
+    let expected_tree = parser.parse(source, None).unwrap();
+    assert!(!expected_tree.root_node().has_error());
+
+    // Return one byte at a time, so that every multi-byte character is split.
+    let tree = parser
+        .parse_with(
+            &mut |offset, _| {
+                bytes
+                    .get(offset..(offset + 1).min(bytes.len()))
+                    .unwrap_or_default()
+            },
+            None,
+        )
+        .unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        expected_tree.root_node().to_sexp()
+    );
+
+    let identifier = tree
+        .root_node()
+        .descendant_for_point_range(Point::new(1, 4), Point::new(1, 4))
+        .unwrap();
+    assert_eq!(identifier.kind(), "identifier");
+    assert_eq!(identifier.utf8_text(bytes).unwrap(), "变量");
+    assert_eq!(identifier.end_position(), Point::new(1, 10));
+}
+
+#[test]
+fn test_parsing_with_a_chunk_size_hint() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("rust")).unwrap();
+
+    let source = "fn main() {\n    let x = foo(1, 2);\n    bar(x);\n}\n".repeat(20);
+    let lines = source.split_inclusive('\n').collect::<Vec<_>>();
+    let line_starts = lines
+        .iter()
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some(start)
+        })
+        .collect::<Vec<_>>();
+
+    // Return at most the rest of the current line, checking that the given
+    // position agrees with the given byte offset.
+    let call_count = AtomicUsize::new(0);
+    let mut read = |offset: usize, position: Point| {
+        call_count.fetch_add(1, Ordering::SeqCst);
+        if position.row >= lines.len() {
+            return &[][..];
+        }
+        assert_eq!(offset, line_starts[position.row] + position.column);
+        &lines[position.row].as_bytes()[position.column..]
+    };
+
+    let tree = parser.parse_with(&mut read, None).unwrap();
+    let unbuffered_call_count = call_count.swap(0, Ordering::SeqCst);
+
+    let buffered_tree = parser.parse_with_chunk_size(&mut read, 256, None).unwrap();
+    let buffered_call_count = call_count.swap(0, Ordering::SeqCst);
+
+    assert!(!tree.root_node().has_error());
+    assert_eq!(
+        buffered_tree.root_node().to_sexp(),
+        tree.root_node().to_sexp()
+    );
+    assert!(buffered_call_count < unbuffered_call_count);
+
+    // A chunk size smaller than the callback's chunks does not batch anything.
+    let tree = parser.parse_with_chunk_size(&mut read, 1, None).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        buffered_tree.root_node().to_sexp()
+    );
+    assert!(call_count.load(Ordering::SeqCst) > buffered_call_count);
+
+    // A chunk size of zero still reads the whole input.
+    let tree = parser.parse_with_chunk_size(&mut read, 0, None).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        buffered_tree.root_node().to_sexp()
+    );
+}
+
+#[test]
+fn test_parsing_utf16_matches_utf8() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+
+    let source = "const café = \"☕\";\nlet ñ = café + 1;";
+    let utf16_source = source.encode_utf16().collect::<Vec<_>>();
+
+    let utf8_tree = parser.parse(source, None).unwrap();
+    let utf16_tree = parser.parse_utf16(&utf16_source, None).unwrap();
+    assert_eq!(
+        utf16_tree.root_node().to_sexp(),
+        utf8_tree.root_node().to_sexp()
+    );
+
+    let identifier = utf16_tree
+        .root_node()
+        .named_child(1)
+        .unwrap()
+        .named_child(0)
+        .unwrap()
+        .child_by_field_name("name")
+        .unwrap();
+    assert_eq!(
+        String::from_utf16(identifier.utf16_text(&utf16_source)).unwrap(),
+        "ñ"
+    );
+}
+
 #[test]
 fn test_parsing_with_custom_utf16_input() {
     let mut parser = Parser::new();
@@ -214,6 +593,97 @@ fn test_parsing_with_callback_returning_owned_strings() {
     );
 }
 
+#[test]
+fn test_parsing_from_a_stream() {
+    struct SeekCounter<R> {
+        inner: R,
+        seek_count: usize,
+    }
+
+    impl<R: io::Read> io::Read for SeekCounter<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.inner.read(buf)
+        }
+    }
+
+    impl<R: io::Seek> io::Seek for SeekCounter<R> {
+        fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+            self.seek_count += 1;
+            self.inner.seek(pos)
+        }
+    }
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("rust")).unwrap();
+
+    let source = include_str!("parser_test.rs");
+    let mut reader = SeekCounter {
+        inner: io::Cursor::new(source),
+        seek_count: 0,
+    };
+    let tree = parser.parse_stream(&mut reader, None).unwrap().unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        parser.parse(source, None).unwrap().root_node().to_sexp()
+    );
+
+    // Apart from querying the stream's starting position, reading the file
+    // from start to finish never needs to seek.
+    assert_eq!(reader.seek_count, 1);
+
+    // The external scanner skips whitespace before the lexer resets to the
+    // start of the whitespace, so whitespace that spans the boundary between
+    // the stream's 64KiB chunks is read twice. Reading it again from the end
+    // of the previous chunk doesn't need to seek either.
+    let line = "fn a() -> u8 { b(c, d) }\n";
+    let chunk_size = 64 * 1024;
+    let source = format!(
+        "{}{}{}",
+        line.repeat(chunk_size / line.len()),
+        " ".repeat(line.len() * 2),
+        line.repeat(100),
+    );
+    let mut reader = SeekCounter {
+        inner: io::Cursor::new(source.as_str()),
+        seek_count: 0,
+    };
+    let tree = parser.parse_stream(&mut reader, None).unwrap().unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        parser.parse(&source, None).unwrap().root_node().to_sexp()
+    );
+    assert_eq!(reader.seek_count, 1);
+}
+
+#[test]
+fn test_parsing_from_a_stream_that_fails() {
+    struct FailingReader(io::Cursor<&'static str>);
+
+    impl io::Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.position() > 0 {
+                return Err(io::Error::other("disk on fire"));
+            }
+            let len = buf.len().min(4);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    impl io::Seek for FailingReader {
+        fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+            self.0.seek(pos)
+        }
+    }
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("rust")).unwrap();
+
+    let error = parser
+        .parse_stream(FailingReader(io::Cursor::new("fn a() {}")), None)
+        .unwrap_err();
+    assert_eq!(error.to_string(), "disk on fire");
+}
+
 #[test]
 fn test_parsing_text_with_byte_order_mark() {
     let mut parser = Parser::new();
@@ -364,6 +834,58 @@ fn test_parsing_after_editing_beginning_of_code() {
     assert_eq!(recorder.strings_read(), vec!["123 || 5 "]);
 }
 
+#[test]
+fn test_parsing_after_editing_matches_full_parse() {
+    let source = indoc! {"
+        int add(int a, int b) {
+          int sum = a + b;
+          return sum;
+        }
+    "};
+    assert_incremental_matches(
+        &get_language("c"),
+        source,
+        &[
+            // Replace an operand in the middle of the function.
+            (
+                InputEdit {
+                    start_byte: 36,
+                    old_end_byte: 37,
+                    new_end_byte: 43,
+                    start_position: Point::new(1, 12),
+                    old_end_position: Point::new(1, 13),
+                    new_end_position: Point::new(1, 19),
+                },
+                b"(a * 2)",
+            ),
+            // Insert a statement before the return statement.
+            (
+                InputEdit {
+                    start_byte: 51,
+                    old_end_byte: 51,
+                    new_end_byte: 63,
+                    start_position: Point::new(2, 2),
+                    old_end_position: Point::new(2, 2),
+                    new_end_position: Point::new(3, 2),
+                },
+                b"sum += 1;\n  ",
+            ),
+            // Introduce a syntax error inside the new operand.
+            (
+                InputEdit {
+                    start_byte: 39,
+                    old_end_byte: 40,
+                    new_end_byte: 40,
+                    start_position: Point::new(1, 15),
+                    old_end_position: Point::new(1, 16),
+                    new_end_position: Point::new(1, 16),
+                },
+                b"{",
+            ),
+        ],
+    );
+}
+
 #[test]
 fn test_parsing_after_editing_end_of_code() {
     let mut parser = Parser::new();
@@ -640,8 +1162,99 @@ fn test_parsing_cancelled_by_another_thread() {
     assert!(tree.is_none());
 }
 
+#[test]
+fn test_parsing_cancelled_by_another_thread_with_a_shared_flag() {
+    let cancellation_flag = Arc::new(AtomicUsize::new(0));
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    parser.set_shared_cancellation_flag(Some(cancellation_flag.clone()));
+
+    let flag = cancellation_flag.clone();
+    let cancel_thread = thread::spawn(move || {
+        thread::sleep(time::Duration::from_millis(100));
+        flag.store(1, Ordering::SeqCst);
+    });
+
+    // Infinite input
+    let start_time = time::Instant::now();
+    let tree = parser.parse_with(
+        &mut |offset, _| {
+            thread::yield_now();
+            if offset == 0 {
+                b" ["
+            } else {
+                b"0,"
+            }
+        },
+        None,
+    );
+
+    // Parsing returns None because it was cancelled.
+    cancel_thread.join().unwrap();
+    assert!(tree.is_none());
+    assert!(start_time.elapsed() < time::Duration::from_secs(5));
+
+    // Clearing the flag makes parsing uninterruptible again, even though the
+    // flag itself is still set.
+    parser.reset();
+    parser.set_shared_cancellation_flag(None);
+    let tree = parser.parse("[0, 1]", None).unwrap();
+    assert_eq!(
+        tree.root_node().child(0).unwrap().kind(),
+        "expression_statement"
+    );
+}
+
 // Timeouts
 
+#[test]
+fn test_parsing_with_an_operation_limit() {
+    let source = format!("[{}0]", "0, ".repeat(1000));
+
+    // Parse with an operation limit, recording the log messages up to the
+    // point where parsing halts.
+    let parse_with_limit = |limit| {
+        let limit = NonZeroUsize::new(limit);
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let mut parser = Parser::new();
+        parser.set_language(&get_language("json")).unwrap();
+        parser.set_operation_limit(limit);
+        assert_eq!(parser.operation_limit(), limit);
+        let recorded_messages = messages.clone();
+        parser.set_logger(Some(Box::new(move |_, message| {
+            recorded_messages.lock().unwrap().push(message.to_string());
+        })));
+        let tree = parser.parse(&source, None);
+        parser.set_logger(None);
+        let messages = messages.lock().unwrap().clone();
+        (parser, tree, messages)
+    };
+
+    let (mut parser, tree, messages) = parse_with_limit(500);
+    assert!(tree.is_none());
+
+    // The same input always halts at the same point.
+    for _ in 0..3 {
+        let (_, tree, other_messages) = parse_with_limit(500);
+        assert!(tree.is_none());
+        assert_eq!(other_messages, messages);
+    }
+
+    // A higher limit gets further.
+    let (_, tree, other_messages) = parse_with_limit(1000);
+    assert!(tree.is_none());
+    assert!(other_messages.len() > messages.len());
+    assert_eq!(other_messages[..messages.len()], messages);
+
+    // Parsing can be resumed without a limit.
+    parser.set_operation_limit(None);
+    assert_eq!(parser.operation_limit(), None);
+    let tree = parser.parse(&source, None).unwrap();
+    assert!(!tree.root_node().has_error());
+    assert_eq!(tree.root_node().child(0).unwrap().named_child_count(), 1001);
+}
+
 #[test]
 #[retry(10)]
 fn test_parsing_with_a_timeout() {
@@ -786,6 +1399,36 @@ fn test_parsing_with_a_timeout_and_implicit_reset() {
     });
 }
 
+#[test]
+#[retry(10)]
+fn test_parsing_after_a_reset_matches_a_first_parse() {
+    let source_code = "[\"ok\", 1, 2, 3, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32]";
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    let expected_sexp = parser
+        .parse(source_code, None)
+        .unwrap()
+        .root_node()
+        .to_sexp();
+
+    // Interrupt a parse of a different document partway through.
+    parser.set_timeout_micros(5);
+    let tree = parser.parse(
+        "[null, 1, 2, 3, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32]",
+        None,
+    );
+    assert!(tree.is_none());
+
+    // After a reset, the parser's settings are kept, but the partial parse
+    // is discarded.
+    parser.reset();
+    assert_eq!(parser.timeout_micros(), 5);
+    parser.set_timeout_micros(0);
+    let tree = parser.parse(source_code, None).unwrap();
+    assert_eq!(tree.root_node().to_sexp(), expected_sexp);
+}
+
 #[test]
 #[retry(10)]
 fn test_parsing_with_timeout_and_no_completion() {
@@ -804,6 +1447,51 @@ fn test_parsing_with_timeout_and_no_completion() {
     });
 }
 
+#[test]
+#[retry(10)]
+fn test_parsing_with_a_deadline() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("json")).unwrap();
+
+    // Parse an infinitely-long array, but halt 1ms from now.
+    parser.set_deadline(Some(time::Instant::now() + time::Duration::from_millis(1)));
+    let start_time = time::Instant::now();
+    let tree = parser.parse_with(
+        &mut |offset, _| {
+            if offset == 0 {
+                b" ["
+            } else {
+                b",0"
+            }
+        },
+        None,
+    );
+    assert!(tree.is_none());
+    assert!(start_time.elapsed().as_micros() < 2000);
+
+    // The timeout set by the caller is left untouched.
+    assert_eq!(parser.timeout_micros(), 0);
+
+    // A deadline in the past halts before reading any input.
+    parser.reset();
+    let mut read_count = 0;
+    let tree = parser.parse_with(
+        &mut |_, _| {
+            read_count += 1;
+            b"[]"
+        },
+        None,
+    );
+    assert!(tree.is_none());
+    assert_eq!(read_count, 0);
+
+    // Clearing the deadline allows parsing to finish.
+    parser.set_deadline(None);
+    assert_eq!(parser.deadline(), None);
+    let tree = parser.parse("[0, 1]", None).unwrap();
+    assert_eq!(tree.root_node().child(0).unwrap().kind(), "array");
+}
+
 // Included Ranges
 
 #[test]
@@ -847,6 +1535,118 @@ fn test_parsing_with_one_included_range() {
     assert_eq!(js_tree.included_ranges(), &[script_content_node.range()]);
 }
 
+#[test]
+fn test_parsing_with_a_multi_parser() {
+    let source_code =
+        "<p>hi</p><script>let a = 1;</script><style>p {}</style><script>a++;</script>";
+    let html = get_language("html");
+    let javascript = get_language("javascript");
+
+    let injection_query = Query::new(
+        &html,
+        "(script_element (raw_text) @javascript) (style_element (raw_text) @css)",
+    )
+    .unwrap();
+    let mut parser = MultiParser::new(
+        html.clone(),
+        injection_query,
+        [("javascript", javascript.clone())],
+    )
+    .unwrap();
+    let layers = parser.parse(source_code.as_bytes()).unwrap();
+    assert_eq!(layers.len(), 2);
+
+    let html_layer = &layers[&html];
+    assert_eq!(*html_layer.tree.language(), html);
+    assert_eq!(html_layer.tree.root_node().kind(), "document");
+    assert_eq!(html_layer.tree.root_node().named_child_count(), 4);
+
+    // Both scripts are parsed together, as one tree.
+    let js_layer = &layers[&javascript];
+    let script_range = |text: &str| {
+        let start_byte = source_code.find(text).unwrap();
+        Range {
+            start_byte,
+            end_byte: start_byte + text.len(),
+            start_point: Point::new(0, start_byte),
+            end_point: Point::new(0, start_byte + text.len()),
+        }
+    };
+    assert_eq!(
+        js_layer.included_ranges,
+        [script_range("let a = 1;"), script_range("a++;")]
+    );
+    assert_eq!(js_layer.tree.included_ranges(), js_layer.included_ranges);
+    assert_eq!(
+        js_layer.tree.root_node().to_sexp(),
+        concat!(
+            "(program ",
+            "(lexical_declaration (variable_declarator name: (identifier) value: (number))) ",
+            "(expression_statement (update_expression argument: (identifier))))",
+        )
+    );
+}
+
+#[test]
+fn test_parsing_with_a_multi_parser_and_overlapping_injections() {
+    let source_code = "<p>hi</p><script>let a = 1;</script>";
+    let html = get_language("html");
+    let javascript = get_language("javascript");
+
+    // The start tag's range is a prefix of the script element's range.
+    let injection_query = Query::new(
+        &html,
+        "(script_element (start_tag) @javascript) (script_element) @javascript",
+    )
+    .unwrap();
+    let mut parser = MultiParser::new(
+        html.clone(),
+        injection_query,
+        [("javascript", javascript.clone())],
+    )
+    .unwrap();
+    let layers = parser.parse(source_code.as_bytes()).unwrap();
+
+    let script_start = source_code.find("<script>").unwrap();
+    assert_eq!(
+        layers[&javascript].included_ranges,
+        [Range {
+            start_byte: script_start,
+            end_byte: source_code.len(),
+            start_point: Point::new(0, script_start),
+            end_point: Point::new(0, source_code.len()),
+        }]
+    );
+}
+
+#[test]
+fn test_parsing_after_clearing_included_ranges() {
+    let source_code = "const a = 1;\n/* b */\nconst c = 2;\n";
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    let full_tree = parser.parse(source_code, None).unwrap();
+    let whole_document_ranges = parser.included_ranges();
+
+    let first_statement = full_tree.root_node().child(0).unwrap();
+    parser
+        .set_included_ranges(&[first_statement.range()])
+        .unwrap();
+    let partial_tree = parser.parse(source_code, None).unwrap();
+    assert_eq!(partial_tree.root_node().child_count(), 1);
+    assert_eq!(
+        partial_tree.root_node().end_byte(),
+        first_statement.end_byte()
+    );
+
+    parser.clear_included_ranges();
+    assert_eq!(parser.included_ranges(), whole_document_ranges);
+    let tree = parser.parse(source_code, None).unwrap();
+    assert_eq!(tree.root_node().byte_range(), 0..source_code.len());
+    assert_eq!(tree.root_node().to_sexp(), full_tree.root_node().to_sexp());
+    assert_eq!(tree.root_node().child_count(), 3);
+}
+
 #[test]
 fn test_parsing_with_multiple_included_ranges() {
     let source_code = "html `<div>Hello, ${name.toUpperCase()}, it's <b>${now()}</b>.</div>`";
@@ -942,6 +1742,89 @@ fn test_parsing_with_multiple_included_ranges() {
     );
 }
 
+#[test]
+fn test_coalescing_included_ranges() {
+    let source_code = "a\nbc\ndef\nghij\nklmno\n";
+    let range = |start: &str, end: &str| {
+        let start_byte = source_code.find(start).unwrap();
+        let end_byte = source_code.find(end).unwrap() + end.len();
+        Range {
+            start_byte,
+            end_byte,
+            start_point: position_for_offset(source_code.as_bytes(), start_byte).unwrap(),
+            end_point: position_for_offset(source_code.as_bytes(), end_byte).unwrap(),
+        }
+    };
+
+    let mut ranges = vec![
+        range("ghij", "ghij"),
+        range("a", "a"),
+        range("\nbc", "bc"),
+        range("hi", "\nklm"),
+        range("kl", "lm"),
+        range("o", "o"),
+        range("de", "de"),
+    ];
+    Range::coalesce(&mut ranges);
+    assert_eq!(
+        ranges,
+        &[
+            range("a", "bc"),
+            range("de", "de"),
+            range("ghij", "lm"),
+            range("o", "o")
+        ]
+    );
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    parser.set_included_ranges(&ranges).unwrap();
+    let tree = parser.parse(source_code, None).unwrap();
+    assert_eq!(tree.included_ranges(), ranges);
+}
+
+#[test]
+fn test_range_intersection() {
+    let source_code = "one\ntwo three\nfour";
+    let range = |text: &str| {
+        let start_byte = source_code.find(text).unwrap();
+        let end_byte = start_byte + text.len();
+        Range {
+            start_byte,
+            end_byte,
+            start_point: position_for_offset(source_code.as_bytes(), start_byte).unwrap(),
+            end_point: position_for_offset(source_code.as_bytes(), end_byte).unwrap(),
+        }
+    };
+
+    // Overlapping
+    let a = range("one\ntwo");
+    let b = range("two three");
+    assert!(a.intersects(&b));
+    assert!(b.intersects(&a));
+    assert_eq!(a.intersection(&b), Some(range("two")));
+    assert_eq!(b.intersection(&a), Some(range("two")));
+
+    // Nested
+    let c = range("three");
+    assert_eq!(b.intersection(&c), Some(c));
+    assert_eq!(c.intersection(&b), Some(c));
+
+    // Touching
+    let d = range("\nfour");
+    assert!(!c.intersects(&d));
+    assert_eq!(c.intersection(&d), None);
+
+    // Disjoint
+    assert!(!a.intersects(&d));
+    assert_eq!(d.intersection(&a), None);
+
+    assert!(c.contains_byte(c.start_byte));
+    assert!(c.contains_byte(c.end_byte - 1));
+    assert!(!c.contains_byte(c.end_byte));
+    assert!(!c.contains_byte(c.start_byte - 1));
+}
+
 #[test]
 fn test_parsing_with_included_range_containing_mismatched_positions() {
     let source_code = "<div>test</div>{_ignore_this_part_}";
@@ -1446,6 +2329,110 @@ fn test_parsing_with_scanner_logging() {
     assert!(found);
 }
 
+#[test]
+fn test_parsing_with_an_incompatible_language_version() {
+    let (grammar_name, parser_code) = generate_parser_for_grammar(
+        r#"{
+            "name": "future_language_version",
+            "rules": {
+                "document": {"type": "STRING", "value": "x"}
+            }
+        }"#,
+    )
+    .unwrap();
+    let parser_code = parser_code.replace(
+        &format!("#define LANGUAGE_VERSION {LANGUAGE_VERSION}\n"),
+        &format!("#define LANGUAGE_VERSION {}\n", LANGUAGE_VERSION + 1),
+    );
+    let language = get_test_language(&grammar_name, &parser_code, None);
+    assert_eq!(language.version(), LANGUAGE_VERSION + 1);
+
+    let mut parser = Parser::new();
+    let error = parser.set_language(&language).unwrap_err();
+    assert_eq!(
+        error,
+        LanguageError::IncompatibleVersion {
+            got: LANGUAGE_VERSION + 1,
+            min: MIN_COMPATIBLE_LANGUAGE_VERSION,
+            max: LANGUAGE_VERSION,
+        }
+    );
+    assert!(parser.language().is_none());
+}
+
+#[test]
+fn test_parser_language() {
+    let mut parser = Parser::new();
+    assert!(parser.language().is_none());
+
+    let rust = get_language("rust");
+    parser.set_language(&rust).unwrap();
+    let language = parser.language().unwrap();
+    assert_eq!(language, rust);
+    assert_eq!(language.node_kind_count(), rust.node_kind_count());
+    assert_eq!(
+        language.id_for_node_kind("function_item", true),
+        rust.id_for_node_kind("function_item", true)
+    );
+
+    let json = get_language("json");
+    parser.set_language(&json).unwrap();
+    assert_eq!(
+        parser.language().unwrap().node_kind_count(),
+        json.node_kind_count()
+    );
+    assert_ne!(json.node_kind_count(), rust.node_kind_count());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_loading_a_cpp_scanner_without_c_linkage() {
+    let grammar_json = r#"{
+        "name": "cpp_scanner_without_c_linkage",
+        "externals": [{"type": "SYMBOL", "name": "word"}],
+        "rules": {
+            "document": {"type": "REPEAT", "content": {"type": "SYMBOL", "name": "word"}}
+        }
+    }"#;
+    let (grammar_name, parser_code) = generate_parser_for_grammar(grammar_json).unwrap();
+
+    let src_dir = scratch_dir().join("src").join(&grammar_name);
+    fs::create_dir_all(src_dir.join("tree_sitter")).unwrap();
+    fs::write(
+        src_dir.join("tree_sitter").join("parser.h"),
+        tree_sitter::PARSER_HEADER,
+    )
+    .unwrap();
+    fs::write(src_dir.join("parser.c"), parser_code).unwrap();
+
+    // Without `extern "C"`, the scanner functions' names are mangled, so the parser can't
+    // find them.
+    fs::write(
+        src_dir.join("scanner.cc"),
+        ["create", "destroy", "serialize", "deserialize", "scan"]
+            .iter()
+            .map(|f| format!("void tree_sitter_{grammar_name}_external_scanner_{f}() {{}}\n"))
+            .collect::<String>(),
+    )
+    .unwrap();
+
+    let mut config = CompileConfig::new(
+        &src_dir,
+        None,
+        Some(scratch_dir().join(format!("{grammar_name}.{}", env::consts::DLL_EXTENSION))),
+    );
+    config.name = grammar_name.clone();
+
+    let message = test_loader()
+        .load_language_at_path_with_name(config)
+        .unwrap_err()
+        .to_string();
+    assert!(message.contains("Missing required functions in the external scanner"));
+    assert!(message.contains(&format!(
+        "`tree_sitter_{grammar_name}_external_scanner_create`"
+    )));
+}
+
 const fn simple_range(start: usize, end: usize) -> Range {
     Range {
         start_byte: start,
@@ -1,21 +1,23 @@
-use std::{env, fmt::Write};
+use std::{collections::HashMap, env, fmt::Write, fs};
 
 use indoc::indoc;
 use lazy_static::lazy_static;
 use rand::{prelude::StdRng, SeedableRng};
 use tree_sitter::{
-    CaptureQuantifier, Language, Node, Parser, Point, Query, QueryCursor, QueryError,
-    QueryErrorKind, QueryPredicate, QueryPredicateArg, QueryProperty,
+    CaptureQuantifier, InvalidRange, Language, Node, Parser, Point, Query, QueryCursor,
+    QueryCursorPool, QueryError, QueryErrorKind, QueryMatch, QueryPredicate, QueryPredicateArg,
+    QueryProperty,
 };
 use unindent::Unindent;
 
 use super::helpers::{
     allocations,
-    fixtures::{get_language, get_test_language},
+    fixtures::{get_language, get_language_queries_path, get_test_language, scratch_dir},
     query_helpers::{assert_query_matches, Match, Pattern},
 };
 use crate::{
     generate::generate_parser_for_grammar,
+    query::{check_query_at_path, query_with_inherits, resolve_query_inherits},
     tests::{
         helpers::query_helpers::{collect_captures, collect_matches},
         ITERATION_COUNT,
@@ -318,6 +320,53 @@ fn test_query_errors_on_invalid_predicates() {
     });
 }
 
+#[test]
+fn test_query_error_positions() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+
+        for (kind, source, text_at_offset) in [
+            (
+                QueryErrorKind::Syntax,
+                "(identifier) @a\n(call_expression\n  function: (identifier) @b ))",
+                ")",
+            ),
+            (
+                QueryErrorKind::NodeType,
+                "(identifier) @a\n(call_expression\n  function: (identifierr) @b)",
+                "identifierr",
+            ),
+            (
+                QueryErrorKind::Field,
+                "(identifier) @a\n(call_expression\n  functionn: (identifier) @b)",
+                "functionn",
+            ),
+            (
+                QueryErrorKind::Capture,
+                "(identifier) @a\n(call_expression\n  function: (identifier) @b\n  (#eq? @b @c))",
+                "c))",
+            ),
+            (
+                QueryErrorKind::Predicate,
+                "(identifier) @a\n  (call_expression\n  function: (identifier) @b\n  (#eq? @b))",
+                "(call_expression",
+            ),
+        ] {
+            let error = Query::new(&language, source).unwrap_err();
+            assert_eq!(error.kind, kind, "{source:?}");
+            assert!(
+                source[error.offset..].starts_with(text_at_offset),
+                "{kind:?} error at {:?}",
+                &source[error.offset..]
+            );
+
+            let line = source.lines().nth(error.row).unwrap();
+            let line_start = line.as_ptr() as usize - source.as_ptr() as usize;
+            assert_eq!(line_start + error.column, error.offset, "{kind:?}");
+        }
+    });
+}
+
 #[test]
 fn test_query_errors_on_impossible_patterns() {
     let js_lang = get_language("javascript");
@@ -508,6 +557,126 @@ fn test_query_matches_with_simple_pattern() {
     });
 }
 
+#[test]
+fn test_query_matches_into_owned() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            &language,
+            "(function_declaration name: (identifier) @fn-name body: (statement_block) @body)",
+        )
+        .unwrap();
+        let source = "function one() { two(); function three() {} }";
+
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let mut cursor = QueryCursor::new();
+        let matches = cursor
+            .matches(&query, tree.root_node(), source.as_bytes())
+            .map(QueryMatch::into_owned)
+            .collect::<Vec<_>>();
+        drop(cursor);
+        drop(tree);
+
+        assert_eq!(
+            matches
+                .iter()
+                .map(|m| {
+                    (
+                        m.pattern_index,
+                        m.captures
+                            .iter()
+                            .map(|(index, node)| {
+                                (
+                                    query.capture_names()[*index as usize],
+                                    node.kind,
+                                    &source[node.byte_range.clone()],
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .collect::<Vec<_>>(),
+            &[
+                (
+                    0,
+                    vec![
+                        ("fn-name", "identifier", "one"),
+                        ("body", "statement_block", "{ two(); function three() {} }"),
+                    ]
+                ),
+                (
+                    0,
+                    vec![
+                        ("fn-name", "identifier", "three"),
+                        ("body", "statement_block", "{}"),
+                    ]
+                ),
+            ],
+        );
+        assert_eq!(matches[1].captures[0].1.start_position, Point::new(0, 33));
+        assert_eq!(matches[1].captures[0].1.end_position, Point::new(0, 38));
+    });
+}
+
+#[test]
+fn test_query_cursor_pool() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(&language, "(identifier) @id").unwrap();
+        let source = "a; b; c;";
+
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let pool = QueryCursorPool::new();
+        assert_send_sync(&pool);
+        assert_eq!(pool.available(), 0);
+
+        // Repeated queries all reuse the same cursor.
+        for _ in 0..1000 {
+            let mut cursor = pool.get();
+            assert_eq!(pool.available(), 0);
+            let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+            assert_eq!(
+                collect_matches(matches, &query, source),
+                &[
+                    (0, vec![("id", "a")]),
+                    (0, vec![("id", "b")]),
+                    (0, vec![("id", "c")]),
+                ]
+            );
+        }
+        assert_eq!(pool.available(), 1);
+
+        // A restricted range doesn't carry over to the cursor's next use.
+        {
+            let mut cursor = pool.get();
+            cursor.set_byte_range(3..4).unwrap();
+            let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+            assert_eq!(
+                collect_matches(matches, &query, source),
+                &[(0, vec![("id", "b")])]
+            );
+        }
+        {
+            let mut cursor = pool.get();
+            let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+            assert_eq!(collect_matches(matches, &query, source).len(), 3);
+        }
+
+        // Cursors that are in use at the same time are distinct.
+        let cursor1 = pool.get();
+        let cursor2 = pool.get();
+        drop((cursor1, cursor2));
+        assert_eq!(pool.available(), 2);
+    });
+}
+
 #[test]
 fn test_query_matches_with_multiple_on_same_root() {
     allocations::record(|| {
@@ -1726,7 +1895,7 @@ fn test_query_matches_with_too_many_permutations_to_track() {
         parser.set_language(&language).unwrap();
         let tree = parser.parse(&source, None).unwrap();
         let mut cursor = QueryCursor::new();
-        cursor.set_match_limit(32);
+        cursor.set_match_limit(Some(32));
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
 
         // For this pathological query, some match permutations will be dropped.
@@ -1740,6 +1909,39 @@ fn test_query_matches_with_too_many_permutations_to_track() {
     });
 }
 
+#[test]
+fn test_query_cursor_match_limit() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(&language, "(array (identifier) @pre (identifier) @post)").unwrap();
+
+        let mut source = "hello, ".repeat(50);
+        source.insert(0, '[');
+        source.push_str("];");
+
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(&source, None).unwrap();
+
+        let mut cursor = QueryCursor::new();
+        assert_eq!(cursor.match_limit(), u32::MAX);
+
+        cursor.set_match_limit(Some(2));
+        assert_eq!(cursor.match_limit(), 2);
+        cursor
+            .matches(&query, tree.root_node(), source.as_bytes())
+            .for_each(drop);
+        assert!(cursor.did_exceed_match_limit());
+
+        cursor.set_match_limit(None);
+        assert_eq!(cursor.match_limit(), u32::MAX);
+        cursor
+            .matches(&query, tree.root_node(), source.as_bytes())
+            .for_each(drop);
+        assert!(!cursor.did_exceed_match_limit());
+    });
+}
+
 #[test]
 fn test_query_sibling_patterns_dont_match_children_of_an_error() {
     allocations::record(|| {
@@ -1828,7 +2030,7 @@ fn test_query_matches_with_alternatives_and_too_many_permutations_to_track() {
         parser.set_language(&language).unwrap();
         let tree = parser.parse(&source, None).unwrap();
         let mut cursor = QueryCursor::new();
-        cursor.set_match_limit(32);
+        cursor.set_match_limit(Some(32));
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
 
         assert_eq!(
@@ -1971,10 +2173,11 @@ fn test_query_matches_within_byte_range() {
 
         let mut cursor = QueryCursor::new();
 
-        let matches =
-            cursor
-                .set_byte_range(0..8)
-                .matches(&query, tree.root_node(), source.as_bytes());
+        let matches = cursor.set_byte_range(0..8).unwrap().matches(
+            &query,
+            tree.root_node(),
+            source.as_bytes(),
+        );
         assert_eq!(
             collect_matches(matches, &query, source),
             &[
@@ -1984,10 +2187,11 @@ fn test_query_matches_within_byte_range() {
             ]
         );
 
-        let matches =
-            cursor
-                .set_byte_range(5..15)
-                .matches(&query, tree.root_node(), source.as_bytes());
+        let matches = cursor.set_byte_range(5..15).unwrap().matches(
+            &query,
+            tree.root_node(),
+            source.as_bytes(),
+        );
         assert_eq!(
             collect_matches(matches, &query, source),
             &[
@@ -1997,10 +2201,11 @@ fn test_query_matches_within_byte_range() {
             ]
         );
 
-        let matches =
-            cursor
-                .set_byte_range(12..0)
-                .matches(&query, tree.root_node(), source.as_bytes());
+        let matches = cursor.set_byte_range(12..0).unwrap().matches(
+            &query,
+            tree.root_node(),
+            source.as_bytes(),
+        );
         assert_eq!(
             collect_matches(matches, &query, source),
             &[
@@ -2037,6 +2242,7 @@ fn test_query_matches_within_point_range() {
 
         let matches = cursor
             .set_point_range(Point::new(1, 0)..Point::new(2, 3))
+            .unwrap()
             .matches(&query, tree.root_node(), source.as_bytes());
         assert_eq!(
             collect_matches(matches, &query, &source),
@@ -2049,6 +2255,7 @@ fn test_query_matches_within_point_range() {
 
         let matches = cursor
             .set_point_range(Point::new(2, 0)..Point::new(3, 3))
+            .unwrap()
             .matches(&query, tree.root_node(), source.as_bytes());
         assert_eq!(
             collect_matches(matches, &query, &source),
@@ -2062,6 +2269,7 @@ fn test_query_matches_within_point_range() {
         // Zero end point is treated like no end point.
         let matches = cursor
             .set_point_range(Point::new(4, 1)..Point::new(0, 0))
+            .unwrap()
             .matches(&query, tree.root_node(), source.as_bytes());
         assert_eq!(
             collect_matches(matches, &query, &source),
@@ -2077,6 +2285,54 @@ fn test_query_matches_within_point_range() {
     });
 }
 
+#[test]
+#[allow(clippy::reversed_empty_ranges)]
+fn test_query_cursor_range_validation() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(&language, "(identifier) @element").unwrap();
+
+        let source = "[a, b, c,\n d, e, f]";
+
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let mut cursor = QueryCursor::new();
+
+        // Valid ranges can be chained.
+        let matches = cursor
+            .set_byte_range(0..14)
+            .unwrap()
+            .set_point_range(Point::new(0, 4)..Point::new(1, 3))
+            .unwrap()
+            .set_max_start_depth(None)
+            .matches(&query, tree.root_node(), source.as_bytes());
+        assert_eq!(
+            collect_matches(matches, &query, source),
+            &[
+                (0, vec![("element", "b")]),
+                (0, vec![("element", "c")]),
+                (0, vec![("element", "d")]),
+            ]
+        );
+
+        // Inverted ranges are rejected.
+        assert_eq!(cursor.set_byte_range(8..4).err(), Some(InvalidRange));
+        assert_eq!(
+            cursor
+                .set_point_range(Point::new(1, 2)..Point::new(0, 5))
+                .err(),
+            Some(InvalidRange)
+        );
+        assert_eq!(
+            cursor
+                .set_point_range(Point::new(1, 2)..Point::new(1, 1))
+                .err(),
+            Some(InvalidRange)
+        );
+    });
+}
+
 #[test]
 fn test_query_captures_within_byte_range() {
     allocations::record(|| {
@@ -2100,10 +2356,11 @@ fn test_query_captures_within_byte_range() {
         let tree = parser.parse(source, None).unwrap();
 
         let mut cursor = QueryCursor::new();
-        let captures =
-            cursor
-                .set_byte_range(3..27)
-                .captures(&query, tree.root_node(), source.as_bytes());
+        let captures = cursor.set_byte_range(3..27).unwrap().captures(
+            &query,
+            tree.root_node(),
+            source.as_bytes(),
+        );
 
         assert_eq!(
             collect_captures(captures, &query, source),
@@ -2138,10 +2395,11 @@ fn test_query_cursor_next_capture_with_byte_range() {
         let tree = parser.parse(source, None).unwrap();
 
         let mut cursor = QueryCursor::new();
-        let captures =
-            cursor
-                .set_byte_range(12..17)
-                .captures(&query, tree.root_node(), source.as_bytes());
+        let captures = cursor.set_byte_range(12..17).unwrap().captures(
+            &query,
+            tree.root_node(),
+            source.as_bytes(),
+        );
 
         assert_eq!(
             collect_captures(captures, &query, source),
@@ -2174,6 +2432,7 @@ fn test_query_cursor_next_capture_with_point_range() {
         let mut cursor = QueryCursor::new();
         let captures = cursor
             .set_point_range(Point::new(1, 0)..Point::new(1, 5))
+            .unwrap()
             .captures(&query, tree.root_node(), source.as_bytes());
 
         assert_eq!(
@@ -2205,7 +2464,7 @@ fn test_query_matches_with_unrooted_patterns_intersecting_byte_range() {
 
         // within the type parameter list
         let offset = source.find("D: E>").unwrap();
-        let matches = cursor.set_byte_range(offset..offset).matches(
+        let matches = cursor.set_byte_range(offset..offset).unwrap().matches(
             &query,
             tree.root_node(),
             source.as_bytes(),
@@ -2221,11 +2480,10 @@ fn test_query_matches_with_unrooted_patterns_intersecting_byte_range() {
         // from within the type parameter list to within the function body
         let start_offset = source.find("D: E>").unwrap();
         let end_offset = source.find("g(f)").unwrap();
-        let matches = cursor.set_byte_range(start_offset..end_offset).matches(
-            &query,
-            tree.root_node(),
-            source.as_bytes(),
-        );
+        let matches = cursor
+            .set_byte_range(start_offset..end_offset)
+            .unwrap()
+            .matches(&query, tree.root_node(), source.as_bytes());
         assert_eq!(
             collect_matches(matches, &query, source),
             &[
@@ -2271,6 +2529,7 @@ fn test_query_matches_with_wildcard_at_root_intersecting_byte_range() {
         let offset = source.find("A:").unwrap() + 2;
         let matches = cursor
             .set_byte_range(offset..offset)
+            .unwrap()
             .matches(&query, tree.root_node(), source.as_bytes())
             .map(|mat| mat.captures[0].node.kind())
             .collect::<Vec<_>>();
@@ -2280,6 +2539,7 @@ fn test_query_matches_with_wildcard_at_root_intersecting_byte_range() {
         let offset = source.find("b():").unwrap() + 4;
         let matches = cursor
             .set_byte_range(offset..offset)
+            .unwrap()
             .matches(&query, tree.root_node(), source.as_bytes())
             .map(|mat| mat.captures[0].node.kind())
             .collect::<Vec<_>>();
@@ -2289,6 +2549,7 @@ fn test_query_matches_with_wildcard_at_root_intersecting_byte_range() {
         let offset = source.find("c:").unwrap() + 2;
         let matches = cursor
             .set_byte_range(offset..offset)
+            .unwrap()
             .matches(&query, tree.root_node(), source.as_bytes())
             .map(|mat| mat.captures[0].node.kind())
             .collect::<Vec<_>>();
@@ -2422,6 +2683,7 @@ fn test_query_matches_within_range_of_long_repetition() {
 
         let matches = cursor
             .set_point_range(Point::new(8, 0)..Point::new(20, 0))
+            .unwrap()
             .matches(&query, tree.root_node(), source.as_bytes());
         assert_eq!(
             collect_matches(matches, &query, &source),
@@ -3048,6 +3310,48 @@ fn test_query_captures_with_predicates() {
     });
 }
 
+#[test]
+fn test_query_matches_with_custom_predicate_handler() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            &language,
+            r#"
+            ((identifier) @name
+             (#longer-than? @name "3"))"#,
+        )
+        .unwrap();
+
+        let source = "const ab = abcd + abcdef;";
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+
+        let mut cursor = QueryCursor::new();
+        cursor.set_predicate_handler(Box::new(|operator, args| {
+            assert_eq!(operator, "longer-than?");
+            match args {
+                [QueryPredicateArg::String(text), QueryPredicateArg::String(len)] => {
+                    text.len() > len.parse().unwrap()
+                }
+                _ => panic!("Unexpected arguments {args:?}"),
+            }
+        }));
+
+        let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+        assert_eq!(
+            collect_matches(matches, &query, source),
+            &[(0, vec![("name", "abcd")]), (0, vec![("name", "abcdef")])],
+        );
+
+        let captures = cursor.captures(&query, tree.root_node(), source.as_bytes());
+        assert_eq!(
+            collect_captures(captures, &query, source),
+            &[("name", "abcd"), ("name", "abcdef")],
+        );
+    });
+}
+
 #[test]
 fn test_query_captures_with_quoted_predicate_args() {
     allocations::record(|| {
@@ -3321,7 +3625,7 @@ fn test_query_captures_with_too_many_nested_results() {
         parser.set_language(&language).unwrap();
         let tree = parser.parse(source, None).unwrap();
         let mut cursor = QueryCursor::new();
-        cursor.set_match_limit(32);
+        cursor.set_match_limit(Some(32));
         let captures = cursor.captures(&query, tree.root_node(), source.as_bytes());
         let captures = collect_captures(captures, &query, source);
 
@@ -3523,6 +3827,55 @@ fn test_query_captures_with_matches_removed_before_they_finish() {
     });
 }
 
+#[test]
+fn test_query_captures_with_matches_removed_under_a_match_limit() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let query = Query::new(
+            &language,
+            r#"(parenthesized_expression "(" @open ")" @close)"#,
+        )
+        .unwrap();
+
+        // Eight nested parenthesized expressions, opened at bytes 4 to 11.
+        let source = "x = ((((((((a))))))));";
+
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+
+        // Return the start byte of the opening parenthesis of each match that
+        // completes, optionally removing the matches that open at odd bytes.
+        let completed_matches = |remove_odd_matches: bool| {
+            let mut cursor = QueryCursor::new();
+            cursor.set_match_limit(Some(4));
+            let mut open_bytes = HashMap::new();
+            let mut result = Vec::new();
+            for (m, i) in cursor.captures(&query, tree.root_node(), source.as_bytes()) {
+                let capture = m.captures[i];
+                let start_byte = capture.node.start_byte();
+                if query.capture_names()[capture.index as usize] == "open" {
+                    if remove_odd_matches && start_byte % 2 == 1 {
+                        m.remove();
+                        continue;
+                    }
+                    open_bytes.insert(m.id(), start_byte);
+                } else {
+                    result.push(open_bytes[&m.id()]);
+                }
+            }
+            assert!(cursor.did_exceed_match_limit());
+            result
+        };
+
+        // Only the four outermost matches fit within the limit.
+        assert_eq!(completed_matches(false), [7, 6, 5, 4]);
+
+        // Removing matches makes room for later ones.
+        assert_eq!(completed_matches(true), [10, 8, 6, 4]);
+    });
+}
+
 #[test]
 fn test_query_captures_and_matches_iterators_are_fused() {
     allocations::record(|| {
@@ -3686,6 +4039,33 @@ fn test_query_start_end_byte_for_pattern() {
     );
 }
 
+#[test]
+fn test_query_pattern_count_and_start_bytes() {
+    let language = get_language("javascript");
+    let source = indoc! {"
+        (identifier) @variable
+        (call_expression function: (identifier) @function)
+        ((string) @a . (string) @b)
+    "};
+    let query = Query::new(&language, source).unwrap();
+
+    assert_eq!(query.pattern_count(), 3);
+    let start_bytes = (0..query.pattern_count())
+        .map(|i| query.start_byte_for_pattern(i))
+        .collect::<Vec<_>>();
+    assert_eq!(start_bytes, [0, 23, 74]);
+    for i in 0..query.pattern_count() {
+        assert!(query.start_byte_for_pattern(i) < query.end_byte_for_pattern(i));
+    }
+
+    // The last pattern matches a sequence of siblings, so it has no single root.
+    assert!(query.is_pattern_rooted(0));
+    assert!(query.is_pattern_rooted(1));
+    assert!(!query.is_pattern_rooted(2));
+    assert!(!query.is_pattern_non_local(0));
+    assert!(query.is_pattern_non_local(2));
+}
+
 #[test]
 fn test_query_capture_names() {
     allocations::record(|| {
@@ -3713,6 +4093,38 @@ fn test_query_capture_names() {
     });
 }
 
+#[test]
+fn test_query_capture_names_for_highlights_query() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let source =
+            fs::read_to_string(get_language_queries_path("javascript").join("highlights.scm"))
+                .unwrap();
+        let query = Query::new(&language, &source).unwrap();
+
+        let capture_names = query.capture_names();
+        assert_eq!(
+            capture_names[..4],
+            ["variable", "property", "function", "function.method"]
+        );
+        assert_eq!(query.capture_index_for_name("variable"), Some(0));
+        assert_eq!(query.capture_index_for_name("function.method"), Some(3));
+        assert_eq!(query.capture_index_for_name("nonexistent"), None);
+
+        let source = "function one() {}";
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let mut cursor = QueryCursor::new();
+        let names = cursor
+            .captures(&query, tree.root_node(), source.as_bytes())
+            .filter(|(m, i)| m.captures[*i].node.utf8_text(source.as_bytes()) == Ok("one"))
+            .map(|(m, i)| capture_names[m.captures[i].index as usize])
+            .collect::<Vec<_>>();
+        assert!(names.contains(&"function"));
+    });
+}
+
 #[test]
 fn test_query_lifetime_is_separate_from_nodes_lifetime() {
     allocations::record(|| {
@@ -3847,6 +4259,45 @@ fn test_query_disable_pattern() {
     });
 }
 
+#[test]
+fn test_query_captures_with_disabled_capture() {
+    allocations::record(|| {
+        let language = get_language("javascript");
+        let mut query = Query::new(
+            &language,
+            "
+                (comment) @comment
+                (identifier) @variable
+            ",
+        )
+        .unwrap();
+
+        let source = "// one\na = b; /* two */";
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let mut cursor = QueryCursor::new();
+
+        let captures = cursor.captures(&query, tree.root_node(), source.as_bytes());
+        assert_eq!(
+            collect_captures(captures, &query, source),
+            &[
+                ("comment", "// one"),
+                ("variable", "a"),
+                ("variable", "b"),
+                ("comment", "/* two */"),
+            ],
+        );
+
+        query.disable_capture("comment");
+        let captures = cursor.captures(&query, tree.root_node(), source.as_bytes());
+        assert_eq!(
+            collect_captures(captures, &query, source),
+            &[("variable", "a"), ("variable", "b")],
+        );
+    });
+}
+
 #[test]
 fn test_query_alternative_predicate_prefix() {
     allocations::record(|| {
@@ -3889,7 +4340,7 @@ fn test_query_random() {
         let mut parser = Parser::new();
         parser.set_language(&language).unwrap();
         let mut cursor = QueryCursor::new();
-        cursor.set_match_limit(64);
+        cursor.set_match_limit(Some(64));
 
         let pattern_tree = parser
             .parse(include_str!("helpers/query_helpers.rs"), None)
@@ -5171,3 +5622,83 @@ fn test_query_execution_with_timeout() {
         .count();
     assert_eq!(matches, 1000);
 }
+
+#[test]
+fn test_check_query_at_path() {
+    let language = get_language("javascript");
+
+    let path = scratch_dir().join("valid-query.scm");
+    fs::write(&path, "(function_declaration name: (identifier) @name)").unwrap();
+    check_query_at_path(&language, &path).unwrap();
+
+    let path = scratch_dir().join("invalid-query.scm");
+    fs::write(
+        &path,
+        "(identifier) @variable\n\n(call_expression\n  function: (bogus_node_type) @function)\n",
+    )
+    .unwrap();
+    let error = check_query_at_path(&language, &path)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.starts_with(&format!(
+            "{}:4:14: NodeType error at byte offset 54\n",
+            path.display()
+        )),
+        "{error}"
+    );
+    assert!(
+        error.contains("Invalid node type bogus_node_type"),
+        "{error}"
+    );
+}
+
+#[test]
+fn test_query_with_inherits() {
+    let language = get_language("javascript");
+    let queries = HashMap::from([
+        ("ecma", "; inherits: common\n(identifier) @variable\n"),
+        ("common", "(comment) @comment"),
+        ("cyclic-a", "; inherits: cyclic-b\n(number) @number\n"),
+        (
+            "cyclic-b",
+            ";; inherits: ecma, cyclic-a\n(string) @string\n",
+        ),
+    ]);
+    let resolve = |name: &str| queries.get(name).map(ToString::to_string);
+
+    let query = query_with_inherits(
+        &language,
+        "; inherits: ecma\n; Numbers\n(number) @number\n",
+        resolve,
+    )
+    .unwrap();
+    assert_eq!(query.capture_names(), ["comment", "variable", "number"]);
+    assert_query_matches(
+        &language,
+        &query,
+        "a = 1; // one",
+        &[
+            (1, vec![("variable", "a")]),
+            (2, vec![("number", "1")]),
+            (0, vec![("comment", "// one")]),
+        ],
+    );
+
+    // Each query is included once, even if it is inherited more than once.
+    let source = resolve_query_inherits("; inherits: common,ecma\n", resolve).unwrap();
+    assert_eq!(source.matches("(comment) @comment").count(), 1);
+
+    let error = resolve_query_inherits("; inherits: cyclic-a\n", resolve).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Circular query inheritance: <query> -> cyclic-a -> cyclic-b -> cyclic-a"
+    );
+
+    let error = resolve_query_inherits("; inherits: missing\n", resolve).unwrap_err();
+    assert_eq!(error.to_string(), "Failed to resolve query \"missing\"");
+
+    // Directives after the first pattern are ignored.
+    let source = "(number) @number\n; inherits: ecma\n";
+    assert_eq!(resolve_query_inherits(source, resolve).unwrap(), source);
+}
@@ -4,7 +4,7 @@ use std::{
 };
 
 use tree_sitter::Point;
-use tree_sitter_tags::{c_lib as c, Error, TagsConfiguration, TagsContext};
+use tree_sitter_tags::{c_lib as c, Error, TagKind, TagsConfiguration, TagsContext};
 
 use super::helpers::{
     allocations,
@@ -130,6 +130,63 @@ fn test_tags_python() {
     assert_eq!(tags[1].docs.as_ref().unwrap(), "Get the customer's age");
 }
 
+#[test]
+fn test_tags_python_are_streamed_in_source_order() {
+    let language = get_language("python");
+    let tags_config = TagsConfiguration::new(language, PYTHON_TAG_QUERY, "").unwrap();
+    let mut tag_context = TagsContext::new();
+
+    let source = br#"
+def one():
+    """First"""
+    two()
+
+def two():
+    three()
+
+class Three:
+    def three(self):
+        """Third"""
+        one()
+"#;
+
+    let mut tags = tag_context
+        .generate_tags(&tags_config, source, None)
+        .unwrap()
+        .0;
+
+    let tag = tags.next().unwrap().unwrap();
+    assert_eq!(substr(source, &tag.name_range), "one");
+    assert!(tag.is_definition);
+    assert_eq!(tag.docs.as_deref(), Some("First"));
+    assert_eq!(
+        substr(source, tag.docs_range.as_ref().unwrap()),
+        "\"\"\"First\"\"\""
+    );
+
+    let rest = tags.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        rest.iter()
+            .map(|t| (
+                substr(source, &t.name_range),
+                t.is_definition,
+                t.docs_range.is_some()
+            ))
+            .collect::<Vec<_>>(),
+        &[
+            ("two", false, false),
+            ("two", true, false),
+            ("three", false, false),
+            ("Three", true, false),
+            ("three", true, true),
+            ("one", false, false),
+        ]
+    );
+    assert!(rest
+        .windows(2)
+        .all(|pair| pair[0].name_range.start < pair[1].name_range.start));
+}
+
 #[test]
 fn test_tags_javascript() {
     let language = get_language("javascript");
@@ -301,6 +358,55 @@ fn test_invalid_capture() {
     assert_eq!(e, Error::InvalidCapture("method".to_string()));
 }
 
+#[test]
+fn test_tags_with_custom_capture_kinds() {
+    let language = get_language("python");
+    let tags_config = TagsConfiguration::with_capture_kinds(
+        language,
+        r"
+        (class_definition
+          body: (block
+            (function_definition
+              name: (identifier) @name) @local.definition.method))
+
+        (call
+          function: (attribute
+            attribute: (identifier) @name)) @local.reference.call @highlight
+        ",
+        "",
+        &[
+            ("local.definition.", TagKind::Definition),
+            ("local.reference.", TagKind::Reference),
+        ],
+    )
+    .unwrap();
+    let mut tag_context = TagsContext::new();
+
+    let source = b"
+class Customer:
+    def age(self):
+        return self.compute_age()
+";
+
+    let tags = tag_context
+        .generate_tags(&tags_config, source, None)
+        .unwrap()
+        .0
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(
+        tags.iter()
+            .map(|t| (
+                substr(source, &t.name_range),
+                tags_config.syntax_type_name(t.syntax_type_id),
+                t.is_definition,
+            ))
+            .collect::<Vec<_>>(),
+        &[("age", "method", true), ("compute_age", "call", false)]
+    );
+}
+
 #[test]
 fn test_tags_with_parse_error() {
     let language = get_language("python");
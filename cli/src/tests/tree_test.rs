@@ -1,9 +1,16 @@
-use std::str;
+use std::{collections::HashSet, fs, str, thread};
 
-use tree_sitter::{InputEdit, Parser, Point, Range, Tree};
+use tree_sitter::{edits_from_diff, DiagnosticKind, InputEdit, Node, Parser, Point, Range, Tree};
 
-use super::helpers::fixtures::get_language;
-use crate::{fuzz::edits::Edit, parse::perform_edit, tests::invert_edit};
+use super::helpers::{
+    allocations,
+    fixtures::{get_language, scratch_dir},
+};
+use crate::{
+    fuzz::{allocations as fuzz_allocations, edits::Edit},
+    parse::perform_edit,
+    tests::invert_edit,
+};
 
 #[test]
 fn test_tree_edit() {
@@ -298,6 +305,118 @@ fn test_tree_edit_with_included_ranges() {
     );
 }
 
+#[test]
+fn test_tree_included_ranges_changed() {
+    // Parse the HTML outside of each `<% ... %>` tag.
+    fn html_ranges(source: &[u8]) -> Vec<Range> {
+        let source = str::from_utf8(source).unwrap();
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        for (tag_start, _) in source.match_indices("<%") {
+            let tag_end = tag_start + source[tag_start..].find("%>").unwrap() + 2;
+            ranges.push(start..tag_start);
+            start = tag_end;
+        }
+        ranges.push(start..source.len());
+        ranges
+            .into_iter()
+            .map(|range| Range {
+                start_byte: range.start,
+                end_byte: range.end,
+                start_point: Point::new(0, range.start),
+                end_point: Point::new(0, range.end),
+            })
+            .collect()
+    }
+
+    let mut source = b"<div><% a %><b>x</b><% c %></div>".to_vec();
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("html")).unwrap();
+    parser.set_included_ranges(&html_ranges(&source)).unwrap();
+    let mut tree = parser.parse(&source, None).unwrap();
+
+    // Growing the code inside a tag moves the later ranges along with the
+    // edit, which does not count as a change.
+    let edit = Edit {
+        position: index_of(&source, "a"),
+        deleted_length: 1,
+        inserted_text: b"abc".to_vec(),
+    };
+    perform_edit(&mut tree, &mut source, &edit).unwrap();
+    parser.set_included_ranges(&html_ranges(&source)).unwrap();
+    let new_tree = parser.parse(&source, Some(&tree)).unwrap();
+    assert!(!tree.included_ranges_changed(&new_tree));
+    tree = new_tree;
+
+    // Moving the end of a tag shifts the start of the range after it.
+    let edit = Edit {
+        position: index_of(&source, " %><b>"),
+        deleted_length: 6,
+        inserted_text: b" <b>%>".to_vec(),
+    };
+    perform_edit(&mut tree, &mut source, &edit).unwrap();
+    parser.set_included_ranges(&html_ranges(&source)).unwrap();
+    let new_tree = parser.parse(&source, Some(&tree)).unwrap();
+    assert!(tree.included_ranges_changed(&new_tree));
+    assert_eq!(
+        new_tree.included_ranges()[1].start_byte,
+        index_of(&source, "x")
+    );
+    tree = new_tree;
+
+    // Adding a tag splits one of the ranges in two.
+    let edit = Edit {
+        position: index_of(&source, "x"),
+        deleted_length: 1,
+        inserted_text: b"<% y %>".to_vec(),
+    };
+    perform_edit(&mut tree, &mut source, &edit).unwrap();
+    parser.set_included_ranges(&html_ranges(&source)).unwrap();
+    let new_tree = parser.parse(&source, Some(&tree)).unwrap();
+    assert!(tree.included_ranges_changed(&new_tree));
+    assert_eq!(new_tree.included_ranges().len(), 4);
+}
+
+#[test]
+fn test_tree_edit_from_diff() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+
+    let old_source = "function a() {\n  return 1;\n}\n\nfunction b() {\n  return 2;\n}\n";
+    let new_source =
+        "function a() {\n  return 1;\n}\n\nconst c = [3, 4];\n\nfunction b() {\n  return c;\n}\n";
+
+    let mut tree = parser.parse(old_source, None).unwrap();
+    let edits = edits_from_diff(old_source, new_source);
+    assert_eq!(
+        edits,
+        [InputEdit {
+            start_byte: 30,
+            old_end_byte: 55,
+            new_end_byte: 74,
+            start_position: Point::new(4, 0),
+            old_end_position: Point::new(5, 10),
+            new_end_position: Point::new(7, 10),
+        }]
+    );
+    for edit in &edits {
+        tree.edit(edit);
+    }
+
+    let new_tree = parser.parse(new_source, Some(&tree)).unwrap();
+    let clean_tree = parser.parse(new_source, None).unwrap();
+    assert_eq!(
+        new_tree.root_node().to_sexp(),
+        clean_tree.root_node().to_sexp()
+    );
+    assert_eq!(
+        new_tree.root_node().end_position(),
+        clean_tree.root_node().end_position()
+    );
+
+    assert!(edits_from_diff(new_source, new_source).is_empty());
+}
+
 #[test]
 fn test_tree_cursor() {
     let mut parser = Parser::new();
@@ -377,6 +496,52 @@ fn test_tree_cursor() {
     assert_eq!(copy.node().kind(), "struct_item");
 }
 
+#[test]
+fn test_tree_walk_preorder() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    let tree = parser
+        .parse(
+            "
+            class A {
+                b(c) { return c ? [d, e] : f(); }
+            }
+            g: while (true) break g;
+            ",
+            None,
+        )
+        .unwrap();
+
+    fn collect_recursively<'a>(node: Node<'a>, depth: usize, result: &mut Vec<(Node<'a>, usize)>) {
+        result.push((node, depth));
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            collect_recursively(child, depth + 1, result);
+        }
+    }
+    let mut expected = Vec::new();
+    collect_recursively(tree.root_node(), 0, &mut expected);
+
+    let nodes = tree.walk_preorder().collect::<Vec<_>>();
+    assert_eq!(nodes.len(), expected.len());
+    assert_eq!(nodes, expected);
+    assert_eq!(nodes[0], (tree.root_node(), 0));
+    assert_eq!(
+        nodes
+            .iter()
+            .map(|(node, _)| node.id())
+            .collect::<HashSet<_>>()
+            .len(),
+        nodes.len()
+    );
+
+    let empty_tree = parser.parse("", None).unwrap();
+    assert_eq!(
+        empty_tree.walk_preorder().collect::<Vec<_>>(),
+        &[(empty_tree.root_node(), 0)]
+    );
+}
+
 #[test]
 fn test_tree_cursor_previous_sibling() {
     let mut parser = Parser::new();
@@ -453,6 +618,44 @@ fn test_tree_cursor_fields() {
     assert_eq!(cursor.field_name(), Some("parameters"));
 }
 
+#[test]
+fn test_tree_cursor_field_ids() {
+    let language = get_language("javascript");
+    let mut parser = Parser::new();
+    parser.set_language(&language).unwrap();
+
+    let tree = parser
+        .parse("async function foo(a, b) { return a; }", None)
+        .unwrap();
+    let function_node = tree.root_node().child(0).unwrap();
+
+    let mut cursor = function_node.walk();
+    assert!(cursor.goto_first_child());
+    let mut fields = Vec::new();
+    loop {
+        let field_id = cursor.field_id();
+        assert_eq!(
+            field_id.and_then(|id| language.field_name_for_id(id.get())),
+            cursor.field_name()
+        );
+        fields.push((cursor.node().kind(), cursor.field_name()));
+        if !cursor.goto_next_sibling() {
+            break;
+        }
+    }
+
+    assert_eq!(
+        fields,
+        &[
+            ("async", None),
+            ("function", None),
+            ("identifier", Some("name")),
+            ("formal_parameters", Some("parameters")),
+            ("statement_block", Some("body")),
+        ]
+    );
+}
+
 #[test]
 fn test_tree_cursor_child_for_point() {
     let mut parser = Parser::new();
@@ -572,6 +775,71 @@ fn test_tree_node_equality() {
     assert_ne!(node1.child(0).unwrap(), node2);
 }
 
+#[test]
+fn test_tree_clone_outlives_original() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("javascript")).unwrap();
+    let tree = parser.parse("a(b, c);", None).unwrap();
+    let sexp = tree.root_node().to_sexp();
+
+    let tree_clone = tree.clone();
+    drop(tree);
+    assert_eq!(tree_clone.root_node().to_sexp(), sexp);
+
+    let sexp_from_thread = thread::spawn(move || tree_clone.root_node().to_sexp())
+        .join()
+        .unwrap();
+    assert_eq!(sexp_from_thread, sexp);
+}
+
+#[test]
+fn test_tree_point_for_byte() {
+    let source = "def foo(a):\n    return a\n\nx = foo(1)\n";
+
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("python")).unwrap();
+    let tree = parser.parse(source, None).unwrap();
+
+    assert_eq!(tree.point_for_byte(0), Some(Point::new(0, 0)));
+    assert_eq!(tree.point_for_byte(6), Some(Point::new(0, 6)));
+    assert_eq!(tree.point_for_byte(11), Some(Point::new(0, 11)));
+    assert_eq!(tree.point_for_byte(16), Some(Point::new(1, 4)));
+    assert_eq!(tree.point_for_byte(24), Some(Point::new(1, 12)));
+    assert_eq!(tree.point_for_byte(26), Some(Point::new(3, 0)));
+    assert_eq!(tree.point_for_byte(30), Some(Point::new(3, 4)));
+
+    // The blank line between the two statements isn't covered by any node.
+    assert_eq!(tree.point_for_byte(25), None);
+
+    // Bytes past the end of the input have no position.
+    assert_eq!(tree.point_for_byte(source.len() + 1), None);
+
+    // Node boundaries always agree with the nodes' positions.
+    let mut cursor = tree.walk();
+    let mut visited_children = false;
+    loop {
+        let node = cursor.node();
+        assert_eq!(
+            tree.point_for_byte(node.start_byte()),
+            Some(node.start_position())
+        );
+        assert_eq!(
+            tree.point_for_byte(node.end_byte()),
+            Some(node.end_position())
+        );
+        if !visited_children && cursor.goto_first_child() {
+            continue;
+        }
+        if cursor.goto_next_sibling() {
+            visited_children = false;
+        } else if cursor.goto_parent() {
+            visited_children = true;
+        } else {
+            break;
+        }
+    }
+}
+
 #[test]
 fn test_get_changed_ranges() {
     let source_code = b"{a: null};\n".to_vec();
@@ -679,6 +947,41 @@ fn test_get_changed_ranges() {
     }
 }
 
+#[test]
+fn test_changed_ranges_iterator_frees_its_buffer() {
+    allocations::record(|| {
+        let mut source_code = b"[a, b, c];\n".to_vec();
+
+        let mut parser = Parser::new();
+        parser.set_language(&get_language("javascript")).unwrap();
+        let mut tree = parser.parse(&source_code, None).unwrap();
+
+        // Replace `a` and `c`, producing two separate changed ranges.
+        let edit1 = Edit {
+            position: index_of(&source_code, "c"),
+            deleted_length: 1,
+            inserted_text: b"null".to_vec(),
+        };
+        perform_edit(&mut tree, &mut source_code, &edit1).unwrap();
+        let edit2 = Edit {
+            position: index_of(&source_code, "a"),
+            deleted_length: 1,
+            inserted_text: b"null".to_vec(),
+        };
+        perform_edit(&mut tree, &mut source_code, &edit2).unwrap();
+        let new_tree = parser.parse(&source_code, Some(&tree)).unwrap();
+
+        let mut ranges = tree.changed_ranges(&new_tree);
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges.next(), Some(range_of(&source_code, "null")));
+        assert_eq!(ranges.len(), 1);
+
+        // Dropping the iterator before it is exhausted must still release
+        // the array allocated by the C library.
+        drop(ranges);
+    });
+}
+
 #[test]
 fn test_consistency_with_mid_codepoint_edit() {
     let mut parser = Parser::new();
@@ -729,6 +1032,38 @@ fn main() {
     assert_eq!(cursor.node().kind(), "block_comment");
 }
 
+#[test]
+fn test_serializing_positions_ranges_and_edits() {
+    let point = Point::new(3, 14);
+    let json = serde_json::to_string(&point).unwrap();
+    assert_eq!(json, r#"{"row":3,"column":14}"#);
+    assert_eq!(serde_json::from_str::<Point>(&json).unwrap(), point);
+
+    let range = Range {
+        start_byte: 7,
+        end_byte: 42,
+        start_point: Point::new(1, 2),
+        end_point: Point::new(3, 4),
+    };
+    let json = serde_json::to_string(&range).unwrap();
+    assert_eq!(
+        json,
+        r#"{"start_byte":7,"end_byte":42,"start_point":{"row":1,"column":2},"end_point":{"row":3,"column":4}}"#
+    );
+    assert_eq!(serde_json::from_str::<Range>(&json).unwrap(), range);
+
+    let edit = InputEdit {
+        start_byte: 5,
+        old_end_byte: 9,
+        new_end_byte: 12,
+        start_position: Point::new(0, 5),
+        old_end_position: Point::new(0, 9),
+        new_end_position: Point::new(1, 2),
+    };
+    let json = serde_json::to_string(&edit).unwrap();
+    assert_eq!(serde_json::from_str::<InputEdit>(&json).unwrap(), edit);
+}
+
 fn index_of(text: &[u8], substring: &str) -> usize {
     str::from_utf8(text).unwrap().find(substring).unwrap()
 }
@@ -756,3 +1091,98 @@ fn get_changed_ranges(
     *tree = new_tree;
     result
 }
+
+#[test]
+fn test_tree_assert_no_leaks_with_multiple_trees() {
+    let language = get_language("javascript");
+    fuzz_allocations::assert_no_leaks(|| {
+        let mut parser = Parser::new();
+        parser.set_language(&language).unwrap();
+        let tree1 = parser.parse("let a = 1;", None).unwrap();
+        let tree2 = parser.parse("let a = [1, 2];", Some(&tree1)).unwrap();
+        let tree3 = tree2.clone();
+        drop(tree1);
+        drop(tree2);
+        assert_eq!(tree3.root_node().kind(), "program");
+    });
+}
+
+#[test]
+#[should_panic(expected = "Leaked 2 allocations")]
+fn test_tree_assert_no_leaks_reports_leaked_trees() {
+    let language = get_language("javascript");
+    let mut parser = Parser::new();
+    parser.set_language(&language).unwrap();
+    let tree = parser.parse("let a = 1;", None).unwrap();
+    fuzz_allocations::assert_no_leaks(|| {
+        std::mem::forget(tree.clone());
+    });
+}
+
+#[test]
+fn test_tree_leak_report() {
+    let language = get_language("javascript");
+    let mut parser = Parser::new();
+    parser.set_language(&language).unwrap();
+    let tree = parser.parse("let a = 1;", None).unwrap();
+    let report_path = scratch_dir().join("tree_leak_report.txt");
+
+    let leak_count = fuzz_allocations::write_leak_report(&report_path, || {
+        drop(tree.clone());
+    })
+    .unwrap();
+    assert_eq!(leak_count, 0);
+    assert_eq!(fs::read_to_string(&report_path).unwrap(), "");
+
+    let leak_count = fuzz_allocations::write_leak_report(&report_path, || {
+        std::mem::forget(tree.clone());
+    })
+    .unwrap();
+    assert_eq!(leak_count, 2);
+    let report = fs::read_to_string(&report_path).unwrap();
+    assert!(report.starts_with("Leaked 2 allocations\n"));
+    assert_eq!(report.matches("Allocation #").count(), 2);
+    #[cfg(feature = "allocation-backtraces")]
+    assert!(report.contains("ts_tree_copy"));
+}
+
+#[test]
+fn test_tree_errors() {
+    let mut parser = Parser::new();
+    parser.set_language(&get_language("python")).unwrap();
+    let source = "def f(:\n    pass\n\ny = (1 +)\nprint(a b)\nx = f(a for)\n";
+    let tree = parser.parse(source, None).unwrap();
+
+    let errors = tree
+        .errors()
+        .into_iter()
+        .map(|diagnostic| {
+            let range = diagnostic.range;
+            (
+                diagnostic.kind,
+                range.start_point,
+                &source[range.start_byte..range.end_byte],
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            (DiagnosticKind::Missing(")"), Point::new(0, 6), ""),
+            (DiagnosticKind::Unexpected, Point::new(3, 7), "+"),
+            (DiagnosticKind::Unexpected, Point::new(4, 8), "b"),
+            (DiagnosticKind::Unexpected, Point::new(5, 8), "for"),
+        ]
+    );
+
+    // Every error and missing node is covered by one of the diagnostics.
+    let diagnostics = tree.errors();
+    for (node, _) in tree.walk_preorder() {
+        if node.is_error() || node.is_missing() {
+            assert!(diagnostics.iter().any(|d| d.range == node.range()));
+        }
+    }
+
+    let tree = parser.parse("x = 1\n", None).unwrap();
+    assert!(tree.errors().is_empty());
+}
@@ -1,3 +1,17 @@
+#[cfg(windows)]
+use std::{
+    fs::{self, File},
+    os::windows::io::{AsRawHandle, RawHandle},
+};
+#[cfg(unix)]
+use std::{
+    io::{self, Read},
+    os::unix::{
+        io::{AsRawFd, RawFd},
+        net::UnixStream,
+    },
+    thread::{self, JoinHandle},
+};
 use std::{
     path::{Path, PathBuf},
     process::{Child, ChildStdin, Command, Stdio},
@@ -63,13 +77,56 @@ pub fn cancel_on_signal() -> Arc<AtomicUsize> {
     result
 }
 
+/// The file that a parser or a tree prints its DOT graphs into.
+enum GraphWriter {
+    /// The standard input of a `dot` process, which renders the graphs.
+    Dot(ChildStdin),
+    /// One end of a socket pair. The other end is read into memory.
+    #[cfg(unix)]
+    Memory(UnixStream),
+    /// A scratch file, which is read back once the graphs are printed.
+    #[cfg(windows)]
+    File(File),
+}
+
+#[cfg(unix)]
+impl AsRawFd for GraphWriter {
+    fn as_raw_fd(&self) -> RawFd {
+        match self {
+            Self::Dot(stdin) => stdin.as_raw_fd(),
+            Self::Memory(stream) => stream.as_raw_fd(),
+        }
+    }
+}
+
+#[cfg(windows)]
+impl AsRawHandle for GraphWriter {
+    fn as_raw_handle(&self) -> RawHandle {
+        match self {
+            Self::Dot(stdin) => stdin.as_raw_handle(),
+            Self::File(file) => file.as_raw_handle(),
+        }
+    }
+}
+
 pub struct LogSession {
     path: PathBuf,
     dot_process: Option<Child>,
-    dot_process_stdin: Option<ChildStdin>,
+    dot_process_stdin: Option<GraphWriter>,
     open_log: bool,
 }
 
+/// Collects the DOT graphs that a parser prints while parsing, so that they
+/// can be inspected without running `dot` or writing a log file.
+#[cfg(any(unix, windows))]
+pub struct GraphCapture {
+    writer: GraphWriter,
+    #[cfg(unix)]
+    reader: JoinHandle<io::Result<String>>,
+    #[cfg(windows)]
+    path: PathBuf,
+}
+
 pub fn print_tree_graph(tree: &Tree, path: &str, quiet: bool) -> Result<()> {
     let session = LogSession::new(path, quiet)?;
     tree.print_dot_graph(session.dot_process_stdin.as_ref().unwrap());
@@ -82,6 +139,64 @@ pub fn log_graphs(parser: &mut Parser, path: &str, open_log: bool) -> Result<Log
     Ok(session)
 }
 
+#[cfg(unix)]
+pub fn capture_graphs(parser: &mut Parser) -> Result<GraphCapture> {
+    let (writer, mut reader) =
+        UnixStream::pair().context("Failed to create a socket for debug graphs")?;
+    // Read the graphs as they are printed, so that the parser never blocks
+    // on a full socket buffer.
+    let reader = thread::spawn(move || {
+        let mut result = String::new();
+        reader.read_to_string(&mut result)?;
+        Ok(result)
+    });
+    let capture = GraphCapture {
+        writer: GraphWriter::Memory(writer),
+        reader,
+    };
+    parser.print_dot_graphs(&capture.writer);
+    Ok(capture)
+}
+
+#[cfg(windows)]
+pub fn capture_graphs(parser: &mut Parser) -> Result<GraphCapture> {
+    // There are no socket pairs here, so print the graphs into a scratch file
+    // that is unique to this process and capture.
+    static NEXT_CAPTURE_ID: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "tree-sitter-graphs-{}-{}.dot",
+        std::process::id(),
+        NEXT_CAPTURE_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    let file = File::create(&path).context("Failed to create a file for debug graphs")?;
+    let capture = GraphCapture {
+        writer: GraphWriter::File(file),
+        path,
+    };
+    parser.print_dot_graphs(&capture.writer);
+    Ok(capture)
+}
+
+#[cfg(any(unix, windows))]
+impl GraphCapture {
+    /// Stop the parser from printing graphs, and return everything that it
+    /// printed since [`capture_graphs`] was called.
+    pub fn finish(self, parser: &mut Parser) -> Result<String> {
+        parser.stop_printing_dot_graphs();
+        drop(self.writer);
+        #[cfg(unix)]
+        let result = self
+            .reader
+            .join()
+            .map_err(|_| anyhow!("Failed to read the debug graphs"))?;
+        #[cfg(windows)]
+        let result = fs::read_to_string(&self.path);
+        #[cfg(windows)]
+        fs::remove_file(&self.path).ok();
+        result.context("Failed to read the debug graphs")
+    }
+}
+
 impl LogSession {
     fn new(path: &str, open_log: bool) -> Result<Self> {
         use std::io::Write;
@@ -103,7 +218,7 @@ impl LogSession {
         Ok(Self {
             path: PathBuf::from(path),
             dot_process: Some(dot_process),
-            dot_process_stdin: Some(dot_stdin),
+            dot_process_stdin: Some(GraphWriter::Dot(dot_stdin)),
             open_log,
         })
     }
@@ -3,6 +3,7 @@
 pub mod c_lib;
 use std::{
     collections::HashSet,
+    io::{self, Write},
     iter, mem, ops, str,
     sync::atomic::{AtomicUsize, Ordering},
 };
@@ -93,6 +94,20 @@ pub enum Error {
     Unknown,
 }
 
+/// The reason why rendering HTML failed: either the highlighter failed, or
+/// the HTML could not be written.
+#[derive(Debug)]
+enum RenderError {
+    Highlight(Error),
+    Io(io::Error),
+}
+
+impl From<io::Error> for RenderError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
 /// Represents a single step in rendering a syntax-highlighted document.
 #[derive(Copy, Clone, Debug)]
 pub enum HighlightEvent {
@@ -101,9 +116,32 @@ pub enum HighlightEvent {
     HighlightEnd,
 }
 
+/// A part of a document, as returned by [`Highlighter::highlight_incremental`].
+#[derive(Clone, Debug)]
+pub enum HighlightRegion {
+    /// A byte range whose highlights are not affected by the change, so the
+    /// events from the previous highlighting can be reused.
+    ///
+    /// The range is a range of the new document. The previous events refer to
+    /// the old document, so the offsets of their `Source` events must be
+    /// shifted by the change in length of the edits that precede the region.
+    Unchanged(ops::Range<usize>),
+    /// A byte range that was highlighted again, with its new events.
+    Changed {
+        range: ops::Range<usize>,
+        events: Vec<HighlightEvent>,
+    },
+}
+
 /// Contains the data needed to highlight code written in a particular language.
 ///
-/// This struct is immutable and can be shared between threads.
+/// This struct is immutable and can be shared between threads. Compiling its
+/// queries is expensive, so create it once per language and reuse it for
+/// every [`Highlighter::highlight`] call, wrapping it in an [`Arc`] if it
+/// needs to be shared by several threads. All of the state for a particular
+/// highlighting run is stored in the [`Highlighter`].
+///
+/// [`Arc`]: std::sync::Arc
 pub struct HighlightConfiguration {
     pub language: Language,
     pub language_name: String,
@@ -159,6 +197,7 @@ where
     source: &'a [u8],
     language_name: &'a str,
     byte_offset: usize,
+    byte_range: ops::Range<usize>,
     highlighter: &'a mut Highlighter,
     injection_callback: F,
     cancellation_flag: Option<&'a AtomicUsize>,
@@ -204,8 +243,137 @@ impl Highlighter {
         config: &'a HighlightConfiguration,
         source: &'a [u8],
         cancellation_flag: Option<&'a AtomicUsize>,
-        mut injection_callback: impl FnMut(&str) -> Option<&'a HighlightConfiguration> + 'a,
+        injection_callback: impl FnMut(&str) -> Option<&'a HighlightConfiguration> + 'a,
+    ) -> Result<impl Iterator<Item = Result<HighlightEvent, Error>> + 'a, Error> {
+        self.highlight_with_byte_range(
+            config,
+            source,
+            0..usize::MAX,
+            None,
+            cancellation_flag,
+            injection_callback,
+        )
+    }
+
+    /// Iterate over the highlighted regions within a byte range of a given slice of
+    /// source code, such as the part of a document that is visible on screen.
+    ///
+    /// The whole document is still parsed, so nodes are classified the same way as with
+    /// [`highlight`](Highlighter::highlight), but only highlights that intersect the range
+    /// are reported. `Source` events are clipped to the range, so highlights that begin
+    /// before it or end after it are reported as starting or ending at its edges. Local
+    /// variables are only tracked using the definitions and scopes that intersect the
+    /// range.
+    pub fn highlight_range<'a>(
+        &'a mut self,
+        config: &'a HighlightConfiguration,
+        source: &'a [u8],
+        range: ops::Range<usize>,
+        cancellation_flag: Option<&'a AtomicUsize>,
+        injection_callback: impl FnMut(&str) -> Option<&'a HighlightConfiguration> + 'a,
     ) -> Result<impl Iterator<Item = Result<HighlightEvent, Error>> + 'a, Error> {
+        let end = range.end.min(source.len());
+        let start = range.start.min(end);
+        self.highlight_with_byte_range(
+            config,
+            source,
+            start..end,
+            None,
+            cancellation_flag,
+            injection_callback,
+        )
+    }
+
+    /// Highlight again the parts of a document that are affected by a change.
+    ///
+    /// `old_tree` is the document's previous syntax tree, after it was updated with
+    /// [`Tree::edit`], and `new_tree` is the tree parsed from `source`, the new contents
+    /// of the document. The affected parts are the ranges whose syntax changed,
+    /// according to [`Tree::changed_ranges`], and the ranges of the tokens that were
+    /// edited.
+    ///
+    /// Each affected range is extended to whole lines, and overlapping ranges are merged.
+    /// The returned regions cover the whole document, in order. `new_tree` is not parsed
+    /// again: each changed region is highlighted by running the queries on it, like
+    /// [`highlight_range`](Highlighter::highlight_range). Injected languages are still
+    /// parsed for each changed region that contains them.
+    pub fn highlight_incremental<'a>(
+        &mut self,
+        config: &'a HighlightConfiguration,
+        source: &'a [u8],
+        old_tree: &Tree,
+        new_tree: &Tree,
+        cancellation_flag: Option<&'a AtomicUsize>,
+        mut injection_callback: impl FnMut(&str) -> Option<&'a HighlightConfiguration>,
+    ) -> Result<Vec<HighlightRegion>, Error> {
+        let mut line_ranges = old_tree
+            .changed_ranges(new_tree)
+            .chain(edited_ranges(old_tree))
+            .map(|range| {
+                let end_byte = range.end_byte.min(source.len());
+                let start_byte = range.start_byte.min(end_byte);
+                let start = source[..start_byte]
+                    .iter()
+                    .rposition(|&b| b == b'\n')
+                    .map_or(0, |i| i + 1);
+                let end = if end_byte > start_byte && source[end_byte - 1] == b'\n' {
+                    end_byte
+                } else {
+                    source[end_byte..]
+                        .iter()
+                        .position(|&b| b == b'\n')
+                        .map_or(source.len(), |i| end_byte + i + 1)
+                };
+                start..end
+            })
+            .collect::<Vec<_>>();
+        line_ranges.sort_unstable_by_key(|range| range.start);
+        line_ranges.dedup_by(|range, previous| {
+            if range.start <= previous.end {
+                previous.end = previous.end.max(range.end);
+                true
+            } else {
+                false
+            }
+        });
+
+        let mut regions = Vec::new();
+        let mut offset = 0;
+        for range in line_ranges {
+            if range.start > offset {
+                regions.push(HighlightRegion::Unchanged(offset..range.start));
+            }
+            let events = self
+                .highlight_with_byte_range(
+                    config,
+                    source,
+                    range.clone(),
+                    Some(new_tree.clone()),
+                    cancellation_flag,
+                    |name| injection_callback(name),
+                )?
+                .collect::<Result<Vec<_>, _>>()?;
+            offset = range.end;
+            regions.push(HighlightRegion::Changed { range, events });
+        }
+        if offset < source.len() {
+            regions.push(HighlightRegion::Unchanged(offset..source.len()));
+        }
+        Ok(regions)
+    }
+
+    fn highlight_with_byte_range<'a, F>(
+        &'a mut self,
+        config: &'a HighlightConfiguration,
+        source: &'a [u8],
+        byte_range: ops::Range<usize>,
+        tree: Option<Tree>,
+        cancellation_flag: Option<&'a AtomicUsize>,
+        mut injection_callback: F,
+    ) -> Result<HighlightIter<'a, F>, Error>
+    where
+        F: FnMut(&str) -> Option<&'a HighlightConfiguration> + 'a,
+    {
         let layers = HighlightIterLayer::new(
             source,
             None,
@@ -214,6 +382,8 @@ impl Highlighter {
             &mut injection_callback,
             config,
             0,
+            byte_range.clone(),
+            tree,
             vec![Range {
                 start_byte: 0,
                 end_byte: usize::MAX,
@@ -225,7 +395,8 @@ impl Highlighter {
         let mut result = HighlightIter {
             source,
             language_name: &config.language_name,
-            byte_offset: 0,
+            byte_offset: byte_range.start,
+            byte_range,
             injection_callback,
             cancellation_flag,
             highlighter: self,
@@ -424,6 +595,9 @@ impl<'a> HighlightIterLayer<'a> {
     /// In the event that the new layer contains "combined injections" (injections where multiple
     /// disjoint ranges are parsed as one syntax tree), these will be eagerly processed and
     /// added to the returned vector.
+    ///
+    /// If `tree` is given, it is used as this layer's syntax tree instead of parsing the
+    /// document again.
     #[allow(clippy::too_many_arguments)]
     fn new<F: FnMut(&str) -> Option<&'a HighlightConfiguration> + 'a>(
         source: &'a [u8],
@@ -433,24 +607,34 @@ impl<'a> HighlightIterLayer<'a> {
         injection_callback: &mut F,
         mut config: &'a HighlightConfiguration,
         mut depth: usize,
+        byte_range: ops::Range<usize>,
+        mut tree: Option<Tree>,
         mut ranges: Vec<Range>,
     ) -> Result<Vec<Self>, Error> {
         let mut result = Vec::with_capacity(1);
         let mut queue = Vec::new();
         loop {
-            if highlighter.parser.set_included_ranges(&ranges).is_ok() {
-                highlighter
-                    .parser
-                    .set_language(&config.language)
-                    .map_err(|_| Error::InvalidLanguage)?;
-
-                unsafe { highlighter.parser.set_cancellation_flag(cancellation_flag) };
-                let tree = highlighter
-                    .parser
-                    .parse(source, None)
-                    .ok_or(Error::Cancelled)?;
-                unsafe { highlighter.parser.set_cancellation_flag(None) };
+            let tree = match tree.take() {
+                Some(tree) => Some(tree),
+                None if highlighter.parser.set_included_ranges(&ranges).is_ok() => {
+                    highlighter
+                        .parser
+                        .set_language(&config.language)
+                        .map_err(|_| Error::InvalidLanguage)?;
+
+                    unsafe { highlighter.parser.set_cancellation_flag(cancellation_flag) };
+                    let tree = highlighter
+                        .parser
+                        .parse(source, None)
+                        .ok_or(Error::Cancelled)?;
+                    unsafe { highlighter.parser.set_cancellation_flag(None) };
+                    Some(tree)
+                }
+                None => None,
+            };
+            if let Some(tree) = tree {
                 let mut cursor = highlighter.cursors.pop().unwrap_or_default();
+                cursor.set_byte_range(0..usize::MAX).unwrap();
 
                 // Process combined injections.
                 if let Some(combined_injections_query) = &config.combined_injections_query {
@@ -499,6 +683,7 @@ impl<'a> HighlightIterLayer<'a> {
                 let cursor_ref = unsafe {
                     mem::transmute::<&mut QueryCursor, &'static mut QueryCursor>(&mut cursor)
                 };
+                cursor_ref.set_byte_range(byte_range.clone()).unwrap();
                 let captures = cursor_ref
                     .captures(&config.query, tree_ref.root_node(), source)
                     .peekable();
@@ -660,6 +845,7 @@ where
         offset: usize,
         event: Option<HighlightEvent>,
     ) -> Option<Result<HighlightEvent, Error>> {
+        let offset = offset.min(self.byte_range.end);
         let result;
         if self.byte_offset < offset {
             result = Some(Ok(HighlightEvent::Source {
@@ -744,12 +930,13 @@ where
 
             // If none of the layers have any more highlight boundaries, terminate.
             if self.layers.is_empty() {
-                return if self.byte_offset < self.source.len() {
+                let end = self.source.len().min(self.byte_range.end);
+                return if self.byte_offset < end {
                     let result = Some(Ok(HighlightEvent::Source {
                         start: self.byte_offset,
-                        end: self.source.len(),
+                        end,
                     }));
-                    self.byte_offset = self.source.len();
+                    self.byte_offset = end;
                     result
                 } else {
                     None
@@ -772,6 +959,13 @@ where
                         return self.emit_event(end_byte, Some(HighlightEvent::HighlightEnd));
                     }
                 }
+
+                // Skip any nodes that start at the end of the highlighted range.
+                if range.start >= self.byte_range.end {
+                    layer.captures.next();
+                    self.sort_layers();
+                    continue 'main;
+                }
             }
             // If there are no more captures, then emit any remaining highlight end events.
             // And if there are none of those, then just advance to the end of the document.
@@ -818,6 +1012,8 @@ where
                                 &mut self.injection_callback,
                                 config,
                                 self.layers[0].depth + 1,
+                                self.byte_range.clone(),
+                                None,
                                 ranges,
                             ) {
                                 Ok(layers) => {
@@ -1015,27 +1211,83 @@ impl HtmlRenderer {
         source: &'a [u8],
         attribute_callback: &F,
     ) -> Result<(), Error>
+    where
+        F: Fn(Highlight) -> &'a [u8],
+    {
+        self.render_events(highlighter, source, attribute_callback, None)
+            .map_err(|error| match error {
+                RenderError::Highlight(error) => error,
+                RenderError::Io(_) => unreachable!("no HTML is written without a writer"),
+            })
+    }
+
+    /// Render HTML like [`render`](Self::render), but write it to `writer`
+    /// incrementally as highlight events arrive, instead of buffering the
+    /// whole document.
+    ///
+    /// Line offsets are not recorded, so [`lines`](Self::lines) can't be used
+    /// afterwards. Errors from the highlighter are returned as [`io::Error`]s
+    /// wrapping the original [`Error`].
+    pub fn render_to<'a, F, W>(
+        &mut self,
+        highlighter: impl Iterator<Item = Result<HighlightEvent, Error>>,
+        source: &'a [u8],
+        attribute_callback: &F,
+        mut writer: W,
+    ) -> io::Result<()>
+    where
+        F: Fn(Highlight) -> &'a [u8],
+        W: Write,
+    {
+        self.reset();
+        self.render_events(highlighter, source, attribute_callback, Some(&mut writer))
+            .map_err(|error| match error {
+                RenderError::Highlight(error) => io::Error::other(error),
+                RenderError::Io(error) => error,
+            })?;
+        writer.write_all(&self.html)?;
+        self.reset();
+        writer.flush()
+    }
+
+    /// Render the highlight events into the internal buffer. If a writer is
+    /// given, the buffer is written to it and cleared whenever it fills up.
+    fn render_events<'a, F>(
+        &mut self,
+        highlighter: impl Iterator<Item = Result<HighlightEvent, Error>>,
+        source: &'a [u8],
+        attribute_callback: &F,
+        mut writer: Option<&mut dyn Write>,
+    ) -> Result<(), RenderError>
     where
         F: Fn(Highlight) -> &'a [u8],
     {
         let mut highlights = Vec::new();
+        let mut last_written_byte = None;
         for event in highlighter {
-            match event {
-                Ok(HighlightEvent::HighlightStart(s)) => {
+            match event.map_err(RenderError::Highlight)? {
+                HighlightEvent::HighlightStart(s) => {
                     highlights.push(s);
                     self.start_highlight(s, attribute_callback);
                 }
-                Ok(HighlightEvent::HighlightEnd) => {
+                HighlightEvent::HighlightEnd => {
                     highlights.pop();
                     self.end_highlight();
                 }
-                Ok(HighlightEvent::Source { start, end }) => {
+                HighlightEvent::Source { start, end } => {
                     self.add_text(&source[start..end], &highlights, attribute_callback);
                 }
-                Err(a) => return Err(a),
+            }
+            if let Some(writer) = writer.as_mut() {
+                if self.html.len() >= BUFFER_HTML_RESERVE_CAPACITY {
+                    writer.write_all(&self.html)?;
+                    last_written_byte = self.html.last().copied();
+                    self.html.clear();
+                    self.line_offsets.clear();
+                }
             }
         }
-        if self.html.last() != Some(&b'\n') {
+        if self.html.last().or(last_written_byte.as_ref()) != Some(&b'\n') {
             self.html.push(b'\n');
         }
         if self.line_offsets.last() == Some(&(self.html.len() as u32)) {
@@ -1137,6 +1389,29 @@ impl HtmlRenderer {
     }
 }
 
+// Find the ranges of the nodes that were edited with `Tree::edit`, without
+// descending into the nodes whose descendants were not edited.
+fn edited_ranges(tree: &Tree) -> Vec<Range> {
+    let mut result = Vec::new();
+    let mut cursor = tree.walk();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if !node.has_changes() {
+            continue;
+        }
+        let edited_children = node
+            .children(&mut cursor)
+            .filter(Node::has_changes)
+            .collect::<Vec<_>>();
+        if edited_children.is_empty() {
+            result.push(node.range());
+        } else {
+            stack.extend(edited_children);
+        }
+    }
+    result
+}
+
 fn injection_for_match<'a>(
     config: &'a HighlightConfiguration,
     parent_name: Option<&'a str>,
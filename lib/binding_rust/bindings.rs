@@ -104,6 +104,16 @@ pub struct TSNode {
 }
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
+pub struct TSNodeStringOptions {
+    #[doc = " Write each node's byte range after its name, e.g. `(identifier [4, 7])`."]
+    pub include_ranges: bool,
+    #[doc = " Leave out the missing anonymous tokens that are otherwise written, such\n as `(MISSING \";\")`, so that the string only contains named nodes."]
+    pub named_only: bool,
+    #[doc = " Elide the named nodes that are nested more than this many levels below\n the node, writing each elided group of descendants as `...`."]
+    pub max_depth: u32,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
 pub struct TSTreeCursor {
     pub tree: *const ::core::ffi::c_void,
     pub id: *const ::core::ffi::c_void,
@@ -176,7 +186,7 @@ extern "C" {
     pub fn ts_parser_included_ranges(self_: *const TSParser, count: *mut u32) -> *const TSRange;
 }
 extern "C" {
-    #[doc = " Use the parser to parse some source code and create a syntax tree.\n\n If you are parsing this document for the first time, pass `NULL` for the\n `old_tree` parameter. Otherwise, if you have already parsed an earlier\n version of this document and the document has since been edited, pass the\n previous syntax tree so that the unchanged parts of it can be reused.\n This will save time and memory. For this to work correctly, you must have\n already edited the old syntax tree using the [`ts_tree_edit`] function in a\n way that exactly matches the source code changes.\n\n The [`TSInput`] parameter lets you specify how to read the text. It has the\n following three fields:\n 1. [`read`]: A function to retrieve a chunk of text at a given byte offset\n    and (row, column) position. The function should return a pointer to the\n    text and write its length to the [`bytes_read`] pointer. The parser does\n    not take ownership of this buffer; it just borrows it until it has\n    finished reading it. The function should write a zero value to the\n    [`bytes_read`] pointer to indicate the end of the document.\n 2. [`payload`]: An arbitrary pointer that will be passed to each invocation\n    of the [`read`] function.\n 3. [`encoding`]: An indication of how the text is encoded. Either\n    `TSInputEncodingUTF8` or `TSInputEncodingUTF16`.\n\n This function returns a syntax tree on success, and `NULL` on failure. There\n are three possible reasons for failure:\n 1. The parser does not have a language assigned. Check for this using the\n[`ts_parser_language`] function.\n 2. Parsing was cancelled due to a timeout that was set by an earlier call to\n    the [`ts_parser_set_timeout_micros`] function. You can resume parsing from\n    where the parser left out by calling [`ts_parser_parse`] again with the\n    same arguments. Or you can start parsing from scratch by first calling\n    [`ts_parser_reset`].\n 3. Parsing was cancelled using a cancellation flag that was set by an\n    earlier call to [`ts_parser_set_cancellation_flag`]. You can resume parsing\n    from where the parser left out by calling [`ts_parser_parse`] again with\n    the same arguments.\n\n [`read`]: TSInput::read\n [`payload`]: TSInput::payload\n [`encoding`]: TSInput::encoding\n [`bytes_read`]: TSInput::read"]
+    #[doc = " Use the parser to parse some source code and create a syntax tree.\n\n If you are parsing this document for the first time, pass `NULL` for the\n `old_tree` parameter. Otherwise, if you have already parsed an earlier\n version of this document and the document has since been edited, pass the\n previous syntax tree so that the unchanged parts of it can be reused.\n This will save time and memory. For this to work correctly, you must have\n already edited the old syntax tree using the [`ts_tree_edit`] function in a\n way that exactly matches the source code changes.\n\n The [`TSInput`] parameter lets you specify how to read the text. It has the\n following three fields:\n 1. [`read`]: A function to retrieve a chunk of text at a given byte offset\n    and (row, column) position. The function should return a pointer to the\n    text and write its length to the [`bytes_read`] pointer. The parser does\n    not take ownership of this buffer; it just borrows it until it has\n    finished reading it. The function should write a zero value to the\n    [`bytes_read`] pointer to indicate the end of the document. The chunks\n    can be of any length, and may end in the middle of a multi-byte\n    character.\n 2. [`payload`]: An arbitrary pointer that will be passed to each invocation\n    of the [`read`] function.\n 3. [`encoding`]: An indication of how the text is encoded. Either\n    `TSInputEncodingUTF8` or `TSInputEncodingUTF16`.\n\n This function returns a syntax tree on success, and `NULL` on failure. There\n are three possible reasons for failure:\n 1. The parser does not have a language assigned. Check for this using the\n[`ts_parser_language`] function.\n 2. Parsing was cancelled due to a timeout that was set by an earlier call to\n    the [`ts_parser_set_timeout_micros`] function. You can resume parsing from\n    where the parser left out by calling [`ts_parser_parse`] again with the\n    same arguments. Or you can start parsing from scratch by first calling\n    [`ts_parser_reset`].\n 3. Parsing was cancelled using a cancellation flag that was set by an\n    earlier call to [`ts_parser_set_cancellation_flag`]. You can resume parsing\n    from where the parser left out by calling [`ts_parser_parse`] again with\n    the same arguments.\n\n [`read`]: TSInput::read\n [`payload`]: TSInput::payload\n [`encoding`]: TSInput::encoding\n [`bytes_read`]: TSInput::read"]
     pub fn ts_parser_parse(
         self_: *mut TSParser,
         old_tree: *const TSTree,
@@ -214,6 +224,22 @@ extern "C" {
     #[doc = " Get the duration in microseconds that parsing is allowed to take."]
     pub fn ts_parser_timeout_micros(self_: *const TSParser) -> u64;
 }
+extern "C" {
+    #[doc = " Set the maximum number of parse operations that each call to\n [`ts_parser_parse`] should be allowed to perform before halting. A limit of\n zero means that there is no limit.\n\n Unlike a timeout, this halts at the same point every time that the same\n input is parsed. If the limit is exceeded, parsing halts early, returning\n NULL. See [`ts_parser_parse`] for more information."]
+    pub fn ts_parser_set_operation_limit(self_: *mut TSParser, operation_limit: u64);
+}
+extern "C" {
+    #[doc = " Get the maximum number of parse operations that parsing is allowed to\n perform."]
+    pub fn ts_parser_operation_limit(self_: *const TSParser) -> u64;
+}
+extern "C" {
+    #[doc = " Set whether the parser should represent the text after its last included\n range in the syntax tree.\n\n By default, the end of the last included range is treated as the end of the\n document, and the syntax tree ends there. If this is enabled, the text after\n that range is read to measure it, but not parsed, and it is added to the tree\n as a single `ERROR` node at the end of the root node. Together with a single\n included range that starts at the beginning of the document, this parses\n just a prefix of the document, while the tree still spans all of it."]
+    pub fn ts_parser_set_wrap_remainder(self_: *mut TSParser, wrap_remainder: bool);
+}
+extern "C" {
+    #[doc = " Get whether the parser represents the text after its last included range in\n the syntax tree."]
+    pub fn ts_parser_wrap_remainder(self_: *const TSParser) -> bool;
+}
 extern "C" {
     #[doc = " Set the parser's current cancellation flag pointer.\n\n If a non-null pointer is assigned, then the parser will periodically read\n from this pointer during parsing. If it reads a non-zero value, it will\n halt early, returning NULL. See [`ts_parser_parse`] for more information."]
     pub fn ts_parser_set_cancellation_flag(self_: *mut TSParser, flag: *const usize);
@@ -318,6 +344,19 @@ extern "C" {
     #[doc = " Get an S-expression representing the node as a string.\n\n This string is allocated with `malloc` and the caller is responsible for\n freeing it using `free`."]
     pub fn ts_node_string(self_: TSNode) -> *mut ::core::ffi::c_char;
 }
+extern "C" {
+    #[doc = " Write the same S-expression as [`ts_node_string`] into the given buffer,\n followed by a null character. Like `snprintf`, if the buffer's `size` is\n too small, only as much of the S-expression as fits is written, still\n followed by a null character unless `size` is zero. The buffer may be null\n if `size` is zero.\n\n Returns the length of the whole S-expression, not including the null\n character, so a return value that is greater than or equal to `size` means\n that the buffer was too small."]
+    pub fn ts_node_write_string(self_: TSNode, buffer: *mut ::core::ffi::c_char, size: usize) -> usize;
+}
+extern "C" {
+    #[doc = " Write an S-expression like [`ts_node_write_string`], formatted according to\n the given options. The options can be combined."]
+    pub fn ts_node_write_string_with_options(
+        self_: TSNode,
+        options: TSNodeStringOptions,
+        buffer: *mut ::core::ffi::c_char,
+        size: usize,
+    ) -> usize;
+}
 extern "C" {
     #[doc = " Check if the node is null. Functions like [`ts_node_child`] and\n [`ts_node_next_sibling`] will return a null node to indicate that no such node\n was found."]
     pub fn ts_node_is_null(self_: TSNode) -> bool;
@@ -673,6 +712,7 @@ extern "C" {
         -> bool;
 }
 extern "C" {
+    #[doc = " Remove the match with the given id, whether or not it has finished, and\n free the capture list that it was using."]
     pub fn ts_query_cursor_remove_match(self_: *mut TSQueryCursor, match_id: u32);
 }
 extern "C" {
@@ -0,0 +1,75 @@
+use std::{error, fmt, mem, path::Path, path::PathBuf};
+
+use libloading::{Library, Symbol};
+
+use crate::{ffi, Language, LanguageError, LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION};
+
+/// An error that occurred in [`Language::load_from_path`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// There is no file at the given path.
+    FileNotFound(PathBuf),
+    /// The file exists, but could not be opened as a dynamic library.
+    Open { path: PathBuf, message: String },
+    /// The library does not export the given symbol.
+    SymbolMissing(String),
+    /// The library's language function returned a null pointer.
+    NullLanguage(String),
+    /// The library's language has an ABI version that this library does not
+    /// support.
+    IncompatibleVersion(LanguageError),
+}
+
+impl Language {
+    /// Load a language from the dynamic library at `path`.
+    ///
+    /// `symbol` is the name of the function that returns the language, such as
+    /// `tree_sitter_json`. The library is never unloaded, so the returned
+    /// language remains valid for the rest of the process.
+    pub fn load_from_path(path: &Path, symbol: &str) -> Result<Self, LoadError> {
+        if !path.exists() {
+            return Err(LoadError::FileNotFound(path.to_path_buf()));
+        }
+
+        let library = unsafe { Library::new(path) }.map_err(|e| LoadError::Open {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        let language = unsafe {
+            let language_fn = library
+                .get::<Symbol<unsafe extern "C" fn() -> *const ffi::TSLanguage>>(symbol.as_bytes())
+                .map_err(|_| LoadError::SymbolMissing(symbol.to_string()))?;
+            language_fn()
+        };
+        if language.is_null() {
+            return Err(LoadError::NullLanguage(symbol.to_string()));
+        }
+        let language = Self(language);
+
+        let version = language.version();
+        if !(MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION).contains(&version) {
+            return Err(LoadError::IncompatibleVersion(
+                LanguageError::incompatible_version(version),
+            ));
+        }
+
+        mem::forget(library);
+        Ok(language)
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FileNotFound(path) => write!(f, "Dynamic library not found: {path:?}"),
+            Self::Open { path, message } => {
+                write!(f, "Error opening dynamic library {path:?}: {message}")
+            }
+            Self::SymbolMissing(symbol) => write!(f, "Failed to load symbol {symbol}"),
+            Self::NullLanguage(symbol) => write!(f, "Symbol {symbol} returned a null language"),
+            Self::IncompatibleVersion(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl error::Error for LoadError {}
@@ -51,7 +51,12 @@ impl Parser {
     /// `ptr` must be non-null.
     #[must_use]
     pub const unsafe fn from_raw(ptr: *mut TSParser) -> Self {
-        Self(NonNull::new_unchecked(ptr))
+        Self {
+            ptr: NonNull::new_unchecked(ptr),
+            cancellation_flag: None,
+            #[cfg(feature = "std")]
+            deadline: None,
+        }
     }
 
     /// Consumes the [`Parser`], returning a raw pointer to the underlying C structure.
@@ -63,7 +68,7 @@ impl Parser {
     /// may cause issues like use after free.
     #[must_use]
     pub fn into_raw(self) -> *mut TSParser {
-        ManuallyDrop::new(self).0.as_ptr()
+        ManuallyDrop::new(self).ptr.as_ptr()
     }
 }
 
@@ -148,13 +153,16 @@ impl QueryCursor {
     pub const unsafe fn from_raw(ptr: *mut TSQueryCursor) -> Self {
         Self {
             ptr: NonNull::new_unchecked(ptr),
+            predicate_handler: None,
         }
     }
 
     /// Consumes the [`QueryCursor`], returning a raw pointer to the underlying C structure.
     #[must_use]
     pub fn into_raw(self) -> *mut TSQueryCursor {
-        ManuallyDrop::new(self).ptr.as_ptr()
+        let mut cursor = ManuallyDrop::new(self);
+        drop(cursor.predicate_handler.take());
+        cursor.ptr.as_ptr()
     }
 }
 
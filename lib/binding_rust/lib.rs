@@ -6,7 +6,7 @@ mod util;
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 #[cfg(not(feature = "std"))]
-use alloc::{boxed::Box, format, string::String, string::ToString, vec::Vec};
+use alloc::{boxed::Box, format, string::String, string::ToString, sync::Arc, vec, vec::Vec};
 use core::{
     char,
     ffi::{c_char, c_void, CStr},
@@ -14,21 +14,35 @@ use core::{
     hash, iter,
     marker::PhantomData,
     mem::MaybeUninit,
-    num::NonZeroU16,
+    num::{NonZeroU16, NonZeroUsize},
     ops::{self, Deref},
     ptr::{self, NonNull},
     slice, str,
     sync::atomic::AtomicUsize,
 };
-#[cfg(feature = "std")]
-use std::error;
 #[cfg(all(feature = "std", any(unix, target_os = "wasi")))]
 use std::os::fd::AsRawFd;
 #[cfg(all(windows, feature = "std"))]
 use std::os::windows::io::AsRawHandle;
+#[cfg(feature = "std")]
+use std::{
+    error,
+    io::{self, Read, Seek, SeekFrom},
+    ops::DerefMut,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 use tree_sitter_language::LanguageFn;
 
+#[cfg(feature = "dynamic-loading")]
+mod dynamic_language;
+#[cfg(feature = "dynamic-loading")]
+pub use dynamic_language::*;
+#[cfg(feature = "std")]
+mod multi_parser;
+#[cfg(feature = "std")]
+pub use multi_parser::*;
 #[cfg(feature = "wasm")]
 mod wasm_language;
 #[cfg(feature = "wasm")]
@@ -55,6 +69,10 @@ pub const PARSER_HEADER: &str = include_str!("../src/parser.h");
 
 /// An opaque object that defines how to parse a particular language. The code
 /// for each `Language` is generated by the Tree-sitter CLI.
+///
+/// Two `Language`s are equal if they refer to the same loaded grammar
+/// instance, such as a clone of a language, or the language returned by
+/// [`Parser::language`].
 #[doc(alias = "TSLanguage")]
 #[derive(Debug, PartialEq, Eq, Hash)]
 #[repr(transparent)]
@@ -63,6 +81,10 @@ pub struct Language(*const ffi::TSLanguage);
 pub struct LanguageRef<'a>(*const ffi::TSLanguage, PhantomData<&'a ()>);
 
 /// A tree that represents the syntactic structure of a source code file.
+///
+/// Cloning a tree is cheap: the clone shares its nodes with the original,
+/// and each copy can be edited and dropped independently. Trees are [`Send`]
+/// and [`Sync`], so a clone can be handed off to another thread.
 #[doc(alias = "TSTree")]
 pub struct Tree(NonNull<ffi::TSTree>);
 
@@ -70,6 +92,7 @@ pub struct Tree(NonNull<ffi::TSTree>);
 ///
 /// Rows and columns are zero-based.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     pub row: usize,
     pub column: usize,
@@ -78,6 +101,7 @@ pub struct Point {
 /// A range of positions in a multi-line text document, both in terms of bytes
 /// and of rows and columns.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Range {
     pub start_byte: usize,
     pub end_byte: usize,
@@ -85,8 +109,49 @@ pub struct Range {
     pub end_point: Point,
 }
 
+/// Options for formatting a node as an S-expression with
+/// [`Node::to_sexp_with_options`]. The default options produce the same
+/// S-expression as [`Node::to_sexp`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SexpOptions {
+    /// Write each node's byte range after its name, e.g. `(identifier [4, 7])`.
+    pub include_ranges: bool,
+    /// Only write named nodes. [`Node::to_sexp`] already leaves out most
+    /// anonymous nodes, such as punctuation and keywords, but it includes
+    /// anonymous tokens that the parser inserted during error recovery, e.g.
+    /// `(MISSING ";")`. With this option, those are left out as well, so the
+    /// result only changes when the named structure of the tree does.
+    pub named_only: bool,
+    /// Elide any named nodes that are nested more than this many levels below
+    /// the node, writing each elided group of descendants as `...`, e.g.
+    /// `(array (array ...))`. Only named nodes count toward the depth, so
+    /// this limits the size of the output, but not how deep the writer has to
+    /// walk through hidden nodes.
+    pub max_depth: Option<usize>,
+}
+
+/// A syntax error in a [`Tree`], as returned by [`Tree::errors`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The range of the `ERROR` or `MISSING` node. A missing node is empty.
+    pub range: Range,
+    pub kind: DiagnosticKind,
+}
+
+/// The kind of a [`Diagnostic`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// Text that the parser could not fit into the tree, which was wrapped in
+    /// an `ERROR` node.
+    Unexpected,
+    /// A node of the given kind that the parser inserted in order to recover
+    /// from the error.
+    Missing(&'static str),
+}
+
 /// A summary of a change to a text document.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InputEdit {
     pub start_byte: usize,
     pub old_end_byte: usize,
@@ -97,6 +162,13 @@ pub struct InputEdit {
 }
 
 /// A single node within a syntax [`Tree`].
+///
+/// Two nodes are equal, and hash the same, when they are the same node of the
+/// same `Tree` value, however they were reached. Nodes are compared by
+/// identity, not by structure: the corresponding nodes of a tree and its
+/// [clone](Tree::clone) are not equal, and neither are nodes of a tree that
+/// was reparsed from it. A node is never equal to its parent or child, even
+/// if they span the same bytes.
 #[doc(alias = "TSNode")]
 #[derive(Clone, Copy)]
 #[repr(transparent)]
@@ -105,7 +177,12 @@ pub struct Node<'tree>(ffi::TSNode, PhantomData<&'tree ()>);
 /// A stateful object that this is used to produce a [`Tree`] based on some
 /// source code.
 #[doc(alias = "TSParser")]
-pub struct Parser(NonNull<ffi::TSParser>);
+pub struct Parser {
+    ptr: NonNull<ffi::TSParser>,
+    cancellation_flag: Option<Arc<AtomicUsize>>,
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>,
+}
 
 /// A stateful object that is used to look up symbols valid in a specific parse
 /// state
@@ -120,11 +197,27 @@ pub enum LogType {
     Lex,
 }
 
+/// A log message, parsed into a structured event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogEvent {
+    /// The lexer consumed a character while in the given lex state.
+    Lex { state: u16, character: char },
+    /// The parser reduced `count` children into a node of the given symbol.
+    Reduce { symbol: String, count: usize },
+    /// The parser shifted the lookahead token and moved to the given state.
+    Shift { state: u16 },
+    /// Any other log message.
+    Raw(String),
+}
+
 type FieldId = NonZeroU16;
 
 /// A callback that receives log messages during parser.
 type Logger<'a> = Box<dyn FnMut(LogType, &str) + 'a>;
 
+/// A callback that receives structured log events during parsing.
+type StructuredLogger<'a> = Box<dyn FnMut(LogEvent) + 'a>;
+
 /// A stateful object for walking a syntax [`Tree`] efficiently.
 #[doc(alias = "TSTreeCursor")]
 pub struct TreeCursor<'cursor>(ffi::TSTreeCursor, PhantomData<&'cursor ()>);
@@ -170,8 +263,29 @@ impl From<ffi::TSQuantifier> for CaptureQuantifier {
 #[doc(alias = "TSQueryCursor")]
 pub struct QueryCursor {
     ptr: NonNull<ffi::TSQueryCursor>,
+    predicate_handler: Option<Box<PredicateHandler>>,
+}
+
+/// A set of [`QueryCursor`]s that can be reused across many query executions,
+/// instead of creating a new cursor for each one.
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub struct QueryCursorPool {
+    cursors: Mutex<Vec<QueryCursor>>,
 }
 
+/// A [`QueryCursor`] borrowed from a [`QueryCursorPool`], which is returned
+/// to the pool when dropped.
+#[cfg(feature = "std")]
+pub struct PooledQueryCursor<'pool> {
+    pool: &'pool QueryCursorPool,
+    cursor: Option<QueryCursor>,
+}
+
+/// A callback for evaluating custom query predicates, set with
+/// [`QueryCursor::set_predicate_handler`].
+pub type PredicateHandler = dyn FnMut(&str, &[QueryPredicateArg]) -> bool + Send;
+
 /// A key-value pair associated with a particular pattern in a [`Query`].
 #[derive(Debug, PartialEq, Eq)]
 pub struct QueryProperty {
@@ -201,10 +315,32 @@ pub struct QueryMatch<'cursor, 'tree> {
     cursor: *mut ffi::TSQueryCursor,
 }
 
+/// A [`QueryMatch`] that owns its captures, so that it can outlive the
+/// [`QueryCursor`] and [`Tree`] that produced it.
+///
+/// This is created with [`QueryMatch::into_owned`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnedQueryMatch {
+    pub pattern_index: usize,
+    pub captures: Vec<(u32, OwnedCaptureNode)>,
+}
+
+/// The kind and location of a captured [`Node`], detached from its [`Tree`].
+///
+/// Use the `byte_range` to slice the captured text out of the source code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnedCaptureNode {
+    pub kind: &'static str,
+    pub byte_range: ops::Range<usize>,
+    pub start_position: Point,
+    pub end_position: Point,
+}
+
 /// A sequence of [`QueryMatch`]es associated with a given [`QueryCursor`].
 pub struct QueryMatches<'query, 'cursor, T: TextProvider<I>, I: AsRef<[u8]>> {
     ptr: *mut ffi::TSQueryCursor,
     query: &'query Query,
+    predicate_handler: Option<&'query mut PredicateHandler>,
     text_provider: T,
     buffer1: Vec<u8>,
     buffer2: Vec<u8>,
@@ -215,6 +351,7 @@ pub struct QueryMatches<'query, 'cursor, T: TextProvider<I>, I: AsRef<[u8]>> {
 pub struct QueryCaptures<'query, 'cursor, T: TextProvider<I>, I: AsRef<[u8]>> {
     ptr: *mut ffi::TSQueryCursor,
     query: &'query Query,
+    predicate_handler: Option<&'query mut PredicateHandler>,
     text_provider: T,
     buffer1: Vec<u8>,
     buffer2: Vec<u8>,
@@ -229,6 +366,19 @@ where
     fn text(&mut self, node: Node) -> Self::I;
 }
 
+/// A UTF8 document that can be parsed with [`Parser::parse_chunked`], such as
+/// a rope or a memory-mapped file.
+///
+/// This is named differently from [`TextProvider`], which supplies the text
+/// of nodes to queries.
+pub trait ChunkedText {
+    /// Get a chunk of the document's text starting at the given byte offset.
+    ///
+    /// The chunk may be of any length, but must be empty if and only if the
+    /// offset is at or past the end of the document.
+    fn chunk(&self, byte_offset: usize) -> &[u8];
+}
+
 /// A particular [`Node`] that has been captured with a particular name within a
 /// [`Query`].
 #[derive(Clone, Copy, Debug)]
@@ -241,15 +391,37 @@ pub struct QueryCapture<'tree> {
 /// An error that occurred when trying to assign an incompatible [`Language`] to
 /// a [`Parser`].
 #[derive(Debug, PartialEq, Eq)]
-pub struct LanguageError {
-    version: usize,
+pub enum LanguageError {
+    /// The language's ABI version is outside of the range of versions that
+    /// this library supports, from `min` to `max` inclusive.
+    IncompatibleVersion { got: usize, min: usize, max: usize },
 }
 
 /// An error that occurred in [`Parser::set_included_ranges`].
 #[derive(Debug, PartialEq, Eq)]
 pub struct IncludedRangesError(pub usize);
 
+/// An error that occurred in [`Parser::try_parse_utf8`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The parser has not had a language assigned with [`Parser::set_language`].
+    NoLanguage,
+    /// The text is longer than the 4GiB that a syntax tree can represent.
+    TextTooLong,
+    /// The parse was stopped by a timeout, deadline, or cancellation flag.
+    Cancelled,
+}
+
+/// An error that occurred in [`QueryCursor::set_byte_range`] or
+/// [`QueryCursor::set_point_range`] because the range's start is after its end.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidRange;
+
 /// An error that occurred when trying to create a [`Query`].
+///
+/// The `offset` is the byte offset in the query source at which the error was
+/// found, and `row` and `column` are the same position in terms of lines. For
+/// predicate errors, this is the start of the pattern containing the predicate.
 #[derive(Debug, PartialEq, Eq)]
 pub struct QueryError {
     pub row: usize,
@@ -327,6 +499,9 @@ impl Language {
     }
 
     /// Get the numeric id for the given node kind.
+    ///
+    /// Returns `0` if the language has no visible or supertype node kind with
+    /// this name and namedness.
     #[doc(alias = "ts_language_symbol_for_name")]
     #[must_use]
     pub fn id_for_node_kind(&self, kind: &str, named: bool) -> u16 {
@@ -347,6 +522,8 @@ impl Language {
         unsafe { ffi::ts_language_symbol_type(self.0, id) == ffi::TSSymbolTypeRegular }
     }
 
+    /// Check if the node type for the given numerical id is visible (as
+    /// opposed to a hidden node type).
     #[doc(alias = "ts_language_symbol_type")]
     #[must_use]
     pub fn node_kind_is_visible(&self, id: u16) -> bool {
@@ -360,7 +537,10 @@ impl Language {
         unsafe { ffi::ts_language_field_count(self.0) as usize }
     }
 
-    /// Get the field names for the given numerical id.
+    /// Get the field name for the given numerical id.
+    ///
+    /// Field ids start at 1 and go up to [`field_count`](Language::field_count),
+    /// inclusive. Any other id returns `None`.
     #[doc(alias = "ts_language_field_name_for_id")]
     #[must_use]
     pub fn field_name_for_id(&self, field_id: u16) -> Option<&'static str> {
@@ -457,53 +637,58 @@ impl Parser {
     pub fn new() -> Self {
         unsafe {
             let parser = ffi::ts_parser_new();
-            Self(NonNull::new_unchecked(parser))
+            Self {
+                ptr: NonNull::new_unchecked(parser),
+                cancellation_flag: None,
+                #[cfg(feature = "std")]
+                deadline: None,
+            }
         }
     }
 
     /// Set the language that the parser should use for parsing.
     ///
     /// Returns a Result indicating whether or not the language was successfully
-    /// assigned. True means assignment succeeded. False means there was a
-    /// version mismatch: the language was generated with an incompatible
-    /// version of the Tree-sitter CLI. Check the language's version using
-    /// [`Language::version`] and compare it to this library's
-    /// [`LANGUAGE_VERSION`](LANGUAGE_VERSION) and
-    /// [`MIN_COMPATIBLE_LANGUAGE_VERSION`](MIN_COMPATIBLE_LANGUAGE_VERSION)
-    /// constants.
+    /// assigned. An error means there was a version mismatch: the language was
+    /// generated with an incompatible version of the Tree-sitter CLI. The
+    /// [`LanguageError::IncompatibleVersion`] error contains the language's
+    /// [`version`](Language::version), along with this library's
+    /// [`MIN_COMPATIBLE_LANGUAGE_VERSION`](MIN_COMPATIBLE_LANGUAGE_VERSION) and
+    /// [`LANGUAGE_VERSION`](LANGUAGE_VERSION) constants.
     #[doc(alias = "ts_parser_set_language")]
     pub fn set_language(&mut self, language: &Language) -> Result<(), LanguageError> {
         let version = language.version();
         if (MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION).contains(&version) {
             unsafe {
-                ffi::ts_parser_set_language(self.0.as_ptr(), language.0);
+                ffi::ts_parser_set_language(self.ptr.as_ptr(), language.0);
             }
             Ok(())
         } else {
-            Err(LanguageError { version })
+            Err(LanguageError::incompatible_version(version))
         }
     }
 
-    /// Get the parser's current language.
+    /// Get the parser's current language, or `None` if no language has been
+    /// set.
     #[doc(alias = "ts_parser_language")]
     #[must_use]
     pub fn language(&self) -> Option<Language> {
-        let ptr = unsafe { ffi::ts_parser_language(self.0.as_ptr()) };
-        (!ptr.is_null()).then(|| Language(ptr))
+        let ptr = unsafe { ffi::ts_parser_language(self.ptr.as_ptr()) };
+        (!ptr.is_null()).then(|| Language(unsafe { ffi::ts_language_copy(ptr) }))
     }
 
     /// Get the parser's current logger.
     #[doc(alias = "ts_parser_logger")]
     #[must_use]
     pub fn logger(&self) -> Option<&Logger> {
-        let logger = unsafe { ffi::ts_parser_logger(self.0.as_ptr()) };
+        let logger = unsafe { ffi::ts_parser_logger(self.ptr.as_ptr()) };
         unsafe { logger.payload.cast::<Logger>().as_ref() }
     }
 
     /// Set the logging callback that a parser should use during parsing.
     #[doc(alias = "ts_parser_set_logger")]
     pub fn set_logger(&mut self, logger: Option<Logger>) {
-        let prev_logger = unsafe { ffi::ts_parser_logger(self.0.as_ptr()) };
+        let prev_logger = unsafe { ffi::ts_parser_logger(self.ptr.as_ptr()) };
         if !prev_logger.payload.is_null() {
             drop(unsafe { Box::from_raw(prev_logger.payload.cast::<Logger>()) });
         }
@@ -541,7 +726,18 @@ impl Parser {
             };
         }
 
-        unsafe { ffi::ts_parser_set_logger(self.0.as_ptr(), c_logger) };
+        unsafe { ffi::ts_parser_set_logger(self.ptr.as_ptr(), c_logger) };
+    }
+
+    /// Set a logging callback that receives each log message as a [`LogEvent`].
+    ///
+    /// This replaces any logger set with [`Parser::set_logger`]. To remove it,
+    /// call `set_logger(None)`.
+    pub fn set_structured_logger(&mut self, mut logger: StructuredLogger) {
+        let mut lex_state = 0;
+        self.set_logger(Some(Box::new(move |log_type, message| {
+            logger(LogEvent::parse(log_type, message, &mut lex_state));
+        })));
     }
 
     /// Set the destination to which the parser should write debugging graphs
@@ -559,7 +755,7 @@ impl Parser {
         {
             let fd = file.as_raw_fd();
             unsafe {
-                ffi::ts_parser_print_dot_graphs(self.0.as_ptr(), ffi::_ts_dup(fd));
+                ffi::ts_parser_print_dot_graphs(self.ptr.as_ptr(), ffi::_ts_dup(fd));
             }
         }
 
@@ -567,7 +763,7 @@ impl Parser {
         {
             let handle = file.as_raw_handle();
             unsafe {
-                ffi::ts_parser_print_dot_graphs(self.0.as_ptr(), ffi::_ts_dup(handle));
+                ffi::ts_parser_print_dot_graphs(self.ptr.as_ptr(), ffi::_ts_dup(handle));
             }
         }
     }
@@ -575,7 +771,7 @@ impl Parser {
     /// Stop the parser from printing debugging graphs while parsing.
     #[doc(alias = "ts_parser_print_dot_graphs")]
     pub fn stop_printing_dot_graphs(&mut self) {
-        unsafe { ffi::ts_parser_print_dot_graphs(self.0.as_ptr(), -1) }
+        unsafe { ffi::ts_parser_print_dot_graphs(self.ptr.as_ptr(), -1) }
     }
 
     /// Parse a slice of UTF8 text.
@@ -589,6 +785,7 @@ impl Parser {
     /// Returns a [`Tree`] if parsing succeeded, or `None` if:
     ///  * The parser has not yet had a language assigned with [`Parser::set_language`]
     ///  * The timeout set with [`Parser::set_timeout_micros`] expired
+    ///  * The deadline set with [`Parser::set_deadline`] passed
     ///  * The cancellation flag set with [`Parser::set_cancellation_flag`] was flipped
     #[doc(alias = "ts_parser_parse")]
     pub fn parse(&mut self, text: impl AsRef<[u8]>, old_tree: Option<&Tree>) -> Option<Tree> {
@@ -600,6 +797,102 @@ impl Parser {
         )
     }
 
+    /// Parse a slice of UTF8 text, reporting why no tree was produced.
+    ///
+    /// This behaves like [`Parser::parse`], but returns a [`ParseError`]
+    /// instead of `None`, and also rejects text too long for the byte offsets
+    /// in a tree. Any byte sequence is accepted as input, including invalid
+    /// UTF8, so this method is suitable for fuzzing. A panic from it should be
+    /// considered a bug.
+    ///
+    /// Allocation failure is not reported as an error. As in the rest of the
+    /// library, the default allocator aborts the process when it runs out of
+    /// memory.
+    pub fn try_parse_utf8(
+        &mut self,
+        text: impl AsRef<[u8]>,
+        old_tree: Option<&Tree>,
+    ) -> Result<Tree, ParseError> {
+        let bytes = text.as_ref();
+        if self.language().is_none() {
+            return Err(ParseError::NoLanguage);
+        }
+        if u32::try_from(bytes.len()).is_err() {
+            return Err(ParseError::TextTooLong);
+        }
+        self.parse(bytes, old_tree).ok_or(ParseError::Cancelled)
+    }
+
+    /// Parse only the first `byte_limit` bytes of a slice of UTF8 text.
+    ///
+    /// The parser treats `byte_limit` as the end of the document, so
+    /// constructs that are cut off there are recovered from as if the document
+    /// ended there. The text after the limit is not parsed. Instead, it is
+    /// represented by a single extra `ERROR` node at the end of the root node,
+    /// so the tree still spans the whole text and a caller can parse
+    /// progressively larger prefixes of a large file.
+    ///
+    /// This uses [`set_included_ranges`](Parser::set_included_ranges) and
+    /// [`set_wrap_remainder`](Parser::set_wrap_remainder). For this parse, the
+    /// parser's included ranges are cut off at `byte_limit`, and the text
+    /// after the limit is wrapped. Both settings are restored afterwards.
+    ///
+    /// If `byte_limit` is at or past the end of `text`, the whole text is
+    /// parsed. The limit should fall on a character boundary.
+    ///
+    /// See [`Parser::parse`] for the meaning of `old_tree` and of a `None`
+    /// return value.
+    pub fn parse_prefix(
+        &mut self,
+        text: impl AsRef<[u8]>,
+        byte_limit: usize,
+        old_tree: Option<&Tree>,
+    ) -> Option<Tree> {
+        let bytes = text.as_ref();
+        let byte_limit = byte_limit.min(bytes.len());
+        let prefix = &bytes[..byte_limit];
+        let line_start = prefix
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let end_point = Point::new(
+            prefix.iter().filter(|&&b| b == b'\n').count(),
+            byte_limit - line_start,
+        );
+
+        let included_ranges = self.included_ranges();
+        let mut ranges = included_ranges
+            .iter()
+            .copied()
+            .filter(|range| range.start_byte < byte_limit)
+            .collect::<Vec<_>>();
+        match ranges.last_mut() {
+            Some(range) if range.end_byte > byte_limit => {
+                range.end_byte = byte_limit;
+                range.end_point = end_point;
+            }
+            Some(_) => {}
+            // An empty range at the limit makes the parser stop right away,
+            // whereas no ranges at all would include the whole document.
+            None => ranges.push(Range {
+                start_byte: byte_limit,
+                end_byte: byte_limit,
+                start_point: end_point,
+                end_point,
+            }),
+        }
+
+        let wrap_remainder = self.wrap_remainder();
+        self.set_wrap_remainder(true);
+        let result = self.set_included_ranges(&ranges);
+        debug_assert!(result.is_ok(), "the cut off ranges are still ordered");
+        let tree = self.parse(bytes, old_tree);
+        let result = self.set_included_ranges(&included_ranges);
+        debug_assert!(result.is_ok(), "the original ranges are still ordered");
+        self.set_wrap_remainder(wrap_remainder);
+        tree
+    }
+
     /// Parse a slice of UTF16 text.
     ///
     /// # Arguments:
@@ -625,8 +918,8 @@ impl Parser {
     /// # Arguments:
     /// * `callback` A function that takes a byte offset and position and returns a slice of
     ///   UTF8-encoded text starting at that byte offset and position. The slices can be of any
-    ///   length. If the given position is at the end of the text, the callback should return an
-    ///   empty slice.
+    ///   length, and may end in the middle of a multi-byte character. If the given position is
+    ///   at the end of the text, the callback should return an empty slice.
     /// * `old_tree` A previous syntax tree parsed from the same document. If the text of the
     ///   document has changed since `old_tree` was created, then you must edit `old_tree` to match
     ///   the new text using [`Tree::edit`].
@@ -662,11 +955,93 @@ impl Parser {
             encoding: ffi::TSInputEncodingUTF8,
         };
 
-        let c_old_tree = old_tree.map_or(ptr::null_mut(), |t| t.0.as_ptr());
-        unsafe {
-            let c_new_tree = ffi::ts_parser_parse(self.0.as_ptr(), c_old_tree, c_input);
-            NonNull::new(c_new_tree).map(Tree)
+        self.parse_input(c_input, old_tree)
+    }
+
+    /// Parse a UTF8 document that provides its text in chunks.
+    ///
+    /// This behaves like [`Parser::parse_with`], but takes the text from a
+    /// [`ChunkedText`] implementation instead of a callback.
+    pub fn parse_chunked<T: ChunkedText + ?Sized>(
+        &mut self,
+        text: &T,
+        old_tree: Option<&Tree>,
+    ) -> Option<Tree> {
+        self.parse_with(&mut |byte_offset, _| text.chunk(byte_offset), old_tree)
+    }
+
+    /// Parse UTF8 text provided in chunks by a callback, asking the callback
+    /// for at least `chunk_size` bytes at a time.
+    ///
+    /// This behaves like [`Parser::parse_with`], but the text returned by the
+    /// callback is buffered until `chunk_size` bytes have been collected, and
+    /// later reads that fall inside the buffered text are served from it. This
+    /// reduces the number of callback invocations for inputs where each call is
+    /// expensive, such as ropes. The chunk size is only a hint, and does not
+    /// affect the resulting tree. A chunk size of zero is treated like one, so
+    /// the callback is always called at least once per read.
+    pub fn parse_with_chunk_size<T: AsRef<[u8]>, F: FnMut(usize, Point) -> T>(
+        &mut self,
+        callback: &mut F,
+        chunk_size: usize,
+        old_tree: Option<&Tree>,
+    ) -> Option<Tree> {
+        struct Payload<'a, F> {
+            callback: &'a mut F,
+            chunk_size: usize,
+            buffer: Vec<u8>,
+            buffer_start: usize,
+        }
+
+        unsafe extern "C" fn read<T: AsRef<[u8]>, F: FnMut(usize, Point) -> T>(
+            payload: *mut c_void,
+            byte_offset: u32,
+            position: ffi::TSPoint,
+            bytes_read: *mut u32,
+        ) -> *const c_char {
+            let payload = payload.cast::<Payload<F>>().as_mut().unwrap();
+            let offset = byte_offset as usize;
+            if offset < payload.buffer_start
+                || offset >= payload.buffer_start + payload.buffer.len()
+            {
+                payload.buffer.clear();
+                payload.buffer_start = offset;
+                let mut position = Point::from(position);
+                while payload.buffer.len() < payload.chunk_size {
+                    let text = (payload.callback)(offset + payload.buffer.len(), position);
+                    let text = text.as_ref();
+                    if text.is_empty() {
+                        break;
+                    }
+                    for byte in text {
+                        if *byte == b'\n' {
+                            position.row += 1;
+                            position.column = 0;
+                        } else {
+                            position.column += 1;
+                        }
+                    }
+                    payload.buffer.extend_from_slice(text);
+                }
+            }
+            let slice = &payload.buffer[offset - payload.buffer_start..];
+            *bytes_read = slice.len() as u32;
+            slice.as_ptr().cast::<c_char>()
         }
+
+        let mut payload = Payload {
+            callback,
+            chunk_size: chunk_size.max(1),
+            buffer: Vec::new(),
+            buffer_start: 0,
+        };
+        let c_input = ffi::TSInput {
+            payload: core::ptr::addr_of_mut!(payload).cast::<c_void>(),
+            read: Some(read::<T, F>),
+            encoding: ffi::TSInputEncodingUTF8,
+        };
+
+        self.parse_input(c_input, old_tree)
     }
 
     /// Parse UTF16 text provided in chunks by a callback.
@@ -717,11 +1092,161 @@ impl Parser {
             encoding: ffi::TSInputEncodingUTF16,
         };
 
+        self.parse_input(c_input, old_tree)
+    }
+
+    /// Parse UTF8 text read from a seekable stream.
+    ///
+    /// The text is read in chunks into an internal buffer. Tree-sitter may
+    /// request text at arbitrary byte offsets, so the reader must support
+    /// seeking to them. Byte offsets are measured from the start of the
+    /// stream. The buffer holds the most recently read chunk, along with the
+    /// last few kilobytes of the chunk before it, so that sequential reads
+    /// don't need to seek when the lexer reads back across a chunk boundary.
+    /// Reading further back than that seeks.
+    ///
+    /// # Arguments:
+    /// * `reader` The source of the UTF8-encoded text to parse.
+    /// * `old_tree` A previous syntax tree parsed from the same document. If the text of the
+    ///   document has changed since `old_tree` was created, then you must edit `old_tree` to match
+    ///   the new text using [`Tree::edit`].
+    ///
+    /// Returns an error if reading from or seeking within `reader` failed.
+    /// Otherwise, returns the same result as [`parse`](Parser::parse).
+    #[cfg(feature = "std")]
+    pub fn parse_stream<R: Read + Seek>(
+        &mut self,
+        mut reader: R,
+        old_tree: Option<&Tree>,
+    ) -> io::Result<Option<Tree>> {
+        const CHUNK_SIZE: usize = 64 * 1024;
+        const OVERLAP_SIZE: usize = 4 * 1024;
+
+        struct StreamPayload<R> {
+            reader: R,
+            position: u64,
+            buffer: Vec<u8>,
+            buffer_start: u64,
+            error: Option<io::Error>,
+        }
+
+        impl<R: Read + Seek> StreamPayload<R> {
+            // Read the chunk starting at the given offset, returning `false` at
+            // the end of the stream. The previous chunk is kept in that case,
+            // since the parser often backtracks a few bytes after reaching EOF.
+            //
+            // When the offset is the end of the previous chunk, the end of that
+            // chunk is kept in front of the new one, because the lexer often
+            // reads back a few bytes across the chunk boundary.
+            fn fill(&mut self, offset: u64) -> io::Result<bool> {
+                let buffer_end = self.buffer_start + self.buffer.len() as u64;
+                let overlap = if offset == buffer_end {
+                    self.buffer.len().min(OVERLAP_SIZE)
+                } else {
+                    0
+                };
+                if offset != self.position {
+                    self.position = self.reader.seek(SeekFrom::Start(offset))?;
+                }
+                let prev_len = self.buffer.len();
+                self.buffer.resize(prev_len + CHUNK_SIZE, 0);
+                let len = loop {
+                    match self.reader.read(&mut self.buffer[prev_len..]) {
+                        Ok(len) => break len,
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                        Err(e) => return Err(e),
+                    }
+                };
+                self.buffer.truncate(prev_len + len);
+                if len == 0 {
+                    return Ok(false);
+                }
+                self.buffer.drain(..prev_len - overlap);
+                self.buffer_start = offset - overlap as u64;
+                self.position = offset + len as u64;
+                Ok(true)
+            }
+        }
+
+        // This C function is passed to Tree-sitter as the input callback.
+        unsafe extern "C" fn read<R: Read + Seek>(
+            payload: *mut c_void,
+            byte_offset: u32,
+            _: ffi::TSPoint,
+            bytes_read: *mut u32,
+        ) -> *const c_char {
+            let payload = payload.cast::<StreamPayload<R>>().as_mut().unwrap();
+            let offset = u64::from(byte_offset);
+            let buffer_end = payload.buffer_start + payload.buffer.len() as u64;
+            let has_text = payload.error.is_none()
+                && ((payload.buffer_start..buffer_end).contains(&offset)
+                    || match payload.fill(offset) {
+                        Ok(has_text) => has_text,
+                        Err(e) => {
+                            payload.error = Some(e);
+                            false
+                        }
+                    });
+            if !has_text {
+                *bytes_read = 0;
+                return b"".as_ptr().cast::<c_char>();
+            }
+            let slice = &payload.buffer[(offset - payload.buffer_start) as usize..];
+            *bytes_read = slice.len() as u32;
+            slice.as_ptr().cast::<c_char>()
+        }
+
+        let mut payload = StreamPayload {
+            position: reader.stream_position()?,
+            reader,
+            buffer: Vec::new(),
+            buffer_start: 0,
+            error: None,
+        };
+
+        let c_input = ffi::TSInput {
+            payload: core::ptr::addr_of_mut!(payload).cast::<c_void>(),
+            read: Some(read::<R>),
+            encoding: ffi::TSInputEncodingUTF8,
+        };
+
+        let tree = self.parse_input(c_input, old_tree);
+        payload.error.map_or(Ok(tree), Err)
+    }
+
+    fn parse_input(&mut self, input: ffi::TSInput, old_tree: Option<&Tree>) -> Option<Tree> {
+        // If a deadline is set, temporarily clamp the timeout to the time that
+        // remains before it, restoring the caller's timeout afterwards.
+        #[cfg(feature = "std")]
+        let prev_timeout_micros = match self.deadline {
+            Some(deadline) => {
+                let remaining = deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_micros();
+                if remaining == 0 {
+                    return None;
+                }
+                let remaining = u64::try_from(remaining).unwrap_or(u64::MAX);
+                let prev_timeout_micros = self.timeout_micros();
+                self.set_timeout_micros(if prev_timeout_micros == 0 {
+                    remaining
+                } else {
+                    prev_timeout_micros.min(remaining)
+                });
+                Some(prev_timeout_micros)
+            }
+            None => None,
+        };
+
         let c_old_tree = old_tree.map_or(ptr::null_mut(), |t| t.0.as_ptr());
-        unsafe {
-            let c_new_tree = ffi::ts_parser_parse(self.0.as_ptr(), c_old_tree, c_input);
-            NonNull::new(c_new_tree).map(Tree)
+        let c_new_tree = unsafe { ffi::ts_parser_parse(self.ptr.as_ptr(), c_old_tree, input) };
+
+        #[cfg(feature = "std")]
+        if let Some(prev_timeout_micros) = prev_timeout_micros {
+            self.set_timeout_micros(prev_timeout_micros);
         }
+
+        NonNull::new(c_new_tree).map(Tree)
     }
 
     /// Instruct the parser to start the next parse from the beginning.
@@ -731,9 +1256,15 @@ impl Parser {
     /// to [`parse`](Parser::parse) or other parsing functions. If you don't
     /// want to resume, and instead intend to use this parser to parse some
     /// other document, you must call `reset` first.
+    ///
+    /// Unlike constructing a new parser, this keeps the parser's language,
+    /// logger, timeout, and other settings. Note that
+    /// [`set_language`](Parser::set_language) already resets the parser, so
+    /// there is no need to call both. Calling `reset` from within a logger
+    /// callback, while a parse is in progress, is not supported.
     #[doc(alias = "ts_parser_reset")]
     pub fn reset(&mut self) {
-        unsafe { ffi::ts_parser_reset(self.0.as_ptr()) }
+        unsafe { ffi::ts_parser_reset(self.ptr.as_ptr()) }
     }
 
     /// Get the duration in microseconds that parsing is allowed to take.
@@ -742,7 +1273,7 @@ impl Parser {
     #[doc(alias = "ts_parser_timeout_micros")]
     #[must_use]
     pub fn timeout_micros(&self) -> u64 {
-        unsafe { ffi::ts_parser_timeout_micros(self.0.as_ptr()) }
+        unsafe { ffi::ts_parser_timeout_micros(self.ptr.as_ptr()) }
     }
 
     /// Set the maximum duration in microseconds that parsing should be allowed
@@ -752,7 +1283,78 @@ impl Parser {
     /// See [`parse`](Parser::parse) for more information.
     #[doc(alias = "ts_parser_set_timeout_micros")]
     pub fn set_timeout_micros(&mut self, timeout_micros: u64) {
-        unsafe { ffi::ts_parser_set_timeout_micros(self.0.as_ptr(), timeout_micros) }
+        unsafe { ffi::ts_parser_set_timeout_micros(self.ptr.as_ptr(), timeout_micros) }
+    }
+
+    /// Get the maximum number of parse operations that parsing is allowed to
+    /// perform.
+    ///
+    /// This is set via [`set_operation_limit`](Parser::set_operation_limit).
+    #[doc(alias = "ts_parser_operation_limit")]
+    #[must_use]
+    pub fn operation_limit(&self) -> Option<NonZeroUsize> {
+        let limit = unsafe { ffi::ts_parser_operation_limit(self.ptr.as_ptr()) };
+        NonZeroUsize::new(limit as usize)
+    }
+
+    /// Set the maximum number of parse operations that each call to
+    /// [`parse`](Parser::parse) should be allowed to perform before halting.
+    ///
+    /// Unlike a timeout, this is deterministic: parsing the same input always
+    /// halts at the same point. If the limit is exceeded, parsing halts early,
+    /// returning `None`, and can be resumed like after a timeout. Pass `None`
+    /// to remove the limit.
+    ///
+    /// The limit can't be zero, because the C library uses a limit of zero to
+    /// mean that there is no limit.
+    #[doc(alias = "ts_parser_set_operation_limit")]
+    pub fn set_operation_limit(&mut self, limit: Option<NonZeroUsize>) {
+        let limit = limit.map_or(0, NonZeroUsize::get);
+        unsafe { ffi::ts_parser_set_operation_limit(self.ptr.as_ptr(), limit as u64) }
+    }
+
+    /// Get whether the parser represents the text after its last included
+    /// range in the syntax tree.
+    ///
+    /// This is set via [`set_wrap_remainder`](Parser::set_wrap_remainder).
+    #[doc(alias = "ts_parser_wrap_remainder")]
+    #[must_use]
+    pub fn wrap_remainder(&self) -> bool {
+        unsafe { ffi::ts_parser_wrap_remainder(self.ptr.as_ptr()) }
+    }
+
+    /// Set whether the parser should represent the text after its last
+    /// included range in the syntax tree.
+    ///
+    /// By default, the end of the last included range is treated as the end
+    /// of the document, and the syntax tree ends there. If this is enabled,
+    /// the text after that range is not parsed, but is added to the tree as a
+    /// single extra `ERROR` node at the end of the root node. See
+    /// [`parse_prefix`](Parser::parse_prefix) for the common use of this.
+    #[doc(alias = "ts_parser_set_wrap_remainder")]
+    pub fn set_wrap_remainder(&mut self, wrap_remainder: bool) {
+        unsafe { ffi::ts_parser_set_wrap_remainder(self.ptr.as_ptr(), wrap_remainder) }
+    }
+
+    /// Get the instant by which parsing must finish.
+    ///
+    /// This is set via [`set_deadline`](Parser::set_deadline).
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub const fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Set an absolute instant by which parsing should halt.
+    ///
+    /// At the start of each parse, the time remaining before the deadline is
+    /// used as the parse's timeout, or the timeout set with
+    /// [`set_timeout_micros`](Parser::set_timeout_micros) if that is shorter.
+    /// If the deadline has already passed, parsing halts immediately, returning
+    /// `None`. Pass `None` to clear a previously set deadline.
+    #[cfg(feature = "std")]
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
     }
 
     /// Set the ranges of text that the parser should include when parsing.
@@ -762,6 +1364,14 @@ impl Parser {
     /// still return a syntax tree whose ranges match up with the document
     /// as a whole. You can also pass multiple disjoint ranges.
     ///
+    /// The end of the last range is treated as the end of the document, so a
+    /// single range that starts at the beginning of a document parses just
+    /// that prefix of it. The resulting tree ends at the end of the range,
+    /// unless [`set_wrap_remainder`](Parser::set_wrap_remainder) is enabled,
+    /// and constructs that are cut off there are recovered from as if the
+    /// document ended there. [`parse_prefix`](Parser::parse_prefix) does this
+    /// for a given byte offset.
+    ///
     /// If `ranges` is empty, then the entire document will be parsed.
     /// Otherwise, the given ranges must be ordered from earliest to latest
     /// in the document, and they must not overlap. That is, the following
@@ -777,7 +1387,7 @@ impl Parser {
         let ts_ranges = ranges.iter().copied().map(Into::into).collect::<Vec<_>>();
         let result = unsafe {
             ffi::ts_parser_set_included_ranges(
-                self.0.as_ptr(),
+                self.ptr.as_ptr(),
                 ts_ranges.as_ptr(),
                 ts_ranges.len() as u32,
             )
@@ -797,6 +1407,15 @@ impl Parser {
         }
     }
 
+    /// Make the parser include the entire document when parsing again, undoing
+    /// any earlier call to [`set_included_ranges`](Parser::set_included_ranges).
+    ///
+    /// This is equivalent to calling `set_included_ranges(&[])`.
+    #[doc(alias = "ts_parser_set_included_ranges")]
+    pub fn clear_included_ranges(&mut self) {
+        unsafe { ffi::ts_parser_set_included_ranges(self.ptr.as_ptr(), ptr::null(), 0) };
+    }
+
     /// Get the ranges of text that the parser will include when parsing.
     #[doc(alias = "ts_parser_included_ranges")]
     #[must_use]
@@ -804,7 +1423,7 @@ impl Parser {
         let mut count = 0u32;
         unsafe {
             let ptr =
-                ffi::ts_parser_included_ranges(self.0.as_ptr(), core::ptr::addr_of_mut!(count));
+                ffi::ts_parser_included_ranges(self.ptr.as_ptr(), core::ptr::addr_of_mut!(count));
             let ranges = slice::from_raw_parts(ptr, count as usize);
             let result = ranges.iter().copied().map(Into::into).collect();
             result
@@ -819,7 +1438,7 @@ impl Parser {
     #[doc(alias = "ts_parser_cancellation_flag")]
     #[must_use]
     pub unsafe fn cancellation_flag(&self) -> Option<&AtomicUsize> {
-        ffi::ts_parser_cancellation_flag(self.0.as_ptr())
+        ffi::ts_parser_cancellation_flag(self.ptr.as_ptr())
             .cast::<AtomicUsize>()
             .as_ref()
     }
@@ -836,22 +1455,40 @@ impl Parser {
     /// It uses FFI
     #[doc(alias = "ts_parser_set_cancellation_flag")]
     pub unsafe fn set_cancellation_flag(&mut self, flag: Option<&AtomicUsize>) {
+        self.cancellation_flag = None;
         if let Some(flag) = flag {
             ffi::ts_parser_set_cancellation_flag(
-                self.0.as_ptr(),
+                self.ptr.as_ptr(),
                 (flag as *const AtomicUsize).cast::<usize>(),
             );
         } else {
-            ffi::ts_parser_set_cancellation_flag(self.0.as_ptr(), ptr::null());
+            ffi::ts_parser_set_cancellation_flag(self.ptr.as_ptr(), ptr::null());
         }
     }
+
+    /// Set the parser's current cancellation flag to a flag that is shared
+    /// with other threads.
+    ///
+    /// This is a safe alternative to
+    /// [`set_cancellation_flag`](Parser::set_cancellation_flag): the parser
+    /// keeps the flag alive for as long as it is assigned. If another thread
+    /// stores a non-zero value in the flag while parsing, the parser will halt
+    /// early, returning `None`. Pass `None` to clear the flag.
+    #[doc(alias = "ts_parser_set_cancellation_flag")]
+    pub fn set_shared_cancellation_flag(&mut self, flag: Option<Arc<AtomicUsize>>) {
+        let ptr = flag.as_deref().map_or(ptr::null(), |flag| {
+            (flag as *const AtomicUsize).cast::<usize>()
+        });
+        unsafe { ffi::ts_parser_set_cancellation_flag(self.ptr.as_ptr(), ptr) };
+        self.cancellation_flag = flag;
+    }
 }
 
 impl Drop for Parser {
     fn drop(&mut self) {
         self.stop_printing_dot_graphs();
         self.set_logger(None);
-        unsafe { ffi::ts_parser_delete(self.0.as_ptr()) }
+        unsafe { ffi::ts_parser_delete(self.ptr.as_ptr()) }
     }
 }
 
@@ -865,6 +1502,11 @@ impl Tree {
 
     /// Get the root node of the syntax tree, but with its position shifted
     /// forward by the given offset.
+    ///
+    /// This is useful when the tree was parsed from a fragment of a larger
+    /// document, starting at the given byte offset and position in that
+    /// document. The positions of the node and its descendants then refer to
+    /// the whole document.
     #[doc(alias = "ts_tree_root_node_with_offset")]
     #[must_use]
     pub fn root_node_with_offset(&self, offset_bytes: usize, offset_extent: Point) -> Node {
@@ -878,6 +1520,67 @@ impl Tree {
         .unwrap()
     }
 
+    /// Get the row and column of the given byte offset, using the positions
+    /// of the nodes in this tree.
+    ///
+    /// The result agrees with the [`start_position`](Node::start_position)
+    /// and [`end_position`](Node::end_position) of every node. Returns `None`
+    /// if the byte is past the end of the parsed input, or if it falls inside
+    /// a token or whitespace that spans multiple lines, since the tree does not
+    /// record where those line breaks are.
+    #[must_use]
+    pub fn point_for_byte(&self, byte: usize) -> Option<Point> {
+        let root = self.root_node();
+        if byte > root.end_byte() {
+            return None;
+        }
+
+        let mut cursor = root.walk();
+        let (mut start_byte, mut start_point) = (0, Point::default());
+        let (mut end_byte, mut end_point) = (root.start_byte(), root.start_position());
+        let mut node = None;
+        if byte >= end_byte {
+            (start_byte, start_point) = (end_byte, end_point);
+            (end_byte, end_point) = (root.end_byte(), root.end_position());
+            node = Some(root);
+        }
+
+        loop {
+            if byte == start_byte {
+                return Some(start_point);
+            }
+            if byte == end_byte {
+                return Some(end_point);
+            }
+            let Some(parent) = node else {
+                break;
+            };
+
+            // Narrow the span down to the child containing the byte, or to
+            // the gap between two children.
+            node = None;
+            for child in parent.children(&mut cursor) {
+                if child.end_byte() <= byte {
+                    (start_byte, start_point) = (child.end_byte(), child.end_position());
+                } else {
+                    if child.start_byte() <= byte {
+                        (start_byte, start_point) = (child.start_byte(), child.start_position());
+                        node = Some(child);
+                    }
+                    (end_byte, end_point) = if node.is_some() {
+                        (child.end_byte(), child.end_position())
+                    } else {
+                        (child.start_byte(), child.start_position())
+                    };
+                    break;
+                }
+            }
+        }
+
+        (start_point.row == end_point.row)
+            .then(|| Point::new(start_point.row, start_point.column + byte - start_byte))
+    }
+
     /// Get the language that was used to parse the syntax tree.
     #[doc(alias = "ts_tree_language")]
     #[must_use]
@@ -905,6 +1608,62 @@ impl Tree {
         self.root_node().walk()
     }
 
+    /// Iterate over every node in the tree in preorder, starting with the
+    /// root node, pairing each node with its depth below the root.
+    pub fn walk_preorder(&self) -> impl Iterator<Item = (Node<'_>, usize)> {
+        let mut cursor = self.walk();
+        let mut done = false;
+        iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let result = (cursor.node(), cursor.depth() as usize);
+            if !cursor.goto_first_child() {
+                while !cursor.goto_next_sibling() {
+                    if !cursor.goto_parent() {
+                        done = true;
+                        break;
+                    }
+                }
+            }
+            Some(result)
+        })
+    }
+
+    /// Get the syntax errors in the tree, in document order.
+    ///
+    /// There is one diagnostic for each `ERROR` node and each `MISSING` node.
+    /// The children of an `ERROR` node are not searched, since they belong to
+    /// the same error.
+    #[must_use]
+    pub fn errors(&self) -> Vec<Diagnostic> {
+        let mut result = Vec::new();
+        let mut cursor = self.walk();
+        loop {
+            let node = cursor.node();
+            let kind = if node.is_error() {
+                Some(DiagnosticKind::Unexpected)
+            } else if node.is_missing() {
+                Some(DiagnosticKind::Missing(node.kind()))
+            } else {
+                None
+            };
+            if let Some(kind) = kind {
+                result.push(Diagnostic {
+                    range: node.range(),
+                    kind,
+                });
+            }
+            if kind.is_some() || !node.has_error() || !cursor.goto_first_child() {
+                while !cursor.goto_next_sibling() {
+                    if !cursor.goto_parent() {
+                        return result;
+                    }
+                }
+            }
+        }
+    }
+
     /// Compare this old edited syntax tree to a new syntax tree representing
     /// the same document, returning a sequence of ranges whose syntactic
     /// structure has changed.
@@ -942,6 +1701,22 @@ impl Tree {
         }
     }
 
+    /// Check whether this syntax tree and another one were parsed with
+    /// different included ranges.
+    ///
+    /// Ranges are compared by both byte offset and point. As with
+    /// [`Tree::changed_ranges`], call this on an old tree that has been edited
+    /// to match the new tree's document. [`Tree::edit`] moves the tree's
+    /// included ranges along with the text around them, so ranges that were
+    /// only moved by an edit are not reported as changed. A boundary that an
+    /// edit shifted relative to the surrounding text, such as the end of an
+    /// injection that was moved, is reported as a change, as are ranges that
+    /// were added or removed.
+    #[must_use]
+    pub fn included_ranges_changed(&self, other: &Self) -> bool {
+        self.included_ranges() != other.included_ranges()
+    }
+
     /// Print a graph of the tree to the given file descriptor.
     /// The graph is formatted in the DOT language. You may want to pipe this
     /// graph directly to a `dot(1)` process in order to generate SVG
@@ -1027,6 +1802,9 @@ impl<'tree> Node<'tree> {
 
     /// Get this node's symbol name as it appears in the grammar ignoring
     /// aliases as a string.
+    ///
+    /// An alias that is applied to every occurrence of a symbol renames the
+    /// symbol itself, so it is not ignored.
     #[doc(alias = "ts_node_grammar_type")]
     #[must_use]
     pub fn grammar_name(&self) -> &'static str {
@@ -1346,7 +2124,7 @@ impl<'tree> Node<'tree> {
         })
     }
 
-    /// Get this node's immediate parent.
+    /// Get this node's immediate parent, or `None` if this is the root node.
     /// Prefer [`child_containing_descendant`](Node::child_containing_descendant)
     /// for iterating over this node's ancestors.
     #[doc(alias = "ts_node_parent")]
@@ -1376,14 +2154,15 @@ impl<'tree> Node<'tree> {
         Self::new(unsafe { ffi::ts_node_prev_sibling(self.0) })
     }
 
-    /// Get this node's next named sibling.
+    /// Get this node's next named sibling, or `None` if this is the last one.
     #[doc(alias = "ts_node_next_named_sibling")]
     #[must_use]
     pub fn next_named_sibling(&self) -> Option<Self> {
         Self::new(unsafe { ffi::ts_node_next_named_sibling(self.0) })
     }
 
-    /// Get this node's previous named sibling.
+    /// Get this node's previous named sibling, or `None` if this is the first
+    /// one.
     #[doc(alias = "ts_node_prev_named_sibling")]
     #[must_use]
     pub fn prev_named_sibling(&self) -> Option<Self> {
@@ -1391,6 +2170,9 @@ impl<'tree> Node<'tree> {
     }
 
     /// Get the node's first child that extends beyond the given byte offset.
+    ///
+    /// This is the child containing the offset, if there is one. Otherwise,
+    /// it is the first child that starts after the offset.
     #[doc(alias = "ts_node_first_child_for_byte")]
     #[must_use]
     pub fn first_child_for_byte(&self, byte: usize) -> Option<Self> {
@@ -1398,7 +2180,9 @@ impl<'tree> Node<'tree> {
     }
 
     /// Get the node's first named child that extends beyond the given byte offset.
-    #[doc(alias = "ts_node_first_named_child_for_point")]
+    ///
+    /// See [`Node::first_child_for_byte`].
+    #[doc(alias = "ts_node_first_named_child_for_byte")]
     #[must_use]
     pub fn first_named_child_for_byte(&self, byte: usize) -> Option<Self> {
         Self::new(unsafe { ffi::ts_node_first_named_child_for_byte(self.0, byte as u32) })
@@ -1411,6 +2195,27 @@ impl<'tree> Node<'tree> {
         unsafe { ffi::ts_node_descendant_count(self.0) as usize }
     }
 
+    /// Get the node's number of named descendants, including one for the node
+    /// itself if it is named.
+    #[must_use]
+    pub fn named_descendant_count(&self) -> usize {
+        let mut cursor = self.walk();
+        let mut count = 0;
+        loop {
+            if cursor.node().is_named() {
+                count += 1;
+            }
+            if cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return count;
+                }
+            }
+        }
+    }
+
     /// Get the smallest node within this node that spans the given range.
     #[doc(alias = "ts_node_descendant_for_byte_range")]
     #[must_use]
@@ -1430,6 +2235,8 @@ impl<'tree> Node<'tree> {
     }
 
     /// Get the smallest node within this node that spans the given range.
+    ///
+    /// If the range extends beyond this node, this node itself is returned.
     #[doc(alias = "ts_node_descendant_for_point_range")]
     #[must_use]
     pub fn descendant_for_point_range(&self, start: Point, end: Point) -> Option<Self> {
@@ -1439,6 +2246,8 @@ impl<'tree> Node<'tree> {
     }
 
     /// Get the smallest named node within this node that spans the given range.
+    ///
+    /// If the range extends beyond this node, this node itself is returned.
     #[doc(alias = "ts_node_named_descendant_for_point_range")]
     #[must_use]
     pub fn named_descendant_for_point_range(&self, start: Point, end: Point) -> Option<Self> {
@@ -1450,22 +2259,137 @@ impl<'tree> Node<'tree> {
     #[doc(alias = "ts_node_string")]
     #[must_use]
     pub fn to_sexp(&self) -> String {
-        let c_string = unsafe { ffi::ts_node_string(self.0) };
-        let result = unsafe { CStr::from_ptr(c_string) }
-            .to_str()
-            .unwrap()
-            .to_string();
-        unsafe { (FREE_FN)(c_string.cast::<c_void>()) };
+        self.to_sexp_with_options(SexpOptions::default())
+    }
+
+    /// Get an S-expression like [`Node::to_sexp`], formatted according to the
+    /// given options.
+    #[must_use]
+    pub fn to_sexp_with_options(&self, options: SexpOptions) -> String {
+        // The empty string has no capacity, so the S-expression is measured
+        // first, and then written into a string of exactly the right size.
+        let mut result = String::new();
+        self.write_sexp_with_options(options, &mut result);
         result
     }
 
+    /// Append the S-expression returned by [`Node::to_sexp`] to a string.
+    ///
+    /// The S-expression is written directly into the string's spare capacity,
+    /// so by reusing one string across calls, a caller can avoid allocating
+    /// memory once the string has grown large enough.
+    #[doc(alias = "ts_node_write_string")]
+    pub fn write_sexp(&self, out: &mut String) {
+        self.write_sexp_with_options(SexpOptions::default(), out);
+    }
+
+    /// Append the S-expression returned by [`Node::to_sexp_with_options`] to a
+    /// string, like [`Node::write_sexp`].
+    #[doc(alias = "ts_node_write_string_with_options")]
+    pub fn write_sexp_with_options(&self, options: SexpOptions, out: &mut String) {
+        let options = ffi::TSNodeStringOptions {
+            include_ranges: options.include_ranges,
+            named_only: options.named_only,
+            max_depth: options
+                .max_depth
+                .map_or(u32::MAX, |depth| u32::try_from(depth).unwrap_or(u32::MAX)),
+        };
+        let start = out.len();
+        // SAFETY: the bytes written by the C library are checked to be valid
+        // UTF8 below, before the string can be used again.
+        let bytes = unsafe { out.as_mut_vec() };
+
+        // Write into the string's spare capacity. If it's too small, this only
+        // measures the S-expression, and it's written again with enough room.
+        loop {
+            let size = bytes.capacity() - start;
+            let buffer = if size == 0 {
+                ptr::null_mut()
+            } else {
+                unsafe { bytes.as_mut_ptr().add(start).cast::<c_char>() }
+            };
+            let len =
+                unsafe { ffi::ts_node_write_string_with_options(self.0, options, buffer, size) };
+            if len < size {
+                unsafe { bytes.set_len(start + len) };
+                break;
+            }
+            bytes.reserve(len + 1);
+        }
+        if str::from_utf8(&bytes[start..]).is_err() {
+            bytes.truncate(start);
+            panic!("S-expression is not valid UTF8");
+        }
+    }
+
+    /// Render this node and its named descendants as an indented listing for
+    /// debugging, with one line per node showing its field name, kind, text
+    /// and byte range, e.g. `name: identifier "foo" [4..7]`.
+    ///
+    /// Text longer than 24 characters is truncated with an ellipsis. Panics if
+    /// a node extends beyond the end of `source`.
+    #[must_use]
+    pub fn debug_tree(&self, source: &[u8]) -> String {
+        const MAX_TEXT_LEN: usize = 24;
+
+        let mut result = String::new();
+        let mut cursor = self.walk();
+        let mut ancestors_are_named = Vec::new();
+        let mut indent_level = 0;
+        loop {
+            let node = cursor.node();
+            if node.is_named() {
+                let text = String::from_utf8_lossy(&source[node.byte_range()]);
+                let mut chars = text.chars();
+                let mut snippet = chars.by_ref().take(MAX_TEXT_LEN).collect::<String>();
+                if chars.next().is_some() {
+                    snippet.push('…');
+                }
+                result.push_str(&"  ".repeat(indent_level));
+                if let Some(field_name) = cursor.field_name() {
+                    write!(result, "{field_name}: ").unwrap();
+                }
+                writeln!(
+                    result,
+                    "{} {snippet:?} [{}..{}]",
+                    node.kind(),
+                    node.start_byte(),
+                    node.end_byte()
+                )
+                .unwrap();
+            }
+
+            if cursor.goto_first_child() {
+                ancestors_are_named.push(node.is_named());
+                indent_level += usize::from(node.is_named());
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return result;
+                }
+                if ancestors_are_named.pop() == Some(true) {
+                    indent_level -= 1;
+                }
+            }
+        }
+    }
+
+    /// Get the node's text from the UTF8 source code that it was parsed from.
+    ///
+    /// Returns an error if the node's range of `source` is not valid UTF8.
+    /// Panics if the node extends beyond the end of `source`.
     pub fn utf8_text<'a>(&self, source: &'a [u8]) -> Result<&'a str, str::Utf8Error> {
         str::from_utf8(&source[self.start_byte()..self.end_byte()])
     }
 
+    /// Get the node's text from UTF16 source code that it was parsed from.
+    ///
+    /// The node's byte offsets count two bytes per UTF16 code unit, so they
+    /// are halved to index into `source`.
     #[must_use]
     pub fn utf16_text<'a>(&self, source: &'a [u16]) -> &'a [u16] {
-        &source[self.start_byte()..self.end_byte()]
+        &source[self.start_byte() / 2..self.end_byte() / 2]
     }
 
     /// Create a new [`TreeCursor`] starting from this node.
@@ -1482,6 +2406,11 @@ impl<'tree> Node<'tree> {
     /// the tree afterward will already reflect the edit. You only need to
     /// use [`Node::edit`] when you have a specific [`Node`] instance that
     /// you want to keep and continue to use after an edit.
+    ///
+    /// This does not reparse anything: it only shifts the node's stored byte
+    /// offsets and positions. The result is only meaningful for nodes whose
+    /// structure is not affected by the edit, such as nodes that lie entirely
+    /// after the edited range.
     #[doc(alias = "ts_node_edit")]
     pub fn edit(&mut self, edit: &InputEdit) {
         let edit = edit.into();
@@ -1489,9 +2418,18 @@ impl<'tree> Node<'tree> {
     }
 }
 
+impl Node<'_> {
+    // The key that `ts_node_eq` compares: the tree, and the address of the
+    // node's subtree within it.
+    fn eq_key(&self) -> (*const ffi::TSTree, *const c_void) {
+        (self.0.tree, self.0.id)
+    }
+}
+
 impl PartialEq for Node<'_> {
+    #[doc(alias = "ts_node_eq")]
     fn eq(&self, other: &Self) -> bool {
-        self.0.id == other.0.id
+        self.eq_key() == other.eq_key()
     }
 }
 
@@ -1499,11 +2437,7 @@ impl Eq for Node<'_> {}
 
 impl hash::Hash for Node<'_> {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
-        self.0.id.hash(state);
-        self.0.context[0].hash(state);
-        self.0.context[1].hash(state);
-        self.0.context[2].hash(state);
-        self.0.context[3].hash(state);
+        self.eq_key().hash(state);
     }
 }
 
@@ -1808,10 +2742,7 @@ impl Query {
                     row: 0,
                     column: 0,
                     offset: 0,
-                    message: LanguageError {
-                        version: language.version(),
-                    }
-                    .to_string(),
+                    message: LanguageError::incompatible_version(language.version()).to_string(),
                     kind: QueryErrorKind::Language,
                 });
             }
@@ -1949,12 +2880,9 @@ impl Query {
                     .unwrap_or_default()
             };
 
-            let byte_offset = unsafe { ffi::ts_query_start_byte_for_pattern(ptr.0, i as u32) };
-            let row = source
-                .char_indices()
-                .take_while(|(i, _)| *i < byte_offset as usize)
-                .filter(|(_, c)| *c == '\n')
-                .count();
+            let offset = unsafe { ffi::ts_query_start_byte_for_pattern(ptr.0, i as u32) } as usize;
+            let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let position = Point::new(source[..offset].matches('\n').count(), offset - line_start);
 
             use ffi::TSQueryPredicateStepType as T;
             const TYPE_DONE: T = ffi::TSQueryPredicateStepTypeDone;
@@ -1972,7 +2900,8 @@ impl Query {
 
                 if p[0].type_ != TYPE_STRING {
                     return Err(predicate_error(
-                        row,
+                        position,
+                        offset,
                         format!(
                             "Expected predicate to start with a function name. Got @{}.",
                             capture_names[p[0].value_id as usize],
@@ -1986,7 +2915,8 @@ impl Query {
                     "eq?" | "not-eq?" | "any-eq?" | "any-not-eq?" => {
                         if p.len() != 3 {
                             return Err(predicate_error(
-                                row,
+                                position,
+                                offset,
                                 format!(
                                 "Wrong number of arguments to #eq? predicate. Expected 2, got {}.",
                                 p.len() - 1
@@ -1994,7 +2924,7 @@ impl Query {
                             ));
                         }
                         if p[1].type_ != TYPE_CAPTURE {
-                            return Err(predicate_error(row, format!(
+                            return Err(predicate_error(position, offset, format!(
                                 "First argument to #eq? predicate must be a capture name. Got literal \"{}\".",
                                 string_values[p[1].value_id as usize],
                             )));
@@ -2025,19 +2955,19 @@ impl Query {
 
                     "match?" | "not-match?" | "any-match?" | "any-not-match?" => {
                         if p.len() != 3 {
-                            return Err(predicate_error(row, format!(
+                            return Err(predicate_error(position, offset, format!(
                                 "Wrong number of arguments to #match? predicate. Expected 2, got {}.",
                                 p.len() - 1
                             )));
                         }
                         if p[1].type_ != TYPE_CAPTURE {
-                            return Err(predicate_error(row, format!(
+                            return Err(predicate_error(position, offset, format!(
                                 "First argument to #match? predicate must be a capture name. Got literal \"{}\".",
                                 string_values[p[1].value_id as usize],
                             )));
                         }
                         if p[2].type_ == TYPE_CAPTURE {
-                            return Err(predicate_error(row, format!(
+                            return Err(predicate_error(position, offset, format!(
                                 "Second argument to #match? predicate must be a literal. Got capture @{}.",
                                 capture_names[p[2].value_id as usize],
                             )));
@@ -2054,7 +2984,11 @@ impl Query {
                         text_predicates.push(TextPredicateCapture::MatchString(
                             p[1].value_id,
                             regex::bytes::Regex::new(regex).map_err(|_| {
-                                predicate_error(row, format!("Invalid regex '{regex}'"))
+                                predicate_error(
+                                    position,
+                                    offset,
+                                    format!("Invalid regex '{regex}'"),
+                                )
                             })?,
                             is_positive,
                             match_all,
@@ -2062,7 +2996,8 @@ impl Query {
                     }
 
                     "set!" => property_settings.push(Self::parse_property(
-                        row,
+                        position,
+                        offset,
                         operator_name,
                         &capture_names,
                         &string_values,
@@ -2071,7 +3006,8 @@ impl Query {
 
                     "is?" | "is-not?" => property_predicates.push((
                         Self::parse_property(
-                            row,
+                            position,
+                            offset,
                             operator_name,
                             &capture_names,
                             &string_values,
@@ -2082,13 +3018,13 @@ impl Query {
 
                     "any-of?" | "not-any-of?" => {
                         if p.len() < 2 {
-                            return Err(predicate_error(row, format!(
+                            return Err(predicate_error(position, offset, format!(
                                 "Wrong number of arguments to #any-of? predicate. Expected at least 1, got {}.",
                                 p.len() - 1
                             )));
                         }
                         if p[1].type_ != TYPE_CAPTURE {
-                            return Err(predicate_error(row, format!(
+                            return Err(predicate_error(position, offset, format!(
                                 "First argument to #any-of? predicate must be a capture name. Got literal \"{}\".",
                                 string_values[p[1].value_id as usize],
                             )));
@@ -2098,7 +3034,7 @@ impl Query {
                         let mut values = Vec::new();
                         for arg in &p[2..] {
                             if arg.type_ == TYPE_CAPTURE {
-                                return Err(predicate_error(row, format!(
+                                return Err(predicate_error(position, offset, format!(
                                     "Arguments to #any-of? predicate must be literals. Got capture @{}.",
                                     capture_names[arg.value_id as usize],
                                 )));
@@ -2193,18 +3129,27 @@ impl Query {
     }
 
     /// Get the names of the captures used in the query.
+    ///
+    /// The names are ordered by capture index, so a [`QueryCapture`]'s `index`
+    /// can be used to look up its name in this slice.
     #[must_use]
     pub const fn capture_names(&self) -> &[&str] {
         &self.capture_names
     }
 
-    /// Get the quantifiers of the captures used in the query.
+    /// Get the quantifiers of the captures used in the given pattern.
+    ///
+    /// The returned slice is indexed by capture id, in the same order as
+    /// [`capture_names`](Query::capture_names). Captures that don't appear in
+    /// the pattern have a quantifier of [`CaptureQuantifier::Zero`].
+    #[doc(alias = "ts_query_capture_quantifier_for_id")]
     #[must_use]
     pub const fn capture_quantifiers(&self, index: usize) -> &[CaptureQuantifier] {
         &self.capture_quantifiers[index]
     }
 
-    /// Get the index for a given capture name.
+    /// Get the index for a given capture name, or `None` if the query does
+    /// not use a capture with that name.
     #[must_use]
     pub fn capture_index_for_name(&self, name: &str) -> Option<u32> {
         self.capture_names
@@ -2245,6 +3190,9 @@ impl Query {
     ///
     /// This prevents the capture from being returned in matches, and also
     /// avoids any resource usage associated with recording the capture.
+    ///
+    /// This can't be undone: the capture stays disabled for as long as this
+    /// `Query` exists. To use the capture again, create a new `Query`.
     #[doc(alias = "ts_query_disable_capture")]
     pub fn disable_capture(&mut self, name: &str) {
         unsafe {
@@ -2260,6 +3208,10 @@ impl Query {
     ///
     /// This prevents the pattern from matching, and also avoids any resource
     /// usage associated with the pattern.
+    ///
+    /// Like [`disable_capture`](Query::disable_capture), this can't be
+    /// undone for this `Query`. The indices of the other patterns are not
+    /// affected.
     #[doc(alias = "ts_query_disable_pattern")]
     pub fn disable_pattern(&mut self, index: usize) {
         unsafe { ffi::ts_query_disable_pattern(self.ptr.as_ptr(), index as u32) }
@@ -2272,7 +3224,9 @@ impl Query {
         unsafe { ffi::ts_query_is_pattern_rooted(self.ptr.as_ptr(), index as u32) }
     }
 
-    /// Check if a given pattern within a query has a single root node.
+    /// Check if a given pattern within a query is 'non-local', meaning that
+    /// it may match a sequence of sibling nodes that is not contained within
+    /// a single parent node.
     #[doc(alias = "ts_query_is_pattern_non_local")]
     #[must_use]
     pub fn is_pattern_non_local(&self, index: usize) -> bool {
@@ -2292,7 +3246,8 @@ impl Query {
     }
 
     fn parse_property(
-        row: usize,
+        position: Point,
+        offset: usize,
         function_name: &str,
         capture_names: &[&str],
         string_values: &[&str],
@@ -2300,7 +3255,8 @@ impl Query {
     ) -> Result<QueryProperty, QueryError> {
         if args.is_empty() || args.len() > 3 {
             return Err(predicate_error(
-                row,
+                position,
+                offset,
                 format!(
                     "Wrong number of arguments to {function_name} predicate. Expected 1 to 3, got {}.",
                     args.len(),
@@ -2316,7 +3272,8 @@ impl Query {
             if arg.type_ == ffi::TSQueryPredicateStepTypeCapture {
                 if capture_id.is_some() {
                     return Err(predicate_error(
-                        row,
+                        position,
+                        offset,
                         format!(
                             "Invalid arguments to {function_name} predicate. Unexpected second capture name @{}",
                             capture_names[arg.value_id as usize]
@@ -2330,7 +3287,8 @@ impl Query {
                 value = Some(string_values[arg.value_id as usize]);
             } else {
                 return Err(predicate_error(
-                    row,
+                    position,
+                    offset,
                     format!(
                         "Invalid arguments to {function_name} predicate. Unexpected third argument @{}",
                         string_values[arg.value_id as usize]
@@ -2343,7 +3301,8 @@ impl Query {
             Ok(QueryProperty::new(key, value, capture_id))
         } else {
             Err(predicate_error(
-                row,
+                position,
+                offset,
                 format!("Invalid arguments to {function_name} predicate. Missing key argument",),
             ))
         }
@@ -2366,10 +3325,13 @@ impl QueryCursor {
     pub fn new() -> Self {
         Self {
             ptr: unsafe { NonNull::new_unchecked(ffi::ts_query_cursor_new()) },
+            predicate_handler: None,
         }
     }
 
     /// Return the maximum number of in-progress matches for this cursor.
+    ///
+    /// This is `u32::MAX` if the cursor has no match limit.
     #[doc(alias = "ts_query_cursor_match_limit")]
     #[must_use]
     pub fn match_limit(&self) -> u32 {
@@ -2378,11 +3340,20 @@ impl QueryCursor {
 
     /// Set the maximum number of in-progress matches for this cursor.  The
     /// limit must be > 0 and <= 65536.
+    ///
+    /// When the limit is reached, the cursor drops its oldest in-progress
+    /// match to make room for new ones, and [`did_exceed_match_limit`] will
+    /// return `true` for the rest of the execution.
+    ///
+    /// Set to `None` to remove the match limit.
+    ///
+    /// [`did_exceed_match_limit`]: QueryCursor::did_exceed_match_limit
     #[doc(alias = "ts_query_cursor_set_match_limit")]
-    pub fn set_match_limit(&mut self, limit: u32) {
+    pub fn set_match_limit(&mut self, limit: Option<u32>) -> &mut Self {
         unsafe {
-            ffi::ts_query_cursor_set_match_limit(self.ptr.as_ptr(), limit);
+            ffi::ts_query_cursor_set_match_limit(self.ptr.as_ptr(), limit.unwrap_or(u32::MAX));
         }
+        self
     }
 
     /// Set the maximum duration in microseconds that query execution should be allowed to
@@ -2431,6 +3402,7 @@ impl QueryCursor {
         QueryMatches {
             ptr,
             query,
+            predicate_handler: self.predicate_handler.as_deref_mut(),
             text_provider,
             buffer1: Vec::default(),
             buffer2: Vec::default(),
@@ -2455,6 +3427,7 @@ impl QueryCursor {
         QueryCaptures {
             ptr,
             query,
+            predicate_handler: self.predicate_handler.as_deref_mut(),
             text_provider,
             buffer1: Vec::default(),
             buffer2: Vec::default(),
@@ -2462,10 +3435,33 @@ impl QueryCursor {
         }
     }
 
+    /// Set a callback for evaluating predicates that are not built in.
+    ///
+    /// The handler is called with the operator and arguments of each of the
+    /// pattern's [`general_predicates`](Query::general_predicates), with every
+    /// capture argument replaced by the text of the first node it captured
+    /// (or an empty string). Matches for which the handler returns `false`
+    /// are filtered out.
+    ///
+    /// The built-in text predicates, such as `#eq?`, `#match?` and
+    /// `#any-of?`, are checked first; the handler is only called for matches
+    /// that satisfy all of them. Custom predicates are then evaluated in the
+    /// order that they appear in the pattern, stopping at the first one for
+    /// which the handler returns `false`.
+    pub fn set_predicate_handler(&mut self, handler: Box<PredicateHandler>) {
+        self.predicate_handler = Some(handler);
+    }
+
     /// Set the range in which the query will be executed, in terms of byte
     /// offsets.
+    ///
+    /// An end of zero means that the range extends to the end of the document.
+    /// Returns an [`InvalidRange`] error if the start is after a nonzero end.
     #[doc(alias = "ts_query_cursor_set_byte_range")]
-    pub fn set_byte_range(&mut self, range: ops::Range<usize>) -> &mut Self {
+    pub fn set_byte_range(&mut self, range: ops::Range<usize>) -> Result<&mut Self, InvalidRange> {
+        if range.end != 0 && range.start > range.end {
+            return Err(InvalidRange);
+        }
         unsafe {
             ffi::ts_query_cursor_set_byte_range(
                 self.ptr.as_ptr(),
@@ -2473,13 +3469,20 @@ impl QueryCursor {
                 range.end as u32,
             );
         }
-        self
+        Ok(self)
     }
 
     /// Set the range in which the query will be executed, in terms of rows and
     /// columns.
+    ///
+    /// An end of `(0, 0)` means that the range extends to the end of the
+    /// document. Returns an [`InvalidRange`] error if the start is after a
+    /// nonzero end.
     #[doc(alias = "ts_query_cursor_set_point_range")]
-    pub fn set_point_range(&mut self, range: ops::Range<Point>) -> &mut Self {
+    pub fn set_point_range(&mut self, range: ops::Range<Point>) -> Result<&mut Self, InvalidRange> {
+        if range.end != Point::default() && range.start > range.end {
+            return Err(InvalidRange);
+        }
         unsafe {
             ffi::ts_query_cursor_set_point_range(
                 self.ptr.as_ptr(),
@@ -2487,7 +3490,7 @@ impl QueryCursor {
                 range.end.into(),
             );
         }
-        self
+        Ok(self)
     }
 
     /// Set the maximum start depth for a query cursor.
@@ -2516,17 +3519,115 @@ impl QueryCursor {
     }
 }
 
+#[cfg(feature = "std")]
+impl QueryCursorPool {
+    /// Create a new, empty pool.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a cursor from the pool, or create a new one if all of the pool's
+    /// cursors are in use.
+    ///
+    /// When the returned guard is dropped, the cursor's byte range, point
+    /// range, match limit, timeout, maximum start depth and predicate handler
+    /// are reset to their defaults, and the cursor is returned to the pool.
+    #[must_use]
+    pub fn get(&self) -> PooledQueryCursor<'_> {
+        let cursor = self.cursors.lock().unwrap().pop();
+        PooledQueryCursor {
+            pool: self,
+            cursor: Some(cursor.unwrap_or_default()),
+        }
+    }
+
+    /// Get the number of cursors that are currently waiting in the pool to be
+    /// reused.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        self.cursors.lock().unwrap().len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Deref for PooledQueryCursor<'_> {
+    type Target = QueryCursor;
+
+    fn deref(&self) -> &Self::Target {
+        self.cursor.as_ref().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl DerefMut for PooledQueryCursor<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.cursor.as_mut().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for PooledQueryCursor<'_> {
+    fn drop(&mut self) {
+        if let Some(mut cursor) = self.cursor.take() {
+            cursor.set_byte_range(0..usize::MAX).unwrap();
+            cursor
+                .set_point_range(Point::new(0, 0)..Point::new(usize::MAX, usize::MAX))
+                .unwrap();
+            cursor.set_max_start_depth(None);
+            cursor.set_match_limit(None);
+            cursor.set_timeout_micros(0);
+            cursor.predicate_handler = None;
+            if let Ok(mut cursors) = self.pool.cursors.lock() {
+                cursors.push(cursor);
+            }
+        }
+    }
+}
+
 impl<'tree> QueryMatch<'_, 'tree> {
     #[must_use]
     pub const fn id(&self) -> u32 {
         self.id
     }
 
+    /// Remove this match from the cursor that produced it.
+    ///
+    /// If the match is still in progress, as can happen when iterating over
+    /// [`QueryCursor::captures`], none of its remaining captures will be
+    /// returned. Its slot is freed right away, so with a
+    /// [match limit](QueryCursor::set_match_limit) set, removing unwanted
+    /// matches makes room for later ones.
     #[doc(alias = "ts_query_cursor_remove_match")]
     pub fn remove(self) {
         unsafe { ffi::ts_query_cursor_remove_match(self.cursor, self.id) }
     }
 
+    /// Copy this match's captures into an [`OwnedQueryMatch`], which does not
+    /// borrow the query cursor or the syntax tree.
+    #[must_use]
+    pub fn into_owned(self) -> OwnedQueryMatch {
+        OwnedQueryMatch {
+            pattern_index: self.pattern_index,
+            captures: self
+                .captures
+                .iter()
+                .map(|capture| {
+                    let node = capture.node;
+                    (
+                        capture.index,
+                        OwnedCaptureNode {
+                            kind: node.kind(),
+                            byte_range: node.byte_range(),
+                            start_position: node.start_position(),
+                            end_position: node.end_position(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
     pub fn nodes_for_capture_index(
         &self,
         capture_ix: u32,
@@ -2657,6 +3758,40 @@ impl<'tree> QueryMatch<'_, 'tree> {
                 }
             })
     }
+
+    fn satisfies_general_predicates<I: AsRef<[u8]>>(
+        &self,
+        query: &Query,
+        handler: Option<&mut PredicateHandler>,
+        buffer: &mut Vec<u8>,
+        text_provider: &mut impl TextProvider<I>,
+    ) -> bool {
+        let Some(handler) = handler else {
+            return true;
+        };
+
+        query.general_predicates[self.pattern_index]
+            .iter()
+            .all(|predicate| {
+                let args = predicate
+                    .args
+                    .iter()
+                    .map(|arg| match arg {
+                        QueryPredicateArg::Capture(i) => {
+                            buffer.clear();
+                            if let Some(node) = self.nodes_for_capture_index(*i).next() {
+                                for chunk in text_provider.text(node) {
+                                    buffer.extend_from_slice(chunk.as_ref());
+                                }
+                            }
+                            QueryPredicateArg::String(String::from_utf8_lossy(buffer).into())
+                        }
+                        QueryPredicateArg::String(s) => QueryPredicateArg::String(s.clone()),
+                    })
+                    .collect::<Vec<_>>();
+                handler(&predicate.operator, &args)
+            })
+    }
 }
 
 impl QueryProperty {
@@ -2686,6 +3821,11 @@ impl<'query, 'tree: 'query, T: TextProvider<I>, I: AsRef<[u8]>> Iterator
                         &mut self.buffer1,
                         &mut self.buffer2,
                         &mut self.text_provider,
+                    ) && result.satisfies_general_predicates(
+                        self.query,
+                        self.predicate_handler.as_deref_mut(),
+                        &mut self.buffer1,
+                        &mut self.text_provider,
                     ) {
                         return Some(result);
                     }
@@ -2718,6 +3858,11 @@ impl<'query, 'tree: 'query, T: TextProvider<I>, I: AsRef<[u8]>> Iterator
                         &mut self.buffer1,
                         &mut self.buffer2,
                         &mut self.text_provider,
+                    ) && result.satisfies_general_predicates(
+                        self.query,
+                        self.predicate_handler.as_deref_mut(),
+                        &mut self.buffer1,
+                        &mut self.text_provider,
                     ) {
                         return Some((result, capture_index as usize));
                     }
@@ -2793,6 +3938,30 @@ impl<'a> TextProvider<&'a [u8]> for &'a [u8] {
     }
 }
 
+impl ChunkedText for [u8] {
+    fn chunk(&self, byte_offset: usize) -> &[u8] {
+        self.get(byte_offset..).unwrap_or_default()
+    }
+}
+
+impl ChunkedText for str {
+    fn chunk(&self, byte_offset: usize) -> &[u8] {
+        self.as_bytes().chunk(byte_offset)
+    }
+}
+
+impl ChunkedText for Vec<u8> {
+    fn chunk(&self, byte_offset: usize) -> &[u8] {
+        self.as_slice().chunk(byte_offset)
+    }
+}
+
+impl ChunkedText for String {
+    fn chunk(&self, byte_offset: usize) -> &[u8] {
+        self.as_bytes().chunk(byte_offset)
+    }
+}
+
 impl PartialEq for Query {
     fn eq(&self, other: &Self) -> bool {
         self.ptr == other.ptr
@@ -2818,6 +3987,114 @@ impl Point {
     }
 }
 
+impl Range {
+    /// Sort a list of ranges and merge any that touch or overlap, so that the
+    /// result can be passed to [`Parser::set_included_ranges`].
+    ///
+    /// Ranges are compared by byte offset. A merged range takes its start
+    /// point from the first of its ranges and its end point from the one that
+    /// ends last.
+    pub fn coalesce(ranges: &mut Vec<Self>) {
+        ranges.sort_unstable_by_key(|range| (range.start_byte, range.end_byte));
+        ranges.dedup_by(|range, previous| {
+            if range.start_byte > previous.end_byte {
+                return false;
+            }
+            if range.end_byte > previous.end_byte {
+                previous.end_byte = range.end_byte;
+                previous.end_point = range.end_point;
+            }
+            true
+        });
+    }
+
+    /// Check if this range shares at least one byte with another range.
+    ///
+    /// Ranges are half-open, so ranges that only touch at their ends do not
+    /// intersect.
+    #[must_use]
+    pub const fn intersects(&self, other: &Self) -> bool {
+        self.start_byte < other.end_byte && other.start_byte < self.end_byte
+    }
+
+    /// Check if the given byte offset is within this range. The end offset is
+    /// not included.
+    #[must_use]
+    pub const fn contains_byte(&self, byte: usize) -> bool {
+        self.start_byte <= byte && byte < self.end_byte
+    }
+
+    /// Get the range of bytes that this range shares with another range, or
+    /// `None` if they do not [intersect](Range::intersects).
+    ///
+    /// Each end of the result is taken, along with its point, from whichever
+    /// range bounds it.
+    #[must_use]
+    pub const fn intersection(&self, other: &Self) -> Option<Self> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let (start_byte, start_point) = if self.start_byte >= other.start_byte {
+            (self.start_byte, self.start_point)
+        } else {
+            (other.start_byte, other.start_point)
+        };
+        let (end_byte, end_point) = if self.end_byte <= other.end_byte {
+            (self.end_byte, self.end_point)
+        } else {
+            (other.end_byte, other.end_point)
+        };
+        Some(Self {
+            start_byte,
+            end_byte,
+            start_point,
+            end_point,
+        })
+    }
+}
+
+impl LogEvent {
+    fn parse(log_type: LogType, message: &str, lex_state: &mut u16) -> Self {
+        let event = match log_type {
+            LogType::Lex => message
+                .strip_prefix("consume character:")
+                .and_then(|character| {
+                    let character = match character.strip_prefix('\'') {
+                        Some(quoted) => quoted.strip_suffix('\'')?.parse().ok()?,
+                        None => char::from_u32(character.parse().ok()?)?,
+                    };
+                    Some(Self::Lex {
+                        state: *lex_state,
+                        character,
+                    })
+                }),
+            LogType::Parse => {
+                if let Some(state) = message
+                    .strip_prefix("lex_internal state:")
+                    .or_else(|| message.strip_prefix("lex_external state:"))
+                    .and_then(|rest| rest.split(',').next()?.parse().ok())
+                {
+                    *lex_state = state;
+                }
+                if let Some(state) = message.strip_prefix("shift state:") {
+                    state.parse().ok().map(|state| Self::Shift { state })
+                } else if let Some(rest) = message.strip_prefix("reduce sym:") {
+                    rest.rsplit_once(", child_count:")
+                        .and_then(|(symbol, count)| {
+                            Some(Self::Reduce {
+                                symbol: symbol.to_string(),
+                                count: count.parse().ok()?,
+                            })
+                        })
+                } else {
+                    None
+                }
+            }
+        };
+        event.unwrap_or_else(|| Self::Raw(message.to_string()))
+    }
+}
+
 impl fmt::Display for Point {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "({}, {})", self.row, self.column)
@@ -2925,12 +4202,12 @@ impl<'a> Iterator for LossyUtf8<'a> {
 }
 
 #[must_use]
-const fn predicate_error(row: usize, message: String) -> QueryError {
+const fn predicate_error(position: Point, offset: usize, message: String) -> QueryError {
     QueryError {
         kind: QueryErrorKind::Predicate,
-        row,
-        column: 0,
-        offset: 0,
+        row: position.row,
+        column: position.column,
+        offset,
         message,
     }
 }
@@ -2941,13 +4218,40 @@ impl fmt::Display for IncludedRangesError {
     }
 }
 
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoLanguage => write!(f, "No language was assigned to the parser"),
+            Self::TextTooLong => write!(f, "Text is too long to parse"),
+            Self::Cancelled => write!(f, "Parsing was cancelled"),
+        }
+    }
+}
+
+impl fmt::Display for InvalidRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid range: start is after end")
+    }
+}
+
+impl LanguageError {
+    const fn incompatible_version(got: usize) -> Self {
+        Self::IncompatibleVersion {
+            got,
+            min: MIN_COMPATIBLE_LANGUAGE_VERSION,
+            max: LANGUAGE_VERSION,
+        }
+    }
+}
+
 impl fmt::Display for LanguageError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "Incompatible language version {}. Expected minimum {}, maximum {}",
-            self.version, MIN_COMPATIBLE_LANGUAGE_VERSION, LANGUAGE_VERSION,
-        )
+        match self {
+            Self::IncompatibleVersion { got, min, max } => write!(
+                f,
+                "Incompatible language version {got}. Expected minimum {min}, maximum {max}",
+            ),
+        }
     }
 }
 
@@ -2977,6 +4281,64 @@ impl fmt::Display for QueryError {
     }
 }
 
+/// Compute the [`InputEdit`]s that transform `old` into `new`.
+///
+/// The changed region is found by trimming the longest common prefix and
+/// suffix of the two texts, so the result is empty if they are equal and
+/// otherwise contains a single edit. Pass each edit to [`Tree::edit`] before
+/// reparsing `new` with the edited tree.
+#[must_use]
+pub fn edits_from_diff(old: &str, new: &str) -> Vec<InputEdit> {
+    if old == new {
+        return Vec::new();
+    }
+
+    let mut prefix_len = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(prefix_len) || !new.is_char_boundary(prefix_len) {
+        prefix_len -= 1;
+    }
+
+    let max_suffix_len = old.len().min(new.len()) - prefix_len;
+    let mut suffix_len = old
+        .bytes()
+        .rev()
+        .zip(new.bytes().rev())
+        .take(max_suffix_len)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(old.len() - suffix_len)
+        || !new.is_char_boundary(new.len() - suffix_len)
+    {
+        suffix_len -= 1;
+    }
+
+    let start_position = point_for_offset(old, prefix_len);
+    let old_end_byte = old.len() - suffix_len;
+    let new_end_byte = new.len() - suffix_len;
+    vec![InputEdit {
+        start_byte: prefix_len,
+        old_end_byte,
+        new_end_byte,
+        start_position,
+        old_end_position: point_for_offset(old, old_end_byte),
+        new_end_position: point_for_offset(new, new_end_byte),
+    }]
+}
+
+fn point_for_offset(text: &str, offset: usize) -> Point {
+    let text = &text.as_bytes()[..offset];
+    let row = text.iter().filter(|&&b| b == b'\n').count();
+    let column = text
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(offset, |i| offset - i - 1);
+    Point::new(row, column)
+}
+
 #[doc(hidden)]
 #[must_use]
 pub fn format_sexp(sexp: &str, initial_indent_level: usize) -> String {
@@ -3112,6 +4474,10 @@ pub unsafe fn set_allocator(
 #[cfg(feature = "std")]
 impl error::Error for IncludedRangesError {}
 #[cfg(feature = "std")]
+impl error::Error for ParseError {}
+#[cfg(feature = "std")]
+impl error::Error for InvalidRange {}
+#[cfg(feature = "std")]
 impl error::Error for LanguageError {}
 #[cfg(feature = "std")]
 impl error::Error for QueryError {}
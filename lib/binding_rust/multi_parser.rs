@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use crate::{Language, LanguageError, Parser, Query, QueryCursor, Range, Tree};
+
+/// A parser for documents that embed other languages.
+///
+/// The document is first parsed with a host language. An injection query is
+/// then run on the host tree, and the nodes captured by each capture name are
+/// parsed together, as one tree, with the language assigned to that name.
+pub struct MultiParser {
+    parser: Parser,
+    host_language: Language,
+    injection_query: Query,
+    injection_languages: Vec<Option<Language>>,
+}
+
+/// One language's tree within the result of [`MultiParser::parse`].
+pub struct ParseLayer {
+    /// The syntax tree of this layer's language.
+    pub tree: Tree,
+    /// The ranges of the document that were parsed to produce `tree`, sorted
+    /// and with overlapping captures merged. For the host language, this is
+    /// the whole document.
+    pub included_ranges: Vec<Range>,
+}
+
+impl MultiParser {
+    /// Create a parser for the given host language.
+    ///
+    /// `injection_query` must be written for the host language. Each entry of
+    /// `injection_languages` assigns a language to one of the query's capture
+    /// names. Captures without a language are ignored.
+    pub fn new<'a>(
+        host_language: Language,
+        injection_query: Query,
+        injection_languages: impl IntoIterator<Item = (&'a str, Language)>,
+    ) -> Result<Self, LanguageError> {
+        let mut parser = Parser::new();
+        parser.set_language(&host_language)?;
+
+        let mut languages = vec![None; injection_query.capture_names().len()];
+        for (name, language) in injection_languages {
+            if let Some(index) = injection_query.capture_index_for_name(name) {
+                parser.set_language(&language)?;
+                languages[index as usize] = Some(language);
+            }
+        }
+
+        Ok(Self {
+            parser,
+            host_language,
+            injection_query,
+            injection_languages: languages,
+        })
+    }
+
+    /// Parse a document, returning one layer for the host language and one
+    /// for each injected language that occurs in the document.
+    ///
+    /// Injections of the host language itself are ignored. Returns `None` if
+    /// any of the parses times out or is cancelled.
+    pub fn parse(&mut self, source: &[u8]) -> Option<HashMap<Language, ParseLayer>> {
+        self.parser.set_language(&self.host_language).ok()?;
+        self.parser.clear_included_ranges();
+        let host_tree = self.parser.parse(source, None)?;
+
+        let mut ranges_by_language = HashMap::<Language, Vec<Range>>::new();
+        let mut cursor = QueryCursor::new();
+        for (query_match, capture_index) in
+            cursor.captures(&self.injection_query, host_tree.root_node(), source)
+        {
+            let capture = query_match.captures[capture_index];
+            if let Some(language) = &self.injection_languages[capture.index as usize] {
+                if *language != self.host_language {
+                    ranges_by_language
+                        .entry(language.clone())
+                        .or_default()
+                        .push(capture.node.range());
+                }
+            }
+        }
+
+        let mut layers = HashMap::new();
+        for (language, mut ranges) in ranges_by_language {
+            // Included ranges must be ordered and must not overlap.
+            Range::coalesce(&mut ranges);
+
+            self.parser.set_language(&language).ok()?;
+            self.parser.set_included_ranges(&ranges).ok()?;
+            let tree = self.parser.parse(source, None)?;
+            layers.insert(
+                language,
+                ParseLayer {
+                    tree,
+                    included_ranges: ranges,
+                },
+            );
+        }
+        self.parser.clear_included_ranges();
+
+        layers.insert(
+            self.host_language.clone(),
+            ParseLayer {
+                included_ranges: host_tree.included_ranges(),
+                tree: host_tree,
+            },
+        );
+        Some(layers)
+    }
+}
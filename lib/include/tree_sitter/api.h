@@ -102,6 +102,23 @@ typedef struct TSNode {
   const TSTree *tree;
 } TSNode;
 
+typedef struct TSNodeStringOptions {
+  /**
+   * Write each node's byte range after its name, e.g. `(identifier [4, 7])`.
+   */
+  bool include_ranges;
+  /**
+   * Leave out the missing anonymous tokens that are otherwise written, such
+   * as `(MISSING ";")`, so that the string only contains named nodes.
+   */
+  bool named_only;
+  /**
+   * Elide the named nodes that are nested more than this many levels below
+   * the node, writing each elided group of descendants as `...`.
+   */
+  uint32_t max_depth;
+} TSNodeStringOptions;
+
 typedef struct TSTreeCursor {
   const void *tree;
   const void *id;
@@ -238,7 +255,9 @@ const TSRange *ts_parser_included_ranges(
  *    text and write its length to the [`bytes_read`] pointer. The parser does
  *    not take ownership of this buffer; it just borrows it until it has
  *    finished reading it. The function should write a zero value to the
- *    [`bytes_read`] pointer to indicate the end of the document.
+ *    [`bytes_read`] pointer to indicate the end of the document. The chunks
+ *    can be of any length, and may end in the middle of a multi-byte
+ *    character.
  * 2. [`payload`]: An arbitrary pointer that will be passed to each invocation
  *    of the [`read`] function.
  * 3. [`encoding`]: An indication of how the text is encoded. Either
@@ -321,6 +340,42 @@ void ts_parser_set_timeout_micros(TSParser *self, uint64_t timeout_micros);
  */
 uint64_t ts_parser_timeout_micros(const TSParser *self);
 
+/**
+ * Set the maximum number of parse operations that each call to
+ * [`ts_parser_parse`] should be allowed to perform before halting. A limit of
+ * zero means that there is no limit.
+ *
+ * Unlike a timeout, this halts at the same point every time that the same
+ * input is parsed. If the limit is exceeded, parsing halts early, returning
+ * NULL. See [`ts_parser_parse`] for more information.
+ */
+void ts_parser_set_operation_limit(TSParser *self, uint64_t operation_limit);
+
+/**
+ * Get the maximum number of parse operations that parsing is allowed to
+ * perform.
+ */
+uint64_t ts_parser_operation_limit(const TSParser *self);
+
+/**
+ * Set whether the parser should represent the text after its last included
+ * range in the syntax tree.
+ *
+ * By default, the end of the last included range is treated as the end of the
+ * document, and the syntax tree ends there. If this is enabled, the text after
+ * that range is read to measure it, but not parsed, and it is added to the tree
+ * as a single `ERROR` node at the end of the root node. Together with a single
+ * included range that starts at the beginning of the document, this parses
+ * just a prefix of the document, while the tree still spans all of it.
+ */
+void ts_parser_set_wrap_remainder(TSParser *self, bool wrap_remainder);
+
+/**
+ * Get whether the parser represents the text after its last included range in
+ * the syntax tree.
+ */
+bool ts_parser_wrap_remainder(const TSParser *self);
+
 /**
  * Set the parser's current cancellation flag pointer.
  *
@@ -495,6 +550,30 @@ TSPoint ts_node_end_point(TSNode self);
  */
 char *ts_node_string(TSNode self);
 
+/**
+ * Write the same S-expression as [`ts_node_string`] into the given buffer,
+ * followed by a null character. Like `snprintf`, if the buffer's `size` is
+ * too small, only as much of the S-expression as fits is written, still
+ * followed by a null character unless `size` is zero. The buffer may be null
+ * if `size` is zero.
+ *
+ * Returns the length of the whole S-expression, not including the null
+ * character, so a return value that is greater than or equal to `size` means
+ * that the buffer was too small.
+ */
+size_t ts_node_write_string(TSNode self, char *buffer, size_t size);
+
+/**
+ * Write an S-expression like [`ts_node_write_string`], formatted according to
+ * the given options. The options can be combined.
+ */
+size_t ts_node_write_string_with_options(
+  TSNode self,
+  TSNodeStringOptions options,
+  char *buffer,
+  size_t size
+);
+
 /**
  * Check if the node is null. Functions like [`ts_node_child`] and
  * [`ts_node_next_sibling`] will return a null node to indicate that no such node
@@ -1019,6 +1098,11 @@ void ts_query_cursor_set_point_range(TSQueryCursor *self, TSPoint start_point, T
  * Otherwise, return `false`.
  */
 bool ts_query_cursor_next_match(TSQueryCursor *self, TSQueryMatch *match);
+
+/**
+ * Remove the match with the given id, whether or not it has finished, and
+ * free the capture list that it was using.
+ */
 void ts_query_cursor_remove_match(TSQueryCursor *self, uint32_t match_id);
 
 /**
@@ -4,6 +4,7 @@
 #include "./length.h"
 #include "./unicode.h"
 #include <stdarg.h>
+#include <string.h>
 
 #define LOG(message, character)              \
   if (self->logger.log) {                    \
@@ -96,6 +97,34 @@ static void ts_lexer__get_lookahead(Lexer *self) {
     chunk = (const uint8_t *)self->chunk;
     size = self->chunk_size;
     self->lookahead_size = decode(chunk, size, &self->data.lookahead);
+
+    // If the fresh chunk also ends in the middle of the character, then the
+    // input is being returned in very small chunks. Collect the character's
+    // bytes from the following chunks.
+    if (self->data.lookahead == TS_DECODE_ERROR && size > 0 && size < 4) {
+      uint8_t buffer[4];
+      uint32_t buffer_size = size;
+      memcpy(buffer, chunk, size);
+      while (buffer_size < 4 && self->data.lookahead == TS_DECODE_ERROR) {
+        TSPoint extent = self->current_position.extent;
+        extent.column += buffer_size;
+        uint32_t next_size = 0;
+        const char *next_chunk = self->input.read(
+          self->input.payload,
+          self->current_position.bytes + buffer_size,
+          extent,
+          &next_size
+        );
+        if (!next_size) break;
+        if (next_size > 4 - buffer_size) next_size = 4 - buffer_size;
+        memcpy(buffer + buffer_size, next_chunk, next_size);
+        buffer_size += next_size;
+        self->lookahead_size = decode(buffer, buffer_size, &self->data.lookahead);
+      }
+
+      // The input callback may have invalidated the current chunk.
+      ts_lexer__get_chunk(self);
+    }
   }
 
   if (self->data.lookahead == TS_DECODE_ERROR) {
@@ -396,6 +425,59 @@ void ts_lexer_mark_end(Lexer *self) {
   ts_lexer__mark_end(&self->data);
 }
 
+// Measure the text that follows the end of the last included range, by
+// reading it from the input until the end of the document. The first
+// character of the text is stored in `first_character`.
+Length ts_lexer_measure_remainder(Lexer *self, int32_t *first_character) {
+  const TSRange *last_range = &self->included_ranges[self->included_range_count - 1];
+  Length start = {last_range->end_byte, last_range->end_point};
+  Length position = start;
+  *first_character = 0;
+  if (start.bytes == UINT32_MAX) return length_zero();
+
+  UnicodeDecodeFunction decode = self->input.encoding == TSInputEncodingUTF8
+    ? ts_decode_utf8
+    : ts_decode_utf16;
+
+  for (;;) {
+    uint32_t chunk_size = 0;
+    const uint8_t *chunk = (const uint8_t *)self->input.read(
+      self->input.payload,
+      position.bytes,
+      position.extent,
+      &chunk_size
+    );
+    if (!chunk_size) break;
+
+    uint32_t offset = 0;
+    while (offset < chunk_size) {
+      int32_t character;
+      uint32_t character_size = decode(chunk + offset, chunk_size - offset, &character);
+
+      // If this chunk ended in the middle of a multi-byte character, read
+      // that character again from the start of the next chunk.
+      if (character == TS_DECODE_ERROR) {
+        if (offset > 0 && chunk_size - offset < 4) break;
+        character_size = 1;
+      }
+
+      if (position.bytes == start.bytes) *first_character = character;
+      position.bytes += character_size;
+      if (character == '\n') {
+        position.extent.row++;
+        position.extent.column = 0;
+      } else {
+        position.extent.column += character_size;
+      }
+      offset += character_size;
+    }
+  }
+
+  // The input callback may have invalidated the current chunk.
+  ts_lexer__clear_chunk(self);
+  return length_sub(position, start);
+}
+
 bool ts_lexer_set_included_ranges(
   Lexer *self,
   const TSRange *ranges,
@@ -480,6 +480,35 @@ char *ts_node_string(TSNode self) {
   );
 }
 
+size_t ts_node_write_string(TSNode self, char *buffer, size_t size) {
+  TSNodeStringOptions options = {
+    .include_ranges = false,
+    .named_only = false,
+    .max_depth = UINT32_MAX,
+  };
+  return ts_node_write_string_with_options(self, options, buffer, size);
+}
+
+size_t ts_node_write_string_with_options(
+  TSNode self,
+  TSNodeStringOptions options,
+  char *buffer,
+  size_t size
+) {
+  TSSymbol alias_symbol = ts_node__alias(&self);
+  Subtree subtree = ts_node__subtree(self);
+  return ts_subtree_write_string(
+    subtree,
+    alias_symbol,
+    ts_language_symbol_metadata(self.tree->language, alias_symbol).visible,
+    self.tree->language,
+    &options,
+    ts_node_start_byte(self) - ts_subtree_padding(subtree).bytes,
+    buffer,
+    size
+  );
+}
+
 bool ts_node_eq(TSNode self, TSNode other) {
   return self.tree == other.tree && self.id == other.id;
 }
@@ -110,6 +110,9 @@ struct TSParser {
   TSDuration timeout_duration;
   unsigned accept_count;
   unsigned operation_count;
+  uint64_t operation_limit;
+  bool wrap_remainder;
+  uint64_t total_operation_count;
   const volatile size_t *cancellation_flag;
   Subtree old_tree;
   TSRangeArray included_range_differences;
@@ -756,9 +759,15 @@ static Subtree ts_parser__reuse_node(
     uint32_t byte_offset = reusable_node_byte_offset(&self->reusable_node);
     uint32_t end_byte_offset = byte_offset + ts_subtree_total_bytes(result);
 
-    // Do not reuse an EOF node if the included ranges array has changes
-    // later on in the file.
-    if (ts_subtree_is_eof(result)) end_byte_offset = UINT32_MAX;
+    // Do not reuse a node if the included ranges array has changes in the
+    // text that the node's tokens looked ahead at, such as a node that was
+    // cut off by the end of the included ranges. Do not reuse an EOF node if
+    // the included ranges array has changes later on in the file.
+    uint32_t lookahead_end_byte_offset = end_byte_offset + ts_subtree_lookahead_bytes(result);
+    if (ts_subtree_is_eof(result)) {
+      end_byte_offset = UINT32_MAX;
+      lookahead_end_byte_offset = UINT32_MAX;
+    }
 
     if (byte_offset > position) {
       LOG("before_reusable_node symbol:%s", TREE_NAME(result));
@@ -788,7 +797,7 @@ static Subtree ts_parser__reuse_node(
       reason = "is_missing";
     } else if (ts_subtree_is_fragile(result)) {
       reason = "is_fragile";
-    } else if (ts_parser__has_included_range_difference(self, byte_offset, end_byte_offset)) {
+    } else if (ts_parser__has_included_range_difference(self, byte_offset, lookahead_end_byte_offset)) {
       reason = "contains_different_included_range";
     }
 
@@ -1089,6 +1098,47 @@ static void ts_parser__accept(
   ts_stack_halt(self->stack, version);
 }
 
+// Add the text after the last included range to the finished tree, as a
+// single error leaf at the end of the root node. The leaf is marked as extra,
+// like the end-of-file token before it, so that it doesn't affect the root's
+// fields or aliases.
+static void ts_parser__wrap_remainder(TSParser *self) {
+  int32_t first_character;
+  Length size = ts_lexer_measure_remainder(&self->lexer, &first_character);
+  if (!size.bytes) return;
+  LOG("wrap_remainder size:%u", size.bytes);
+
+  Subtree root = self->finished_tree;
+  uint32_t child_count = ts_subtree_child_count(root);
+  const Subtree *children = ts_subtree_children(root);
+  SubtreeArray trees = array_new();
+  array_reserve(&trees, child_count + 1);
+  for (uint32_t i = 0; i < child_count; i++) {
+    ts_subtree_retain(children[i]);
+    array_push(&trees, children[i]);
+  }
+
+  MutableSubtree remainder = ts_subtree_to_mut_unsafe(ts_subtree_new_error(
+    &self->tree_pool,
+    first_character,
+    length_zero(),
+    size,
+    0,
+    ERROR_STATE,
+    self->language
+  ));
+  ts_subtree_set_extra(&remainder, true);
+  array_push(&trees, ts_subtree_from_mut(remainder));
+
+  self->finished_tree = ts_subtree_from_mut(ts_subtree_new_node(
+    ts_subtree_symbol(root),
+    &trees,
+    root.ptr->production_id,
+    self->language
+  ));
+  ts_subtree_release(&self->tree_pool, root);
+}
+
 static bool ts_parser__do_all_potential_reductions(
   TSParser *self,
   StackVersion starting_version,
@@ -1568,10 +1618,14 @@ static bool ts_parser__advance(
     if (++self->operation_count == OP_COUNT_PER_PARSER_TIMEOUT_CHECK) {
       self->operation_count = 0;
     }
+    // An operation limit is checked on every parse action, so that parsing
+    // always halts at the same point for the same input.
+    self->total_operation_count++;
     if (
-      self->operation_count == 0 &&
-      ((self->cancellation_flag && atomic_load(self->cancellation_flag)) ||
-       (!clock_is_null(self->end_clock) && clock_is_gt(clock_now(), self->end_clock)))
+      (self->operation_limit && self->total_operation_count > self->operation_limit) ||
+      (self->operation_count == 0 &&
+       ((self->cancellation_flag && atomic_load(self->cancellation_flag)) ||
+        (!clock_is_null(self->end_clock) && clock_is_gt(clock_now(), self->end_clock))))
     ) {
       if (lookahead.ptr) {
         ts_subtree_release(&self->tree_pool, lookahead);
@@ -1858,6 +1912,9 @@ TSParser *ts_parser_new(void) {
   self->external_scanner_payload = NULL;
   self->end_clock = clock_null();
   self->operation_count = 0;
+  self->operation_limit = 0;
+  self->wrap_remainder = false;
+  self->total_operation_count = 0;
   self->old_tree = NULL_SUBTREE;
   self->included_range_differences = (TSRangeArray) array_new();
   self->included_range_difference_index = 0;
@@ -1958,6 +2015,22 @@ void ts_parser_set_timeout_micros(TSParser *self, uint64_t timeout_micros) {
   self->timeout_duration = duration_from_micros(timeout_micros);
 }
 
+uint64_t ts_parser_operation_limit(const TSParser *self) {
+  return self->operation_limit;
+}
+
+void ts_parser_set_operation_limit(TSParser *self, uint64_t operation_limit) {
+  self->operation_limit = operation_limit;
+}
+
+bool ts_parser_wrap_remainder(const TSParser *self) {
+  return self->wrap_remainder;
+}
+
+void ts_parser_set_wrap_remainder(TSParser *self, bool wrap_remainder) {
+  self->wrap_remainder = wrap_remainder;
+}
+
 bool ts_parser_set_included_ranges(
   TSParser *self,
   const TSRange *ranges,
@@ -2038,6 +2111,7 @@ TSTree *ts_parser_parse(
   }
 
   self->operation_count = 0;
+  self->total_operation_count = 0;
   if (self->timeout_duration) {
     self->end_clock = clock_after(clock_now(), self->timeout_duration);
   } else {
@@ -2103,6 +2177,7 @@ TSTree *ts_parser_parse(
 
   assert(self->finished_tree.ptr);
   ts_subtree_balance(self->finished_tree, &self->tree_pool, self->language);
+  if (self->wrap_remainder) ts_parser__wrap_remainder(self);
   LOG("done");
   LOG_TREE(self->finished_tree);
 
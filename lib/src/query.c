@@ -2537,6 +2537,7 @@ static TSQueryError ts_query__parse_pattern(
     );
     if (!field_id) {
       stream->input = field_name;
+      capture_quantifiers_delete(&field_capture_quantifiers);
       return TSQueryErrorField;
     }
 
@@ -4154,7 +4155,13 @@ bool ts_query_cursor_next_capture(
       return true;
     }
 
-    if (capture_list_pool_is_empty(&self->capture_list_pool)) {
+    // If the capture lists have run out, then abandon the earliest unfinished
+    // match. There may not be one, if all of the unfinished matches' captures
+    // have already been returned.
+    if (
+      capture_list_pool_is_empty(&self->capture_list_pool) &&
+      first_unfinished_state_index != UINT32_MAX
+    ) {
       LOG(
         "  abandon state. index:%u, pattern:%u, offset:%u.\n",
         first_unfinished_state_index,
@@ -843,21 +843,32 @@ static size_t ts_subtree__write_char_to_string(char *str, size_t n, int32_t chr)
     return snprintf(str, n, "%d", chr);
 }
 
+static size_t ts_subtree__write_range_to_string(char *str, size_t n, Subtree self, uint32_t position) {
+  uint32_t start_byte = position + ts_subtree_padding(self).bytes;
+  uint32_t end_byte = start_byte + ts_subtree_size(self).bytes;
+  return snprintf(str, n, " [%u, %u]", start_byte, end_byte);
+}
+
 static const char *const ROOT_FIELD = "__ROOT__";
 
 static size_t ts_subtree__write_to_string(
   Subtree self, char *string, size_t limit,
   const TSLanguage *language, bool include_all,
+  const TSNodeStringOptions *options, uint32_t position, uint32_t depth_limit,
   TSSymbol alias_symbol, bool alias_is_named, const char *field_name
 ) {
   if (!self.ptr) return snprintf(string, limit, "(NULL)");
 
-  char *cursor = string;
-  char **writer = (limit > 1) ? &cursor : &string;
+  // Each piece of the string is written as far as it fits within the limit,
+  // but its whole length is counted, so that the result is the length of the
+  // whole string even if the buffer is too small.
+  size_t length = 0;
+  #define WRITE_POSITION (length < limit ? string + length : NULL)
+  #define WRITE_LIMIT (length < limit ? limit - length : 0)
   bool is_root = field_name == ROOT_FIELD;
   bool is_visible =
     include_all ||
-    ts_subtree_missing(self) ||
+    (ts_subtree_missing(self) && !options->named_only) ||
     (
       alias_symbol
         ? alias_is_named
@@ -866,42 +877,56 @@ static size_t ts_subtree__write_to_string(
 
   if (is_visible) {
     if (!is_root) {
-      cursor += snprintf(*writer, limit, " ");
+      length += snprintf(WRITE_POSITION, WRITE_LIMIT, " ");
       if (field_name) {
-        cursor += snprintf(*writer, limit, "%s: ", field_name);
+        length += snprintf(WRITE_POSITION, WRITE_LIMIT, "%s: ", field_name);
       }
     }
 
     if (ts_subtree_is_error(self) && ts_subtree_child_count(self) == 0 && self.ptr->size.bytes > 0) {
-      cursor += snprintf(*writer, limit, "(UNEXPECTED ");
-      cursor += ts_subtree__write_char_to_string(*writer, limit, self.ptr->lookahead_char);
+      length += snprintf(WRITE_POSITION, WRITE_LIMIT, "(UNEXPECTED ");
+      length += ts_subtree__write_char_to_string(WRITE_POSITION, WRITE_LIMIT, self.ptr->lookahead_char);
     } else {
       TSSymbol symbol = alias_symbol ? alias_symbol : ts_subtree_symbol(self);
       const char *symbol_name = ts_language_symbol_name(language, symbol);
       if (ts_subtree_missing(self)) {
-        cursor += snprintf(*writer, limit, "(MISSING ");
+        length += snprintf(WRITE_POSITION, WRITE_LIMIT, "(MISSING ");
         if (alias_is_named || ts_subtree_named(self)) {
-          cursor += snprintf(*writer, limit, "%s", symbol_name);
+          length += snprintf(WRITE_POSITION, WRITE_LIMIT, "%s", symbol_name);
         } else {
-          cursor += snprintf(*writer, limit, "\"%s\"", symbol_name);
+          length += snprintf(WRITE_POSITION, WRITE_LIMIT, "\"%s\"", symbol_name);
         }
       } else {
-        cursor += snprintf(*writer, limit, "(%s", symbol_name);
+        length += snprintf(WRITE_POSITION, WRITE_LIMIT, "(%s", symbol_name);
       }
     }
+    if (options->include_ranges) {
+      length += ts_subtree__write_range_to_string(WRITE_POSITION, WRITE_LIMIT, self, position);
+    }
   } else if (is_root) {
     TSSymbol symbol = alias_symbol ? alias_symbol : ts_subtree_symbol(self);
     const char *symbol_name = ts_language_symbol_name(language, symbol);
-    if (ts_subtree_child_count(self) > 0) {
-      cursor += snprintf(*writer, limit, "(%s", symbol_name);
-    } else if (ts_subtree_named(self)) {
-      cursor += snprintf(*writer, limit, "(%s)", symbol_name);
+    if (ts_subtree_child_count(self) > 0 || ts_subtree_named(self)) {
+      length += snprintf(WRITE_POSITION, WRITE_LIMIT, "(%s", symbol_name);
     } else {
-      cursor += snprintf(*writer, limit, "(\"%s\")", symbol_name);
+      length += snprintf(WRITE_POSITION, WRITE_LIMIT, "(\"%s\"", symbol_name);
+    }
+    if (options->include_ranges) {
+      length += ts_subtree__write_range_to_string(WRITE_POSITION, WRITE_LIMIT, self, position);
+    }
+    if (ts_subtree_child_count(self) == 0) {
+      length += snprintf(WRITE_POSITION, WRITE_LIMIT, ")");
     }
   }
 
-  if (ts_subtree_child_count(self)) {
+  // Once the depth limit is reached, elide the node's named descendants
+  // instead of descending any further.
+  if ((is_visible || is_root) && depth_limit == 0) {
+    if (ts_subtree_child_count(self) && self.ptr->named_child_count > 0) {
+      length += snprintf(WRITE_POSITION, WRITE_LIMIT, " ...");
+    }
+  } else if (ts_subtree_child_count(self)) {
+    uint32_t child_depth_limit = (is_visible || is_root) ? depth_limit - 1 : depth_limit;
     const TSSymbol *alias_sequence = ts_language_alias_sequence(language, self.ptr->production_id);
     const TSFieldMapEntry *field_map, *field_map_end;
     ts_language_field_map(
@@ -912,12 +937,14 @@ static size_t ts_subtree__write_to_string(
     );
 
     uint32_t structural_child_index = 0;
+    uint32_t child_position = position;
     for (uint32_t i = 0; i < self.ptr->child_count; i++) {
       Subtree child = ts_subtree_children(self)[i];
       if (ts_subtree_extra(child)) {
-        cursor += ts_subtree__write_to_string(
-          child, *writer, limit,
+        length += ts_subtree__write_to_string(
+          child, WRITE_POSITION, WRITE_LIMIT,
           language, include_all,
+          options, child_position, child_depth_limit,
           0, false, NULL
         );
       } else {
@@ -936,19 +963,23 @@ static size_t ts_subtree__write_to_string(
           }
         }
 
-        cursor += ts_subtree__write_to_string(
-          child, *writer, limit,
+        length += ts_subtree__write_to_string(
+          child, WRITE_POSITION, WRITE_LIMIT,
           language, include_all,
+          options, child_position, child_depth_limit,
           subtree_alias_symbol, subtree_alias_is_named, child_field_name
         );
         structural_child_index++;
       }
+      child_position += ts_subtree_total_bytes(child);
     }
   }
 
-  if (is_visible) cursor += snprintf(*writer, limit, ")");
+  if (is_visible) length += snprintf(WRITE_POSITION, WRITE_LIMIT, ")");
 
-  return cursor - string;
+  #undef WRITE_POSITION
+  #undef WRITE_LIMIT
+  return length;
 }
 
 char *ts_subtree_string(
@@ -958,21 +989,45 @@ char *ts_subtree_string(
   const TSLanguage *language,
   bool include_all
 ) {
-  char scratch_string[1];
+  TSNodeStringOptions options = {
+    .include_ranges = false,
+    .named_only = false,
+    .max_depth = UINT32_MAX,
+  };
   size_t size = ts_subtree__write_to_string(
-    self, scratch_string, 1,
+    self, NULL, 0,
     language, include_all,
+    &options, 0, options.max_depth,
     alias_symbol, alias_is_named, ROOT_FIELD
   ) + 1;
   char *result = ts_malloc(size * sizeof(char));
   ts_subtree__write_to_string(
     self, result, size,
     language, include_all,
+    &options, 0, options.max_depth,
     alias_symbol, alias_is_named, ROOT_FIELD
   );
   return result;
 }
 
+size_t ts_subtree_write_string(
+  Subtree self,
+  TSSymbol alias_symbol,
+  bool alias_is_named,
+  const TSLanguage *language,
+  const TSNodeStringOptions *options,
+  uint32_t position,
+  char *buffer,
+  size_t size
+) {
+  return ts_subtree__write_to_string(
+    self, buffer, size,
+    language, false,
+    options, position, options->max_depth,
+    alias_symbol, alias_is_named, ROOT_FIELD
+  );
+}
+
 void ts_subtree__print_dot_graph(const Subtree *self, uint32_t start_offset,
                                  const TSLanguage *language, TSSymbol alias_symbol,
                                  FILE *f) {
@@ -207,6 +207,7 @@ void ts_subtree_summarize_children(MutableSubtree, const TSLanguage *);
 void ts_subtree_balance(Subtree, SubtreePool *, const TSLanguage *);
 Subtree ts_subtree_edit(Subtree, const TSInputEdit *edit, SubtreePool *);
 char *ts_subtree_string(Subtree, TSSymbol, bool, const TSLanguage *, bool include_all);
+size_t ts_subtree_write_string(Subtree, TSSymbol, bool, const TSLanguage *, const TSNodeStringOptions *, uint32_t position, char *buffer, size_t size);
 void ts_subtree_print_dot_graph(Subtree, const TSLanguage *, FILE *);
 Subtree ts_subtree_last_external_token(Subtree);
 const ExternalScannerState *ts_subtree_external_scanner_state(Subtree self);
@@ -41,6 +41,18 @@ pub struct TagsConfiguration {
     pattern_info: Vec<PatternInfo>,
 }
 
+/// Whether a tag marks the definition of a name, or a reference to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+    Definition,
+    Reference,
+}
+
+const DEFAULT_CAPTURE_KINDS: &[(&str, TagKind)] = &[
+    ("definition.", TagKind::Definition),
+    ("reference.", TagKind::Reference),
+];
+
 #[derive(Debug)]
 pub struct NamedCapture {
     pub syntax_type_id: u32,
@@ -60,6 +72,7 @@ pub struct Tag {
     pub span: Range<Point>,
     pub utf16_column_range: Range<usize>,
     pub docs: Option<String>,
+    pub docs_range: Option<Range<usize>>,
     pub is_definition: bool,
     pub syntax_type_id: u32,
 }
@@ -122,6 +135,32 @@ struct LineInfo {
 
 impl TagsConfiguration {
     pub fn new(language: Language, tags_query: &str, locals_query: &str) -> Result<Self, Error> {
+        Self::build(language, tags_query, locals_query, None)
+    }
+
+    /// Like [`new`](TagsConfiguration::new), but also recognize tag captures
+    /// whose names start with one of the given prefixes.
+    ///
+    /// A capture named `<prefix><syntax type>` produces tags of the given
+    /// kind, with the rest of the name as their syntax type. The prefixes are
+    /// checked in order, before the standard `definition.` and `reference.`
+    /// prefixes. Captures that don't match any prefix, and aren't otherwise
+    /// used by the tags engine, are ignored.
+    pub fn with_capture_kinds(
+        language: Language,
+        tags_query: &str,
+        locals_query: &str,
+        capture_kinds: &[(&str, TagKind)],
+    ) -> Result<Self, Error> {
+        Self::build(language, tags_query, locals_query, Some(capture_kinds))
+    }
+
+    fn build(
+        language: Language,
+        tags_query: &str,
+        locals_query: &str,
+        capture_kinds: Option<&[(&str, TagKind)]>,
+    ) -> Result<Self, Error> {
         let query = Query::new(&language, &format!("{locals_query}{tags_query}"))?;
 
         let tags_query_offset = locals_query.len();
@@ -149,16 +188,19 @@ impl TagsConfiguration {
                 "local.definition" => local_definition_capture_index = Some(i as u32),
                 "local.reference" | "" => continue,
                 _ => {
-                    let mut is_definition = false;
-
-                    let kind = if name.starts_with("definition.") {
-                        is_definition = true;
-                        name.trim_start_matches("definition.")
-                    } else if name.starts_with("reference.") {
-                        name.trim_start_matches("reference.")
-                    } else {
+                    let Some((prefix, tag_kind)) = capture_kinds
+                        .unwrap_or_default()
+                        .iter()
+                        .chain(DEFAULT_CAPTURE_KINDS)
+                        .find(|(prefix, _)| name.starts_with(prefix))
+                    else {
+                        if capture_kinds.is_some() {
+                            continue;
+                        }
                         return Err(Error::InvalidCapture((*name).to_string()));
                     };
+                    let is_definition = *tag_kind == TagKind::Definition;
+                    let kind = &name[prefix.len()..];
 
                     if let Ok(cstr) = CString::new(kind) {
                         let c_kind = cstr.to_bytes_with_nul().to_vec().into_boxed_slice();
@@ -273,6 +315,11 @@ impl TagsContext {
         &mut self.parser
     }
 
+    /// Parse the given source code and return an iterator over its tags,
+    /// along with a flag that is `true` if the syntax tree contains errors.
+    ///
+    /// Tags are computed lazily as the iterator is advanced, and are yielded
+    /// in the order that their names appear in the source code.
     pub fn generate_tags<'a>(
         &'a mut self,
         config: &'a TagsConfiguration,
@@ -465,6 +512,7 @@ where
                         // Generate a doc string from all of the doc nodes, applying any strip
                         // regexes.
                         let mut docs = None;
+                        let mut docs_range: Option<Range<usize>> = None;
                         for doc_node in &doc_nodes[docs_start_index..] {
                             if let Ok(content) = str::from_utf8(&self.source[doc_node.byte_range()])
                             {
@@ -479,6 +527,9 @@ where
                                         d.push_str(&content);
                                     }
                                 }
+                                let range = doc_node.byte_range();
+                                docs_range =
+                                    Some(docs_range.map_or(range.start, |r| r.start)..range.end);
                             }
                         }
 
@@ -532,6 +583,7 @@ where
                             span,
                             utf16_column_range,
                             docs,
+                            docs_range,
                             is_definition,
                             syntax_type_id,
                         };
@@ -578,6 +630,7 @@ impl Tag {
             utf16_column_range: 0..0,
             range: usize::MAX..usize::MAX,
             docs: None,
+            docs_range: None,
             is_definition: false,
             syntax_type_id: 0,
         }
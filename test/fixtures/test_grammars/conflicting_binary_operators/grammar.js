@@ -0,0 +1,25 @@
+module.exports = grammar({
+    name: 'conflicting_binary_operators',
+
+    rules: {
+        expression: $ => choice(
+            $.sum,
+            $.product,
+            $.identifier
+        ),
+
+        sum: $ => prec.left(seq(
+            $.expression,
+            '+',
+            $.expression,
+        )),
+
+        product: $ => seq(
+            $.expression,
+            '*',
+            $.expression,
+        ),
+
+        identifier: $ => /[a-z]+/,
+    }
+});
@@ -0,0 +1,10 @@
+module.exports = grammar({
+  name: 'external_tokens_cpp_scanner',
+
+  extras: $ => [/\s/],
+  externals: $ => [$.word],
+
+  rules: {
+    document: $ => repeat($.word),
+  }
+});
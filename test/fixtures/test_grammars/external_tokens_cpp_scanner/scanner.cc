@@ -0,0 +1,60 @@
+#include "tree_sitter/parser.h"
+
+#include <cwctype>
+
+namespace {
+
+enum TokenType {
+  WORD
+};
+
+struct Scanner {
+  bool scan(TSLexer *lexer) {
+    while (std::iswspace(lexer->lookahead)) {
+      lexer->advance(lexer, true);
+    }
+
+    bool has_content = false;
+    while (std::iswalpha(lexer->lookahead)) {
+      lexer->advance(lexer, false);
+      has_content = true;
+    }
+
+    lexer->result_symbol = WORD;
+    return has_content;
+  }
+};
+
+} // namespace
+
+extern "C" {
+
+void *tree_sitter_external_tokens_cpp_scanner_external_scanner_create() {
+  static Scanner scanner;
+  return &scanner;
+}
+
+bool tree_sitter_external_tokens_cpp_scanner_external_scanner_scan(
+  void *payload,
+  TSLexer *lexer,
+  const bool *valid_symbols
+) {
+  return static_cast<Scanner *>(payload)->scan(lexer);
+}
+
+unsigned tree_sitter_external_tokens_cpp_scanner_external_scanner_serialize(
+  void *payload,
+  char *buffer
+) {
+  return 0;
+}
+
+void tree_sitter_external_tokens_cpp_scanner_external_scanner_deserialize(
+  void *payload,
+  const char *buffer,
+  unsigned length
+) {}
+
+void tree_sitter_external_tokens_cpp_scanner_external_scanner_destroy(void *payload) {}
+
+}